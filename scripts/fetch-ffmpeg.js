@@ -0,0 +1,121 @@
+#!/usr/bin/env node
+/**
+ * Fetch a static ffmpeg build into src-tauri/bin so it's packaged as a Tauri resource.
+ * Used for transcode-on-cache, cover art thumbnails and tagging on library export.
+ * Idempotent: skips download if binary already exists and --force not passed.
+ */
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+const https = require('https');
+const { execFileSync } = require('child_process');
+const AdmZip = require('adm-zip');
+
+const force = process.argv.includes('--force');
+const platform = process.platform; // win32, darwin, linux
+const arch = process.arch; // x64, arm64
+const binDir = path.join(__dirname, '..', 'src-tauri', 'bin');
+if (!fs.existsSync(binDir)) fs.mkdirSync(binDir, { recursive: true });
+
+// Static GPL builds; BtbN for Windows/Linux, evermeet.cx for macOS
+const BTBN = 'https://github.com/BtbN/FFmpeg-Builds/releases/download/latest';
+const FFMPEG_CONFIG = {
+  win32: {
+    name: 'ffmpeg.exe',
+    url: `${BTBN}/ffmpeg-master-latest-win64-gpl.zip`,
+    archive: 'zip'
+  },
+  linux: {
+    name: 'ffmpeg',
+    url: `${BTBN}/ffmpeg-master-latest-${arch === 'arm64' ? 'linuxarm64' : 'linux64'}-gpl.tar.xz`,
+    archive: 'tar.xz'
+  },
+  darwin: {
+    name: 'ffmpeg',
+    url: 'https://evermeet.cx/ffmpeg/getrelease/zip',
+    archive: 'zip'
+  }
+};
+
+const config = FFMPEG_CONFIG[platform];
+if (!config) {
+  console.error(`[fetch-ffmpeg] Unsupported platform: ${platform}`);
+  process.exit(1);
+}
+
+const dest = path.join(binDir, config.name);
+
+if (fs.existsSync(dest) && !force) {
+  const size = fs.statSync(dest).size;
+  if (size > 1000000) { // >1MB assume valid
+    console.log('[fetch-ffmpeg] existing binary present, skipping. Use --force to re-download.');
+    process.exit(0);
+  }
+}
+
+function download(url) {
+  return new Promise((resolve, reject) => {
+    https.get(url, res => {
+      if (res.statusCode && res.statusCode >= 300 && res.statusCode < 400 && res.headers.location) {
+        // follow redirect (may be relative on evermeet.cx)
+        return resolve(download(new URL(res.headers.location, url).toString()));
+      }
+      if (res.statusCode !== 200) {
+        return reject(new Error('HTTP ' + res.statusCode));
+      }
+      const bufs = [];
+      res.on('data', d => bufs.push(d));
+      res.on('end', () => resolve(Buffer.concat(bufs)));
+    }).on('error', reject);
+  });
+}
+
+// Pull `config.name` out of the archive, wherever it sits (BtbN nests it under <build>/bin/)
+function extract(buf) {
+  if (config.archive === 'zip') {
+    const entry = new AdmZip(buf)
+      .getEntries()
+      .find(e => !e.isDirectory && path.basename(e.entryName) === config.name);
+    if (!entry) throw new Error(`${config.name} not found in archive`);
+    fs.writeFileSync(dest, entry.getData());
+    return;
+  }
+  // No xz support in adm-zip; the system tar handles it on Linux
+  const tmpDir = fs.mkdtempSync(path.join(os.tmpdir(), 'freely-ffmpeg-'));
+  try {
+    const archivePath = path.join(tmpDir, 'ffmpeg.tar.xz');
+    fs.writeFileSync(archivePath, buf);
+    execFileSync('tar', ['-xJf', archivePath, '-C', tmpDir]);
+    const found = findFile(tmpDir, config.name);
+    if (!found) throw new Error(`${config.name} not found in archive`);
+    fs.copyFileSync(found, dest);
+  } finally {
+    fs.rmSync(tmpDir, { recursive: true, force: true });
+  }
+}
+
+function findFile(dir, name) {
+  for (const entry of fs.readdirSync(dir, { withFileTypes: true })) {
+    const p = path.join(dir, entry.name);
+    if (entry.isDirectory()) {
+      const found = findFile(p, name);
+      if (found) return found;
+    } else if (entry.name === name) {
+      return p;
+    }
+  }
+  return null;
+}
+
+console.log('[fetch-ffmpeg] downloading', config.url);
+download(config.url)
+  .then(buf => {
+    console.log('[fetch-ffmpeg] downloaded', (buf.length / 1024 / 1024).toFixed(1), 'MB');
+    extract(buf);
+    try { if (platform !== 'win32') fs.chmodSync(dest, 0o755); } catch(_){}
+    console.log('[fetch-ffmpeg] installed to', dest);
+  })
+  .catch(err => {
+    // Not fatal for the build: the app falls back to ffmpeg on PATH and reports when it's missing
+    console.error('[fetch-ffmpeg] failed', err && err.message ? err.message : err);
+  });
@@ -150,12 +150,16 @@ impl AudioSettings {
         bass_set_config(lib, BASS_CONFIG_NET_TIMEOUT, self.net_timeout_ms);
         bass_set_config(lib, BASS_CONFIG_NET_BUFFER, self.net_buffer_ms);
         
-        // Set HTTP User-Agent for YouTube compatibility
-        let user_agent = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36\0";
-        bass_set_config_ptr(lib, BASS_CONFIG_NET_AGENT, user_agent.as_ptr());
+        apply_user_agent(lib);
     }
 }
 
+/// Give BASS network streams the same User-Agent as the HTTP clients (YouTube checks it)
+pub fn apply_user_agent(lib: &Library) {
+    let user_agent = format!("{}\0", crate::http::get_http_settings().user_agent);
+    bass_set_config_ptr(lib, BASS_CONFIG_NET_AGENT, user_agent.as_ptr());
+}
+
 // Global audio settings instance
 static AUDIO_SETTINGS: Lazy<Mutex<AudioSettings>> = Lazy::new(|| {
     Mutex::new(AudioSettings::load())
@@ -0,0 +1,417 @@
+use chrono::{Datelike, Local, NaiveTime, Timelike};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// How often the schedule is re-evaluated for the torrent session
+const SCHEDULE_TICK: Duration = Duration::from_secs(30);
+// How often a download blocked by a "pause" window re-checks the schedule
+const PAUSE_RECHECK: Duration = Duration::from_secs(5);
+// The torrent session has no global pause; paused windows cap it at this rate instead
+const TORRENT_PAUSED_BPS: u64 = 1024;
+
+/// Traffic class a download is limited under
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SourceClass {
+    Http,
+    Youtube,
+    Torrent,
+}
+
+impl SourceClass {
+    pub fn of(source_type: &str) -> Self {
+        match source_type {
+            "youtube" => SourceClass::Youtube,
+            "torrent" => SourceClass::Torrent,
+            _ => SourceClass::Http,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleAction {
+    /// Hold downloads until the window ends
+    Pause,
+    /// Cap the global rate at `limit_kbps` while the window is active
+    Throttle,
+}
+
+/// A daily time window, in local time. `end` before `start` wraps past midnight.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScheduleWindow {
+    pub start: String, // "HH:MM"
+    pub end: String,   // "HH:MM"
+    // Weekdays the window applies to (0 = Monday ... 6 = Sunday); empty means every day
+    pub days: Vec<u32>,
+    pub action: ScheduleAction,
+    pub limit_kbps: u32,
+}
+
+impl Default for ScheduleWindow {
+    fn default() -> Self {
+        Self {
+            start: "09:00".to_string(),
+            end: "18:00".to_string(),
+            days: Vec::new(),
+            action: ScheduleAction::Throttle,
+            limit_kbps: 256,
+        }
+    }
+}
+
+impl ScheduleWindow {
+    fn is_active(&self, now: chrono::DateTime<Local>) -> bool {
+        let (start, end) = match (parse_hhmm(&self.start), parse_hhmm(&self.end)) {
+            (Some(s), Some(e)) => (s, e),
+            _ => return false,
+        };
+        let t = NaiveTime::from_hms_opt(now.hour(), now.minute(), 0).unwrap_or_default();
+        let today = now.weekday().num_days_from_monday();
+        let applies = |day: u32| self.days.is_empty() || self.days.contains(&day);
+        if start <= end {
+            applies(today) && t >= start && t < end
+        } else if t >= start {
+            applies(today)
+        } else {
+            // Early-morning tail of a window that started yesterday
+            applies((today + 6) % 7) && t < end
+        }
+    }
+}
+
+fn parse_hhmm(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+}
+
+// Bandwidth settings with persistence support (same storage location as audio settings).
+// Rates are KiB/s; 0 means unlimited.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BandwidthSettings {
+    pub global_kbps: u32,
+    pub http_kbps: u32,
+    pub youtube_kbps: u32,
+    pub torrent_kbps: u32,
+    pub schedule_enabled: bool,
+    pub schedule: Vec<ScheduleWindow>,
+}
+
+impl BandwidthSettings {
+    /// Load bandwidth settings from disk, using defaults if file doesn't exist
+    pub fn load() -> Self {
+        match Self::get_settings_path() {
+            Ok(path) => {
+                if path.exists() {
+                    match std::fs::read_to_string(&path) {
+                        Ok(content) => match serde_json::from_str::<BandwidthSettings>(&content) {
+                            Ok(settings) => {
+                                println!("[bandwidth] Loaded settings from: {}", path.display());
+                                return settings;
+                            }
+                            Err(e) => {
+                                println!(
+                                    "[bandwidth] Failed to parse settings: {}, using defaults",
+                                    e
+                                );
+                            }
+                        },
+                        Err(e) => {
+                            println!("[bandwidth] Failed to read settings: {}, using defaults", e);
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                println!(
+                    "[bandwidth] Failed to get settings path: {}, using defaults",
+                    e
+                );
+            }
+        }
+        Self::default()
+    }
+
+    /// Save bandwidth settings to disk
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::get_settings_path()
+            .map_err(|e| format!("Failed to get bandwidth settings path: {}", e))?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+        }
+
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize bandwidth settings: {}", e))?;
+
+        std::fs::write(&path, content)
+            .map_err(|e| format!("Failed to write bandwidth settings: {}", e))?;
+
+        println!("[bandwidth] Saved settings to: {}", path.display());
+        Ok(())
+    }
+
+    fn get_settings_path() -> Result<PathBuf, String> {
+        let data_dir = dirs::data_dir().ok_or("Failed to get system data directory")?;
+
+        let mut path = data_dir;
+        path.push("com.freely.player");
+        path.push("bandwidth_settings.json");
+        Ok(path)
+    }
+
+    /// Reject windows with unparseable times so a typo can't silently disable the schedule
+    pub fn validate(&self) -> Result<(), String> {
+        for w in &self.schedule {
+            for t in [&w.start, &w.end] {
+                if parse_hhmm(t).is_none() {
+                    return Err(format!("Invalid schedule time '{}', expected HH:MM", t));
+                }
+            }
+            if let Some(day) = w.days.iter().find(|d| **d > 6) {
+                return Err(format!("Invalid schedule weekday {}, expected 0-6", day));
+            }
+        }
+        Ok(())
+    }
+
+    fn class_kbps(&self, class: SourceClass) -> u32 {
+        match class {
+            SourceClass::Http => self.http_kbps,
+            SourceClass::Youtube => self.youtube_kbps,
+            SourceClass::Torrent => self.torrent_kbps,
+        }
+    }
+}
+
+// Global bandwidth settings instance
+static SETTINGS: Lazy<Mutex<BandwidthSettings>> =
+    Lazy::new(|| Mutex::new(BandwidthSettings::load()));
+
+/// Get a snapshot of the current bandwidth settings
+pub fn get_bandwidth_settings() -> BandwidthSettings {
+    SETTINGS.lock().unwrap().clone()
+}
+
+/// Update bandwidth settings, save them and apply them to the torrent session
+pub fn update_bandwidth_settings<F>(updater: F) -> Result<BandwidthSettings, String>
+where
+    F: FnOnce(&mut BandwidthSettings),
+{
+    let updated = {
+        let mut settings = SETTINGS.lock().unwrap();
+        let mut next = settings.clone();
+        updater(&mut next);
+        next.validate()?;
+        next.save()?;
+        *settings = next;
+        settings.clone()
+    };
+    apply_torrent_limit();
+    Ok(updated)
+}
+
+/// Effective limits for one traffic class at this moment
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LimiterState {
+    // Shared cap across all HTTP-style downloads (schedule applied), bytes/s
+    pub global_bps: Option<u64>,
+    // Cap for this traffic class, bytes/s
+    pub class_bps: Option<u64>,
+    pub paused: bool,
+    // Index of the schedule window currently in effect
+    pub window: Option<usize>,
+}
+
+impl LimiterState {
+    fn effective_bps(&self) -> Option<u64> {
+        match (self.global_bps, self.class_bps) {
+            (Some(g), Some(c)) => Some(g.min(c)),
+            (g, c) => g.or(c),
+        }
+    }
+}
+
+fn kbps_to_bps(kbps: u32) -> Option<u64> {
+    if kbps == 0 {
+        None
+    } else {
+        Some(kbps as u64 * 1024)
+    }
+}
+
+/// Current limits for a source type, with the time-of-day schedule applied
+pub fn limiter_state(source_type: &str) -> LimiterState {
+    let settings = SETTINGS.lock().unwrap();
+    let class = SourceClass::of(source_type);
+    let mut state = LimiterState {
+        global_bps: kbps_to_bps(settings.global_kbps),
+        class_bps: kbps_to_bps(settings.class_kbps(class)),
+        paused: false,
+        window: None,
+    };
+    if settings.schedule_enabled {
+        let now = Local::now();
+        if let Some(idx) = settings.schedule.iter().position(|w| w.is_active(now)) {
+            let w = &settings.schedule[idx];
+            state.window = Some(idx);
+            match w.action {
+                ScheduleAction::Pause => state.paused = true,
+                ScheduleAction::Throttle => {
+                    if let Some(cap) = kbps_to_bps(w.limit_kbps) {
+                        state.global_bps = Some(state.global_bps.map_or(cap, |g| g.min(cap)));
+                    }
+                }
+            }
+        }
+    }
+    state
+}
+
+// Token bucket allowing up to one second of burst. Tokens may go negative; the
+// debt is paid back by sleeping, which keeps large chunks from overshooting.
+struct Bucket {
+    rate: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl Bucket {
+    fn new(rate: u64) -> Self {
+        Self {
+            rate: rate as f64,
+            tokens: rate as f64,
+            last: Instant::now(),
+        }
+    }
+
+    /// Take `bytes` and return how long the caller must wait to stay under the rate
+    fn reserve(&mut self, rate: u64, bytes: usize) -> Duration {
+        let now = Instant::now();
+        if self.rate != rate as f64 {
+            self.rate = rate as f64;
+            self.tokens = self.tokens.min(self.rate);
+        }
+        self.tokens =
+            (self.tokens + now.duration_since(self.last).as_secs_f64() * self.rate).min(self.rate);
+        self.last = now;
+        self.tokens -= bytes as f64;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+// None = the global bucket; Some(class) = per-class buckets
+static BUCKETS: Lazy<Mutex<HashMap<Option<SourceClass>, Bucket>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn reserve(key: Option<SourceClass>, rate: Option<u64>, bytes: usize) -> Duration {
+    let mut buckets = BUCKETS.lock().unwrap();
+    match rate {
+        Some(rate) => buckets
+            .entry(key)
+            .or_insert_with(|| Bucket::new(rate))
+            .reserve(rate, bytes),
+        None => {
+            // Unlimited; drop the bucket so a later limit starts from a full burst
+            buckets.remove(&key);
+            Duration::ZERO
+        }
+    }
+}
+
+/// Account for `bytes` just received by a download of `source_type`, sleeping as needed to
+/// respect the global and per-class limits. While a "pause" window is active this waits
+/// until it ends, returning early if the download `base` gets cancelled.
+pub async fn throttle(source_type: &str, base: &str, bytes: usize) {
+    let class = SourceClass::of(source_type);
+    loop {
+        let state = limiter_state(source_type);
+        if !state.paused {
+            let wait = reserve(None, state.global_bps, bytes).max(reserve(
+                Some(class),
+                state.class_bps,
+                bytes,
+            ));
+            if !wait.is_zero() {
+                tokio::time::sleep(wait).await;
+            }
+            return;
+        }
+        if crate::downloads::is_cancelled(base) {
+            return;
+        }
+        tokio::time::sleep(PAUSE_RECHECK).await;
+    }
+}
+
+/// Push the current torrent limit (global, torrent class and schedule combined) to the engine
+pub fn apply_torrent_limit() {
+    static LAST_APPLIED: Lazy<Mutex<Option<Option<u64>>>> = Lazy::new(|| Mutex::new(None));
+    let state = limiter_state("torrent");
+    let limit = if state.paused {
+        Some(TORRENT_PAUSED_BPS)
+    } else {
+        state.effective_bps()
+    };
+    {
+        let mut last = LAST_APPLIED.lock().unwrap();
+        if *last == Some(limit) {
+            return;
+        }
+        *last = Some(limit);
+    }
+    // The engine may block while creating its session; keep that off the caller's thread
+    std::thread::spawn(
+        move || match crate::torrents::get_engine().set_download_limit(limit) {
+            Ok(()) => println!("[bandwidth] Torrent download limit: {:?} B/s", limit),
+            Err(e) => println!("[bandwidth] Could not apply torrent limit: {}", e),
+        },
+    );
+}
+
+/// Re-evaluate the schedule periodically so window boundaries reach the torrent session
+pub fn start_schedule_loop() {
+    tauri::async_runtime::spawn(async {
+        loop {
+            apply_torrent_limit();
+            tokio::time::sleep(SCHEDULE_TICK).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn bandwidth_get_settings() -> Result<serde_json::Value, String> {
+    serde_json::to_value(get_bandwidth_settings())
+        .map_err(|e| format!("Failed to serialize bandwidth settings: {}", e))
+}
+
+#[tauri::command]
+pub async fn bandwidth_set_settings(
+    settings: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let incoming: BandwidthSettings = serde_json::from_value(settings)
+        .map_err(|e| format!("Invalid bandwidth settings: {}", e))?;
+    let updated = update_bandwidth_settings(|s| *s = incoming)?;
+    serde_json::to_value(updated)
+        .map_err(|e| format!("Failed to serialize bandwidth settings: {}", e))
+}
+
+/// Limits in effect right now for each traffic class
+#[tauri::command]
+pub async fn bandwidth_get_state() -> Result<serde_json::Value, String> {
+    Ok(serde_json::json!({
+        "http": limiter_state("http"),
+        "youtube": limiter_state("youtube"),
+        "torrent": limiter_state("torrent"),
+    }))
+}
@@ -33,6 +33,12 @@ pub type BassStreamCreateUrl = unsafe extern "system" fn(
     proc_: Option<DownloadProc>,
     user: *mut std::ffi::c_void,
 ) -> u32;
+pub type BassStreamCreateFileUser = unsafe extern "system" fn(
+    system: c_uint,
+    flags: c_uint,
+    procs: *const BassFileProcs,
+    user: *mut std::ffi::c_void,
+) -> u32;
 pub type BassStreamFree = unsafe extern "system" fn(handle: u32) -> c_int;
 pub type BassChannelPlay = unsafe extern "system" fn(handle: u32, restart: c_int) -> c_int;
 pub type BassChannelPause = unsafe extern "system" fn(handle: u32) -> c_int;
@@ -67,8 +73,12 @@ pub const BASS_ERROR_CREATE: c_int = 5;
 pub const BASS_ERROR_FILEOPEN: c_int = 2;
 
 pub const BASS_DEVICE_DEFAULT: c_int = -1;
+// BASS_StreamCreateFileUser file systems
+pub const STREAMFILE_NOBUFFER: c_uint = 0;
+pub const STREAMFILE_BUFFER: c_uint = 1;
 pub const BASS_CONFIG_NET_TIMEOUT: c_uint = 11;
 pub const BASS_CONFIG_NET_AGENT: c_uint = 16;
+pub const BASS_CONFIG_NET_PROXY: c_uint = 17;
 pub const BASS_CONFIG_NET_BUFFER: c_uint = 10;
 // General device buffer length (ms) used prior to BASS_Init
 pub const BASS_CONFIG_BUFFER: c_uint = 0;
@@ -78,6 +88,7 @@ pub const BASS_STREAM_STATUS: c_uint = 0x800000;
 pub const BASS_STREAM_AUTOFREE: c_uint = 0x40000;
 pub const BASS_STREAM_PRESCAN: c_uint = 0x200000;
 pub const BASS_STREAM_RESTRATE: c_uint = 0x80000;
+pub const BASS_STREAM_DECODE: c_uint = 0x200000; // decode only, never played (tag/duration probes)
 
 pub const BASS_POS_BYTE: c_uint = 0;
 pub const BASS_ACTIVE_STOPPED: c_uint = 0;
@@ -123,6 +134,7 @@ pub const BASS_TAG_VENDOR: c_uint = 9; // OGG encoder : UTF-8 string
 pub const BASS_TAG_CA_CODEC: c_uint = 11; // CoreAudio codec info : TAG_CA_CODEC structure
 pub const BASS_TAG_MF: c_uint = 13; // Media Foundation tags : series of null-terminated UTF-8 strings
 pub const BASS_TAG_WAVEFORMAT: c_uint = 14; // WAVE format : WAVEFORMATEX structure
+pub const BASS_TAG_FLAC_PICTURE: c_uint = 0x12000; // + index #, FLAC picture : TAG_FLAC_PICTURE structure (bassflac)
 
 // Structure definitions
 
@@ -227,6 +239,21 @@ pub struct BassChannelInfo {
     pub filename: *const c_char, // filename (NULL=live stream)
 }
 
+// TAG_FLAC_PICTURE structure returned for BASS_TAG_FLAC_PICTURE + index
+#[repr(C)]
+#[derive(Debug)]
+pub struct TagFlacPicture {
+    pub apic: c_uint,         // ID3v2 "APIC" picture type
+    pub mime: *const c_char,  // mime type
+    pub desc: *const c_char,  // description
+    pub width: c_uint,
+    pub height: c_uint,
+    pub depth: c_uint,
+    pub colors: c_uint,
+    pub length: c_uint,       // data length
+    pub data: *const c_void,
+}
+
 // Platform-specific library extensions
 #[cfg(target_os = "windows")]
 const LIB_EXTENSION: &str = ".dll";
@@ -570,6 +597,74 @@ pub fn bass_set_config_ptr(lib: &Library, option: c_uint, value: *const u8) -> c
 
 // legacy variants removed; use stream_create below
 
+// User file callbacks (BASS_FILEPROCS)
+pub type FileCloseProc = unsafe extern "system" fn(user: *mut c_void);
+pub type FileLenProc = unsafe extern "system" fn(user: *mut c_void) -> u64;
+pub type FileReadProc =
+    unsafe extern "system" fn(buffer: *mut c_void, length: c_uint, user: *mut c_void) -> c_uint;
+pub type FileSeekProc = unsafe extern "system" fn(offset: u64, user: *mut c_void) -> c_int;
+
+#[repr(C)]
+pub struct BassFileProcs {
+    pub close: FileCloseProc,
+    pub length: FileLenProc,
+    pub read: FileReadProc,
+    pub seek: FileSeekProc,
+}
+
+/// Random-access byte source for BASS_StreamCreateFileUser. `read` may block until the
+/// requested bytes exist (e.g. torrent pieces still downloading); it is called from BASS's
+/// decoding thread, never the UI thread.
+pub trait BlockingReader: Send {
+    /// Total length in bytes, 0 if unknown
+    fn length(&self) -> u64;
+    /// Read into `buf`, returning 0 at end of file
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize>;
+    /// Move to an absolute offset; false if the position is not reachable
+    fn seek(&mut self, offset: u64) -> bool;
+}
+
+type ReaderBox = Box<dyn BlockingReader>;
+
+unsafe extern "system" fn reader_close(user: *mut c_void) {
+    if !user.is_null() {
+        drop(Box::from_raw(user as *mut ReaderBox));
+    }
+}
+
+unsafe extern "system" fn reader_len(user: *mut c_void) -> u64 {
+    let reader = &*(user as *const ReaderBox);
+    reader.length()
+}
+
+unsafe extern "system" fn reader_read(
+    buffer: *mut c_void,
+    length: c_uint,
+    user: *mut c_void,
+) -> c_uint {
+    let reader = &mut *(user as *mut ReaderBox);
+    let buf = std::slice::from_raw_parts_mut(buffer as *mut u8, length as usize);
+    match reader.read(buf) {
+        Ok(n) => n as c_uint,
+        Err(e) => {
+            println!("[bass] User file read failed: {}", e);
+            c_uint::MAX
+        }
+    }
+}
+
+unsafe extern "system" fn reader_seek(offset: u64, user: *mut c_void) -> c_int {
+    let reader = &mut *(user as *mut ReaderBox);
+    reader.seek(offset) as c_int
+}
+
+static READER_PROCS: BassFileProcs = BassFileProcs {
+    close: reader_close,
+    length: reader_len,
+    read: reader_read,
+    seek: reader_seek,
+};
+
 // Unified stream creation API to handle both file and URL sources with optional offset and callback
 pub enum StreamSource<'a> {
     File(&'a CStr),
@@ -577,6 +672,12 @@ pub enum StreamSource<'a> {
         url: &'a CStr,
         offset: Option<c_ulong>,
     },
+    // Decoded through BASS_StreamCreateFileUser; `buffered` lets BASS read ahead on its own
+    // thread so a blocking reader doesn't stall playback
+    User {
+        reader: Box<dyn BlockingReader>,
+        buffered: bool,
+    },
 }
 
 pub fn stream_create(
@@ -603,6 +704,22 @@ pub fn stream_create(
                 };
                 f(url.as_ptr(), ofs, flags, proc_cb, user)
             }
+            StreamSource::User { reader, buffered } => {
+                let f: Symbol<BassStreamCreateFileUser> =
+                    match lib.get(b"BASS_StreamCreateFileUser") {
+                        Ok(f) => f,
+                        Err(_) => return 0,
+                    };
+                let system = if buffered {
+                    STREAMFILE_BUFFER
+                } else {
+                    STREAMFILE_NOBUFFER
+                };
+                // BASS owns the reader from here: the close callback frees it when the
+                // stream is freed, and also when creation fails
+                let user = Box::into_raw(Box::new(reader)) as *mut c_void;
+                f(system, flags, &READER_PROCS, user)
+            }
         }
     }
 }
@@ -1,4 +1,6 @@
 use crate::downloads;
+use crate::partial;
+use crate::tags::{self, TrackMetadata};
 use crate::utils::resolve_audio_source;
 use once_cell::sync::Lazy;
 use reqwest;
@@ -7,19 +9,19 @@ use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Mutex;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tauri::Emitter;
 use tauri::Manager;
 use tokio::fs as tokio_fs;
-use tokio::io::{AsyncWriteExt, BufWriter};
 use tokio::sync::mpsc;
 
 // Validate that the tail of a file contains real (non-zero) data and, when an
 // expected total is known, that the file has reached at least ~98% of that size.
 // This guards against sparse preallocation when pieces are shared between files
 // in multi-file torrents.
-async fn has_nontrivial_tail(path: &Path, expect_total: Option<u64>) -> bool {
+pub(crate) async fn has_nontrivial_tail(path: &Path, expect_total: Option<u64>) -> bool {
     use tokio::io::{AsyncReadExt, AsyncSeekExt};
     use std::io::SeekFrom;
     let meta = match tokio::fs::metadata(path).await { Ok(m) => m, Err(_) => return false };
@@ -76,6 +78,25 @@ pub struct CacheEntry {
     pub codec: Option<String>,
     pub sample_rate: Option<u32>,
     pub bits_per_sample: Option<u32>,
+    // Pinned entries are never evicted and keep their original file after a transcode
+    #[serde(default)]
+    pub pinned: bool,
+    // Original (pre-transcode) file kept alongside file_path for pinned entries
+    #[serde(default)]
+    pub original_file_path: Option<String>,
+    // Codec of the source file when file_path holds a transcoded copy
+    #[serde(default)]
+    pub transcoded_from: Option<String>,
+    // Embedded tags read after caching; None until the file has been probed
+    #[serde(default)]
+    pub metadata: Option<TrackMetadata>,
+}
+
+impl CacheEntry {
+    /// All files on disk that belong to this entry
+    fn files(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.file_path).chain(self.original_file_path.iter())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -124,7 +145,7 @@ impl AudioCache {
         })
     }
 
-    fn generate_cache_key_with_index(
+    pub(crate) fn generate_cache_key_with_index(
         track_id: &str,
         source_type: &str,
         source_hash: &str,
@@ -262,6 +283,10 @@ impl AudioCache {
             codec,
             sample_rate,
             bits_per_sample,
+            pinned: false,
+            original_file_path: None,
+            transcoded_from: None,
+            metadata: None,
         };
 
         self.index.entries.insert(cache_key, entry);
@@ -289,16 +314,14 @@ impl AudioCache {
             .index
             .entries
             .iter()
+            .filter(|(_, entry)| !entry.pinned)
             .filter(|(_, entry)| now.saturating_sub(entry.cached_at) > max_age_seconds)
             .map(|(key, _)| key.clone())
             .collect();
 
         for cache_key in keys_to_remove {
             if let Some(entry) = self.index.entries.remove(&cache_key) {
-                let file_path = self.cache_dir.join(&entry.file_path);
-                if file_path.exists() {
-                    let _ = fs::remove_file(file_path);
-                }
+                self.remove_entry_files(&entry);
                 self.index.total_size = self.index.total_size.saturating_sub(entry.file_size);
                 println!(
                     "[cache] Removed old cached file: {} ({}:{})",
@@ -314,6 +337,7 @@ impl AudioCache {
                 .index
                 .entries
                 .iter()
+                .filter(|(_, entry)| !entry.pinned)
                 .map(|(key, entry)| (key.clone(), entry.file_size))
                 .collect();
             entries_by_access.sort_by(|a, b| {
@@ -328,10 +352,7 @@ impl AudioCache {
                     break;
                 }
                 if let Some(entry) = self.index.entries.remove(&cache_key) {
-                    let file_path = self.cache_dir.join(&entry.file_path);
-                    if file_path.exists() {
-                        let _ = fs::remove_file(file_path);
-                    }
+                    self.remove_entry_files(&entry);
                     self.index.total_size = self.index.total_size.saturating_sub(entry.file_size);
                     println!(
                         "[cache] Removed LRU cached file: {} ({}:{})",
@@ -341,30 +362,521 @@ impl AudioCache {
             }
         }
 
+        self.prune_cover_art();
         Ok(())
     }
 
+    /// Delete cover art thumbnails no longer referenced by any entry
+    fn prune_cover_art(&self) {
+        let art_dir = self.cache_dir.join(tags::ART_DIR_NAME);
+        let read_dir = match fs::read_dir(&art_dir) {
+            Ok(rd) => rd,
+            Err(_) => return,
+        };
+        let referenced: HashSet<&str> = self
+            .index
+            .entries
+            .values()
+            .filter_map(|e| e.metadata.as_ref()?.cover_art.as_deref())
+            .collect();
+        for item in read_dir.flatten() {
+            let name = item.file_name();
+            let keep = name.to_str().map_or(false, |n| referenced.contains(n));
+            if !keep {
+                let _ = fs::remove_file(item.path());
+            }
+        }
+    }
+
+    fn remove_entry_files(&self, entry: &CacheEntry) {
+        for name in entry.files() {
+            let file_path = self.cache_dir.join(name);
+            if file_path.exists() {
+                let _ = fs::remove_file(file_path);
+            }
+        }
+    }
+
     pub fn get_cache_stats(&self) -> (u64, usize) {
         (self.index.total_size, self.index.entries.len())
     }
 
+    /// Entries matching `filter`, ordered by `sort` (ties broken by key so pages are stable)
+    pub fn list_entries(&self, filter: &CacheListFilter, sort: &CacheListSort) -> Vec<(&String, &CacheEntry)> {
+        let mut items: Vec<(&String, &CacheEntry)> = self
+            .index
+            .entries
+            .iter()
+            .filter(|(key, entry)| filter.matches(key, entry))
+            .collect();
+        items.sort_by(|(ka, a), (kb, b)| {
+            let ord = match sort.field {
+                CacheSortField::Size => a.file_size.cmp(&b.file_size),
+                CacheSortField::LastAccessed => a.last_accessed.cmp(&b.last_accessed),
+                CacheSortField::CachedAt => a.cached_at.cmp(&b.cached_at),
+            };
+            let ord = if sort.descending { ord.reverse() } else { ord };
+            ord.then_with(|| ka.cmp(kb))
+        });
+        items
+    }
+
+    /// Entry count and bytes per source type across the whole cache
+    pub fn size_by_source_type(&self) -> HashMap<String, (usize, u64)> {
+        let mut out: HashMap<String, (usize, u64)> = HashMap::new();
+        for entry in self.index.entries.values() {
+            let slot = out.entry(entry.source_type.clone()).or_default();
+            slot.0 += 1;
+            slot.1 += entry.file_size;
+        }
+        out
+    }
+
     pub fn clear_cache(&mut self) -> Result<(), String> {
         // Remove all cached files
         for entry in self.index.entries.values() {
-            let file_path = self.cache_dir.join(&entry.file_path);
-            if file_path.exists() {
-                let _ = fs::remove_file(file_path);
-            }
+            self.remove_entry_files(entry);
         }
 
         // Reset index
         self.index = CacheIndex::new();
         self.save_index()?;
+        let _ = fs::remove_dir_all(self.cache_dir.join(tags::ART_DIR_NAME));
 
         println!("[cache] Cache cleared");
         Ok(())
     }
+
+    /// Snapshot of the entries `export_bundle` should write (all when `track_ids` is None)
+    pub fn export_selection(&self, track_ids: Option<&[String]>) -> HashMap<String, CacheEntry> {
+        self.index
+            .entries
+            .iter()
+            .filter(|(_, e)| track_ids.map_or(true, |ids| ids.iter().any(|id| id == &e.track_id)))
+            .filter(|(_, e)| self.cache_dir.join(&e.file_path).exists())
+            .map(|(k, e)| {
+                // Only the playable file travels with the bundle
+                let mut e = e.clone();
+                e.original_file_path = None;
+                (k.clone(), e)
+            })
+            .collect()
+    }
+
+    /// Write a snapshot from `export_selection` and its audio files into a zip bundle.
+    /// Runs without the cache lock; entries evicted meanwhile are left out of the manifest.
+    /// Returns the number of entries exported.
+    pub fn export_bundle(
+        cache_dir: &Path,
+        mut selected: HashMap<String, CacheEntry>,
+        dest: &Path,
+    ) -> Result<usize, String> {
+        use zip::write::FileOptions;
+
+        if selected.is_empty() {
+            return Err("No cached entries matched the export selection".to_string());
+        }
+
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create export directory: {}", e))?;
+        }
+        let out = fs::File::create(dest)
+            .map_err(|e| format!("Failed to create bundle {}: {}", dest.display(), e))?;
+        let mut zip = zip::ZipWriter::new(out);
+        // Audio is already compressed; store it as-is and only deflate the manifest
+        let stored = FileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored)
+            .large_file(true);
+        let deflated = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+        let mut missing = Vec::new();
+        for (key, entry) in selected.iter() {
+            let src = cache_dir.join(&entry.file_path);
+            let mut f = match fs::File::open(&src) {
+                Ok(f) => f,
+                Err(e) => {
+                    println!("[cache] Leaving {} out of the bundle: {}", src.display(), e);
+                    missing.push(key.clone());
+                    continue;
+                }
+            };
+            zip.start_file(format!("{}/{}", BUNDLE_AUDIO_DIR, entry.file_path), stored)
+                .map_err(|e| format!("Failed to add {} to bundle: {}", entry.file_path, e))?;
+            std::io::copy(&mut f, &mut zip)
+                .map_err(|e| format!("Failed to add {} to bundle: {}", entry.file_path, e))?;
+        }
+
+        for key in &missing {
+            selected.remove(key);
+        }
+        if selected.is_empty() {
+            let _ = fs::remove_file(dest);
+            return Err("No cached entries matched the export selection".to_string());
+        }
+
+        // Shared cover art travels once per thumbnail
+        let art: HashSet<&str> = selected
+            .values()
+            .filter_map(|e| e.metadata.as_ref()?.cover_art.as_deref())
+            .collect();
+        for name in art {
+            let bytes = match fs::read(cache_dir.join(tags::ART_DIR_NAME).join(name)) {
+                Ok(b) => b,
+                Err(_) => continue,
+            };
+            zip.start_file(format!("{}/{}", BUNDLE_ART_DIR, name), stored)
+                .map_err(|e| format!("Failed to add cover art {} to bundle: {}", name, e))?;
+            zip.write_all(&bytes)
+                .map_err(|e| format!("Failed to add cover art {} to bundle: {}", name, e))?;
+        }
+
+        // The manifest goes last so it lists only what was actually written
+        let manifest = BundleManifest {
+            version: BUNDLE_VERSION,
+            exported_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            entries: selected.clone(),
+        };
+        let manifest_json = serde_json::to_vec_pretty(&manifest)
+            .map_err(|e| format!("Failed to serialize bundle manifest: {}", e))?;
+        zip.start_file(BUNDLE_MANIFEST_FILE, deflated)
+            .map_err(|e| format!("Failed to write bundle manifest: {}", e))?;
+        zip.write_all(&manifest_json)
+            .map_err(|e| format!("Failed to write bundle manifest: {}", e))?;
+
+        zip.finish()
+            .map_err(|e| format!("Failed to finalize bundle: {}", e))?;
+        println!("[cache] Exported {} cached entries to {}", selected.len(), dest.display());
+        Ok(selected.len())
+    }
+
+    /// Open a bundle produced by `export_bundle` and read its manifest
+    pub fn open_bundle(src: &Path) -> Result<(zip::ZipArchive<fs::File>, HashMap<String, CacheEntry>), String> {
+        use std::io::Read;
+
+        let file = fs::File::open(src)
+            .map_err(|e| format!("Failed to open bundle {}: {}", src.display(), e))?;
+        let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("Bad bundle: {}", e))?;
+
+        let manifest: BundleManifest = {
+            let mut mf = archive
+                .by_name(BUNDLE_MANIFEST_FILE)
+                .map_err(|_| "Bundle is missing manifest.json".to_string())?;
+            let mut s = String::new();
+            mf.read_to_string(&mut s)
+                .map_err(|e| format!("Failed to read bundle manifest: {}", e))?;
+            serde_json::from_str(&s).map_err(|e| format!("Failed to parse bundle manifest: {}", e))?
+        };
+        if manifest.version > BUNDLE_VERSION {
+            return Err(format!("Unsupported bundle version {}", manifest.version));
+        }
+        Ok((archive, manifest.entries))
+    }
+
+    /// Whether a bundled entry should replace what the cache holds for `key` under `policy`
+    fn wants_import(&self, key: &str, entry: &CacheEntry, policy: ImportConflictPolicy) -> bool {
+        let existing = match self.index.entries.get(key) {
+            Some(e) => e,
+            None => return true,
+        };
+        let existing_ok = self.cache_dir.join(&existing.file_path).exists();
+        match policy {
+            ImportConflictPolicy::Skip => !existing_ok,
+            ImportConflictPolicy::Overwrite => true,
+            ImportConflictPolicy::Newer => !existing_ok || entry.cached_at > existing.cached_at,
+        }
+    }
+
+    /// Bundled entries worth extracting under `policy`, plus the number left out
+    pub fn import_selection(
+        &self,
+        entries: HashMap<String, CacheEntry>,
+        policy: ImportConflictPolicy,
+    ) -> (HashMap<String, CacheEntry>, usize) {
+        let total = entries.len();
+        let selected: HashMap<_, _> = entries
+            .into_iter()
+            .filter(|(key, entry)| self.wants_import(key, entry, policy))
+            .collect();
+        let skipped = total - selected.len();
+        (selected, skipped)
+    }
+
+    /// Extract the audio of `selected` next to its final place in the cache dir.
+    /// Runs without the cache lock; `merge_import` moves the files into place.
+    /// On error nothing extracted so far is left behind.
+    pub fn extract_bundle(
+        cache_dir: &Path,
+        archive: &mut zip::ZipArchive<fs::File>,
+        selected: HashMap<String, CacheEntry>,
+    ) -> Result<(Vec<StagedImport>, usize), String> {
+        static IMPORT_SEQ: AtomicUsize = AtomicUsize::new(0);
+
+        let mut staged: Vec<StagedImport> = Vec::new();
+        let mut skipped = 0usize;
+        for (key, entry) in selected {
+            // Never trust paths from the bundle; keep only the file name inside the cache dir
+            let name = match Path::new(&entry.file_path).file_name().and_then(|n| n.to_str()) {
+                Some(n) => n.to_string(),
+                None => {
+                    println!("[cache] Skipping bundle entry with invalid path: {}", entry.file_path);
+                    skipped += 1;
+                    continue;
+                }
+            };
+            let mut zf = match archive.by_name(&format!("{}/{}", BUNDLE_AUDIO_DIR, entry.file_path)) {
+                Ok(f) => f,
+                Err(_) => {
+                    println!("[cache] Bundle is missing audio for {}, skipping", key);
+                    skipped += 1;
+                    continue;
+                }
+            };
+            let seq = IMPORT_SEQ.fetch_add(1, Ordering::Relaxed);
+            let tmp = cache_dir.join(format!("{}.{}.import", name, seq));
+            let written = fs::File::create(&tmp).and_then(|mut out| std::io::copy(&mut zf, &mut out));
+            let size = match written {
+                Ok(n) => n,
+                Err(e) => {
+                    let _ = fs::remove_file(&tmp);
+                    for s in &staged {
+                        let _ = fs::remove_file(&s.tmp);
+                    }
+                    return Err(format!("Failed to extract {}: {}", name, e));
+                }
+            };
+            staged.push(StagedImport { key, entry, name, tmp, size });
+        }
+        Ok((staged, skipped))
+    }
+
+    /// Move extracted files into place and record them, then save the index once.
+    /// Entries that stopped qualifying under `policy` while extracting are dropped.
+    /// If a move fails, the entries merged before it are kept and saved and the rest discarded.
+    /// Returns (imported, skipped) entry counts.
+    pub fn merge_import(
+        &mut self,
+        archive: &mut zip::ZipArchive<fs::File>,
+        staged: Vec<StagedImport>,
+        policy: ImportConflictPolicy,
+    ) -> Result<(usize, usize), String> {
+        let mut imported = 0usize;
+        let mut skipped = 0usize;
+        let mut error = None;
+        let mut staged = staged.into_iter();
+        for StagedImport { key, mut entry, name, tmp, size } in staged.by_ref() {
+            if !self.wants_import(&key, &entry, policy) {
+                let _ = fs::remove_file(&tmp);
+                skipped += 1;
+                continue;
+            }
+            let file_name = self.unclaimed_file_name(&key, &name);
+            if file_name != name {
+                println!("[cache] {} is used by another entry; importing {} as {}", name, key, file_name);
+            }
+            if let Err(e) = fs::rename(&tmp, self.cache_dir.join(&file_name)) {
+                let _ = fs::remove_file(&tmp);
+                error = Some(format!("Failed to move imported file {}: {}", file_name, e));
+                break;
+            }
+
+            if let Some(old) = self.index.entries.remove(&key) {
+                self.index.total_size = self.index.total_size.saturating_sub(old.file_size);
+                for stale in old.files().filter(|f| **f != file_name) {
+                    let _ = fs::remove_file(self.cache_dir.join(stale));
+                }
+            }
+            entry.file_path = file_name;
+            entry.file_size = size;
+            if let Some(meta) = entry.metadata.as_mut() {
+                meta.cover_art = meta
+                    .cover_art
+                    .take()
+                    .and_then(|name| Self::import_cover_art(archive, &self.cache_dir, &name));
+            }
+            entry.last_accessed = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            self.index.total_size += entry.file_size;
+            self.index.entries.insert(key, entry);
+            imported += 1;
+        }
+        for rest in staged {
+            let _ = fs::remove_file(&rest.tmp);
+        }
+
+        // Whatever made it in is on disk, so the index has to record it even after a failure
+        self.cleanup_cache()?;
+        self.save_index()?;
+        match error {
+            Some(e) => Err(e),
+            None => Ok((imported, skipped)),
+        }
+    }
+
+    /// `name`, or `name-2`, `name-3`, ... when a different entry than `key` already owns that file
+    fn unclaimed_file_name(&self, key: &str, name: &str) -> String {
+        let claimed = |candidate: &str| {
+            self.index
+                .entries
+                .iter()
+                .any(|(k, e)| k != key && e.files().any(|f| f == candidate))
+        };
+        if !claimed(name) {
+            return name.to_string();
+        }
+        let (stem, ext) = match name.rsplit_once('.') {
+            Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{}", ext)),
+            _ => (name, String::new()),
+        };
+        (2..)
+            .map(|n| format!("{}-{}{}", stem, n, ext))
+            .find(|candidate| !claimed(candidate) && !self.cache_dir.join(candidate).exists())
+            .unwrap()
+    }
+
+    /// Extract one bundled cover art thumbnail unless an identical one is already stored.
+    /// Returns the local file name, or None when the bundle doesn't carry it.
+    fn import_cover_art(archive: &mut zip::ZipArchive<fs::File>, cache_dir: &Path, name: &str) -> Option<String> {
+        let name = Path::new(name).file_name()?.to_str()?.to_string();
+        let art_dir = cache_dir.join(tags::ART_DIR_NAME);
+        if art_dir.join(&name).exists() {
+            return Some(name);
+        }
+        let mut zf = archive.by_name(&format!("{}/{}", BUNDLE_ART_DIR, name)).ok()?;
+        fs::create_dir_all(&art_dir).ok()?;
+        let mut out = fs::File::create(art_dir.join(&name)).ok()?;
+        std::io::copy(&mut zf, &mut out).ok()?;
+        Some(name)
+    }
+}
+
+// Cache bundle layout: manifest.json + audio/<file_path> + art/<cover_art>
+const BUNDLE_VERSION: u32 = 1;
+const BUNDLE_MANIFEST_FILE: &str = "manifest.json";
+const BUNDLE_AUDIO_DIR: &str = "audio";
+const BUNDLE_ART_DIR: &str = "art";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleManifest {
+    version: u32,
+    exported_at: u64,
+    entries: HashMap<String, CacheEntry>,
+}
+
+/// Bundle audio extracted to a temporary name in the cache dir, not yet in the index
+pub struct StagedImport {
+    key: String,
+    entry: CacheEntry,
+    // Sanitized file name from the bundle
+    name: String,
+    tmp: PathBuf,
+    size: u64,
+}
+
+/// How `cache_import` resolves a key that already exists in the local cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportConflictPolicy {
+    Skip,
+    Overwrite,
+    Newer,
+}
+
+impl ImportConflictPolicy {
+    pub fn parse(s: Option<&str>) -> Result<Self, String> {
+        match s.unwrap_or("skip") {
+            "skip" => Ok(Self::Skip),
+            "overwrite" => Ok(Self::Overwrite),
+            "newer" => Ok(Self::Newer),
+            other => Err(format!("Unknown conflict policy: {}", other)),
+        }
+    }
+}
+/// Filter for `cache_list`; all provided fields must match
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CacheListFilter {
+    pub source_type: Option<String>,
+    // Case-insensitive codec name as stored in CacheEntry.codec
+    pub codec: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub pinned: Option<bool>,
+    // Unix timestamps (seconds), inclusive
+    pub cached_after: Option<u64>,
+    pub cached_before: Option<u64>,
+    pub accessed_after: Option<u64>,
+    pub accessed_before: Option<u64>,
+    // Case-insensitive substring of the track id or source hash
+    pub query: Option<String>,
+}
+
+impl CacheListFilter {
+    fn matches(&self, key: &str, entry: &CacheEntry) -> bool {
+        if let Some(t) = &self.source_type {
+            if &entry.source_type != t {
+                return false;
+            }
+        }
+        if let Some(c) = &self.codec {
+            if !entry.codec.as_deref().map_or(false, |ec| ec.eq_ignore_ascii_case(c)) {
+                return false;
+            }
+        }
+        if self.sample_rate.is_some() && entry.sample_rate != self.sample_rate {
+            return false;
+        }
+        if let Some(p) = self.pinned {
+            if entry.pinned != p {
+                return false;
+            }
+        }
+        let in_range = |v: u64, after: Option<u64>, before: Option<u64>| {
+            after.map_or(true, |a| v >= a) && before.map_or(true, |b| v <= b)
+        };
+        if !in_range(entry.cached_at, self.cached_after, self.cached_before)
+            || !in_range(entry.last_accessed, self.accessed_after, self.accessed_before)
+        {
+            return false;
+        }
+        if let Some(q) = self.query.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+            let q = q.to_lowercase();
+            if !key.to_lowercase().contains(&q) && !entry.track_id.to_lowercase().contains(&q) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheSortField {
+    Size,
+    LastAccessed,
+    CachedAt,
+}
+
+/// Sort order for `cache_list`; defaults to most recently accessed first
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CacheListSort {
+    pub field: CacheSortField,
+    pub descending: bool,
+}
+
+impl Default for CacheListSort {
+    fn default() -> Self {
+        Self {
+            field: CacheSortField::LastAccessed,
+            descending: true,
+        }
+    }
 }
+
 /// Create a safe cache filename (without extension) based on identifiers.
 /// Returns a string like "<track>_<source_type>_<hash>" sanitized for filesystem.
 pub fn create_cache_filename(track_id: &str, source_type: &str, source_hash: &str) -> String {
@@ -491,6 +1003,249 @@ fn is_valid_audio_content(bytes: &[u8]) -> bool {
     true
 }
 
+// Parse "bytes <start>-<end>/<total>" (total may be "*") into (start, total)
+fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let spec = value.trim().strip_prefix("bytes")?.trim();
+    let (range, total) = spec.split_once('/')?;
+    let start = range.split_once('-')?.0.trim().parse::<u64>().ok()?;
+    Some((start, total.trim().parse::<u64>().ok()))
+}
+
+// Segmented HTTP downloads: big files on servers that honour Range are split across several
+// connections. The head of the file always stays with the sequential fetch loop.
+const SEGMENT_MAX_CONNECTIONS: usize = 4;
+const SEGMENT_MIN_TOTAL: u64 = 8 * 1024 * 1024;
+const SEGMENT_MIN_LEN: u64 = 2 * 1024 * 1024;
+const SEGMENT_RETRIES: u32 = 3;
+
+// Base GET for media URLs; YouTube's CDN answers faster to browser-like requests
+fn media_request(client: &reqwest::Client, url: &str, youtube: bool) -> reqwest::RequestBuilder {
+    let mut req = client.get(url).header("Accept-Encoding", "identity");
+    if youtube {
+        req = req
+            .header("Connection", "keep-alive")
+            .header("Origin", "https://www.youtube.com")
+            .header("Referer", "https://www.youtube.com/");
+    }
+    req
+}
+
+// Ask for a single byte; a 206 with a total means the server can serve arbitrary ranges
+async fn probe_range_total(client: &reqwest::Client, url: &str, youtube: bool) -> Option<u64> {
+    let (resp, _slot) =
+        crate::http::send_held(media_request(client, url, youtube).header("Range", "bytes=0-0"))
+            .await
+            .ok()?;
+    if resp.status() != 206 {
+        return None;
+    }
+    resp.headers()
+        .get("content-range")
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_content_range)
+        .and_then(|(_, total)| total)
+}
+
+// Split the missing ranges at or after `from` into at most `workers` groups of similar size
+fn plan_segments(gaps: &[(u64, u64)], from: u64, workers: usize) -> Vec<Vec<(u64, u64)>> {
+    let gaps: Vec<(u64, u64)> = gaps
+        .iter()
+        .filter(|&&(_, e)| e > from)
+        .map(|&(s, e)| (s.max(from), e))
+        .collect();
+    let missing: u64 = gaps.iter().map(|&(s, e)| e - s).sum();
+    if workers == 0 || missing == 0 {
+        return Vec::new();
+    }
+    let target = (missing / workers as u64).max(SEGMENT_MIN_LEN);
+    let mut plan: Vec<Vec<(u64, u64)>> = Vec::new();
+    let mut current: Vec<(u64, u64)> = Vec::new();
+    let mut current_len = 0u64;
+    for (mut s, e) in gaps {
+        while s < e {
+            let room = if plan.len() + 1 < workers {
+                target - current_len
+            } else {
+                u64::MAX
+            };
+            let take = (e - s).min(room);
+            current.push((s, s + take));
+            current_len += take;
+            s += take;
+            if current_len >= target && plan.len() + 1 < workers {
+                plan.push(std::mem::take(&mut current));
+                current_len = 0;
+            }
+        }
+    }
+    if !current.is_empty() {
+        plan.push(current);
+    }
+    plan
+}
+
+// Write a downloaded chunk into the part file off the async runtime; the data write and the
+// periodic fsync + range map save are blocking file I/O
+async fn write_part(partial: &partial::SharedPartial, pos: u64, data: Vec<u8>) -> std::io::Result<u64> {
+    let partial = partial.clone();
+    tokio::task::spawn_blocking(move || partial::write_shared(&partial, pos, &data))
+        .await
+        .unwrap_or_else(|e| Err(std::io::Error::other(format!("join error: {e}"))))
+}
+
+// Fetch one group of ranges into the shared part file, retrying each range on failure.
+// Whatever a worker leaves missing is picked up by the sequential loop afterwards.
+async fn fetch_segment(
+    client: reqwest::Client,
+    url: String,
+    youtube: bool,
+    partial: partial::SharedPartial,
+    ranges: Vec<(u64, u64)>,
+    source_type: String,
+    base_name: String,
+) {
+    use futures_util::StreamExt;
+
+    for (start, end) in ranges {
+        let mut failures = 0u32;
+        loop {
+            let gap = partial.lock().unwrap().meta().uncovered(start, end).first().copied();
+            let (gap_start, gap_end) = match gap {
+                Some(g) => g,
+                None => break,
+            };
+            let request = media_request(&client, &url, youtube)
+                .header("Range", format!("bytes={}-{}", gap_start, gap_end - 1));
+            let error = match crate::http::send_held(request).await {
+                Ok((resp, _slot)) if resp.status() == 206 => {
+                    let offset = resp
+                        .headers()
+                        .get("content-range")
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(parse_content_range)
+                        .map(|(s, _)| s);
+                    if offset != Some(gap_start) {
+                        println!(
+                            "[cache] Segment {}-{} of {}: unexpected Content-Range, leaving it to the main connection",
+                            gap_start, gap_end, base_name
+                        );
+                        return;
+                    }
+                    let mut pos = gap_start;
+                    let mut stream = resp.bytes_stream();
+                    let mut error = None;
+                    while let Some(item) = stream.next().await {
+                        if downloads::is_cancelled(&base_name) {
+                            return;
+                        }
+                        if downloads::is_paused(&base_name) {
+                            downloads::wait_while_paused_or_until_cancel(&base_name).await;
+                            if downloads::is_cancelled(&base_name) {
+                                return;
+                            }
+                        }
+                        let chunk = match item {
+                            Ok(chunk) => chunk,
+                            Err(e) => {
+                                error = Some(format!("stream error: {}", e));
+                                break;
+                            }
+                        };
+                        let len = chunk.len().min((gap_end - pos) as usize);
+                        if let Err(e) = write_part(&partial, pos, chunk[..len].to_vec()).await {
+                            println!("[cache] Segment write failed for {}: {}", base_name, e);
+                            return;
+                        }
+                        if len > 0 {
+                            failures = 0;
+                        }
+                        pos += len as u64;
+                        crate::bandwidth::throttle(&source_type, &base_name, len).await;
+                        if pos >= gap_end {
+                            break;
+                        }
+                    }
+                    error
+                }
+                Ok((resp, _)) => Some(format!("HTTP status {}", resp.status())),
+                Err(e) => Some(format!("request failed: {}", e)),
+            };
+            if let Some(error) = error {
+                failures += 1;
+                if failures > SEGMENT_RETRIES {
+                    println!(
+                        "[cache] Segment {}-{} of {} failed after {} attempts: {}",
+                        gap_start, gap_end, base_name, SEGMENT_RETRIES, error
+                    );
+                    return;
+                }
+                println!(
+                    "[cache] Segment {}-{} of {} failed ({}), retrying",
+                    gap_start, gap_end, base_name, error
+                );
+                tokio::time::sleep(std::time::Duration::from_secs(failures as u64)).await;
+            }
+        }
+    }
+}
+
+// Running segment workers of one download; aborted if the download bails out early
+struct SegmentWorkers(Vec<tokio::task::JoinHandle<()>>);
+
+impl SegmentWorkers {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    // Wait for every worker while keeping the inflight map and progress events current
+    async fn wait(
+        &mut self,
+        partial: &partial::SharedPartial,
+        app: Option<&tauri::AppHandle>,
+        ids: (&str, &str, &str),
+        base_name: &str,
+    ) -> u64 {
+        let (track_id, source_type, source_hash) = ids;
+        loop {
+            let (covered, total) = {
+                let p = partial.lock().unwrap();
+                (p.meta().covered(), p.meta().total)
+            };
+            if let Some(v) = INFLIGHT_DOWNLOADS.lock().unwrap().get_mut(base_name) {
+                v.0 = covered;
+                v.1 = total;
+            }
+            if self.0.iter().all(|h| h.is_finished()) {
+                self.0.clear();
+                return covered;
+            }
+            if let Some(app_ref) = app {
+                let _ = app_ref.emit(
+                    "cache:download:progress",
+                    serde_json::json!({
+                        "trackId": track_id,
+                        "sourceType": source_type,
+                        "sourceHash": source_hash,
+                        "bytes_downloaded": covered,
+                        "total_bytes": total,
+                        "limiter": crate::bandwidth::limiter_state(source_type),
+                        "inflight": true
+                    }),
+                );
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+        }
+    }
+}
+
+impl Drop for SegmentWorkers {
+    fn drop(&mut self) {
+        for handle in &self.0 {
+            handle.abort();
+        }
+    }
+}
+
 // Initialize cache with app config directory
 pub fn init_cache(audio_cache_dir: &Path) -> Result<(), String> {
     let mut cache = CACHE.lock().unwrap();
@@ -583,47 +1338,161 @@ pub fn add_cached_file_to_index_with_format(
     sample_rate: Option<u32>,
     bits_per_sample: Option<u32>,
 ) -> Result<(), String> {
-    let mut cache_guard = CACHE.lock().unwrap();
-    if let Some(cache) = cache_guard.as_mut() {
+    {
+        let mut cache_guard = CACHE.lock().unwrap();
+        let cache = cache_guard.as_mut().ok_or("Cache not initialized")?;
         cache.add_cached_file_with_index_and_format(
-            track_id,
-            source_type,
-            source_hash,
+            track_id.clone(),
+            source_type.clone(),
+            source_hash.clone(),
             file_path,
             file_size,
             file_index,
             codec,
             sample_rate,
             bits_per_sample,
-        )
-    } else {
-        Err("Cache not initialized".to_string())
+        )?;
     }
+    on_entry_cached(&track_id, &source_type, &source_hash, file_index);
+    Ok(())
 }
 
-// Return current inflight download status (bytes_downloaded, optional total) for a given key
-pub fn get_inflight_status(
-    track_id: &str,
-    source_type: &str,
-    source_hash: &str,
-) -> Option<(u64, Option<u64>)> {
-    let cache_key = create_cache_filename(track_id, source_type, source_hash);
-    let inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
-    inflight.get(&cache_key).cloned()
+/// Snapshot of every index key, for background sweeps
+pub fn list_cache_keys() -> Vec<String> {
+    let cache_guard = CACHE.lock().unwrap();
+    cache_guard
+        .as_ref()
+        .map(|c| c.index.entries.keys().cloned().collect())
+        .unwrap_or_default()
 }
 
-// Check that several windows in the middle of the file contain non-zero bytes to avoid caching sparse holes.
-async fn has_nonzero_middle_samples(path: &std::path::Path, total_opt: Option<u64>) -> bool {
-    use tokio::io::{AsyncReadExt, AsyncSeekExt};
-    use std::io::SeekFrom;
-    let total = if let Some(t) = total_opt {
-        t
-    } else {
-        match tokio::fs::metadata(path).await { Ok(m) => m.len(), Err(_) => return false }
-    };
-    if total < 64 * 1024 {
-        // Tiny files: treat as OK if they exist
-        return true;
+/// Clone an index entry together with the absolute path of its playable file
+pub fn get_entry_snapshot(cache_key: &str) -> Option<(CacheEntry, PathBuf)> {
+    let cache_guard = CACHE.lock().unwrap();
+    let cache = cache_guard.as_ref()?;
+    let entry = cache.index.entries.get(cache_key)?.clone();
+    let path = cache.cache_dir.join(&entry.file_path);
+    if path.exists() { Some((entry, path)) } else { None }
+}
+
+/// Point an entry at its transcoded file. The original is kept for pinned entries;
+/// otherwise its absolute path is returned so the caller can delete it once released.
+pub fn apply_transcoded_file(
+    cache_key: &str,
+    file_name: String,
+    file_size: u64,
+    codec: String,
+    sample_rate: Option<u32>,
+    source_codec: String,
+) -> Result<Option<PathBuf>, String> {
+    let mut cache_guard = CACHE.lock().unwrap();
+    let cache = cache_guard.as_mut().ok_or("Cache not initialized")?;
+    let cache_dir = cache.cache_dir.clone();
+    let entry = cache
+        .index
+        .entries
+        .get_mut(cache_key)
+        .ok_or("Entry removed while transcoding")?;
+
+    let original = std::mem::replace(&mut entry.file_path, file_name);
+    let original_size = fs::metadata(cache_dir.join(&original)).map(|m| m.len()).unwrap_or(0);
+    let old_size = entry.file_size;
+    entry.codec = Some(codec);
+    entry.sample_rate = sample_rate;
+    entry.bits_per_sample = None;
+    entry.transcoded_from = Some(source_codec);
+    let obsolete = if entry.pinned {
+        entry.original_file_path = Some(original);
+        entry.file_size = file_size + original_size;
+        None
+    } else {
+        entry.file_size = file_size;
+        Some(cache_dir.join(original))
+    };
+    cache.index.total_size = cache.index.total_size.saturating_sub(old_size) + entry.file_size;
+    cache.save_index()?;
+    Ok(obsolete)
+}
+
+/// Cache keys of entries whose file has not been probed for embedded tags yet
+pub fn list_cache_keys_without_metadata() -> Vec<String> {
+    let cache_guard = CACHE.lock().unwrap();
+    cache_guard
+        .as_ref()
+        .map(|c| {
+            c.index
+                .entries
+                .iter()
+                .filter(|(_, e)| e.metadata.is_none())
+                .map(|(k, _)| k.clone())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Store the tags read from an entry's file
+pub fn apply_entry_metadata(cache_key: &str, metadata: TrackMetadata) -> Result<(), String> {
+    let mut cache_guard = CACHE.lock().unwrap();
+    let cache = cache_guard.as_mut().ok_or("Cache not initialized")?;
+    let entry = cache
+        .index
+        .entries
+        .get_mut(cache_key)
+        .ok_or("Entry removed while reading tags")?;
+    entry.metadata = Some(metadata);
+    cache.save_index()
+}
+
+/// Drop an entry and its files (used when a track is moved out to the library)
+pub fn remove_entry(cache_key: &str) -> Result<(), String> {
+    let mut cache_guard = CACHE.lock().unwrap();
+    let cache = cache_guard.as_mut().ok_or("Cache not initialized")?;
+    let entry = cache
+        .index
+        .entries
+        .remove(cache_key)
+        .ok_or("Entry no longer cached")?;
+    cache.remove_entry_files(&entry);
+    cache.index.total_size = cache.index.total_size.saturating_sub(entry.file_size);
+    cache.save_index()
+}
+
+// Hook run once a download has been finalized into the cache index
+fn on_entry_cached(track_id: &str, source_type: &str, source_hash: &str, file_index: Option<usize>) {
+    let cache_key = AudioCache::generate_cache_key_with_index(track_id, source_type, source_hash, file_index);
+    tags::schedule(cache_key.clone());
+    crate::transcode::schedule(cache_key);
+}
+
+// Return current inflight download status (bytes_downloaded, optional total) for a given key
+pub fn get_inflight_status(
+    track_id: &str,
+    source_type: &str,
+    source_hash: &str,
+) -> Option<(u64, Option<u64>)> {
+    let cache_key = create_cache_filename(track_id, source_type, source_hash);
+    let inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
+    inflight.get(&cache_key).cloned()
+}
+
+// Inflight status keyed by cache base name (includes the file index for torrents)
+pub fn get_inflight_status_by_base(base_name: &str) -> Option<(u64, Option<u64>)> {
+    let inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
+    inflight.get(base_name).cloned()
+}
+
+// Check that several windows in the middle of the file contain non-zero bytes to avoid caching sparse holes.
+pub(crate) async fn has_nonzero_middle_samples(path: &std::path::Path, total_opt: Option<u64>) -> bool {
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+    use std::io::SeekFrom;
+    let total = if let Some(t) = total_opt {
+        t
+    } else {
+        match tokio::fs::metadata(path).await { Ok(m) => m.len(), Err(_) => return false }
+    };
+    if total < 64 * 1024 {
+        // Tiny files: treat as OK if they exist
+        return true;
     }
     // Sample up to three windows: at 1/3, 1/2, and 2/3 of the file
     // Each window reads up to 16KB and checks for any non-zero byte
@@ -755,6 +1624,7 @@ pub async fn cache_download_and_store(
     source_hash: String,
     url: String,
     file_index: Option<usize>,
+    priority: Option<i32>,
 ) -> Result<String, String> {
     println!("[cache] cache_download_and_store called with track_id: '{}', source_type: '{}', source_hash: '{}', url: '{}', file_index: {:?}", track_id, source_type, source_hash, &url[..50.min(url.len())], file_index);
 
@@ -788,211 +1658,265 @@ pub async fn cache_download_and_store(
         }
     }
 
+    // Hand the download to the queue; it dedupes by job id and enforces the concurrency limit
+    let queued = downloads::enqueue(
+        &app,
+        downloads::JobSpec {
+            track_id,
+            source_type,
+            source_hash,
+            url,
+            file_index,
+        },
+        priority.unwrap_or(downloads::PRIORITY_NORMAL),
+    )?;
+    Ok(if queued { "Download queued" } else { "Already in progress" }.to_string())
+}
+
+/// Run one cache download to completion; torrent sources retry internally while they make progress.
+/// Called by the download queue. Returns the last reported error when nothing ended up cached.
+// Timeouts and retry backoff of torrent downloads; tests run them 100x faster
+fn torrent_clock(secs: u64) -> std::time::Duration {
+    let divisor = if cfg!(test) { 100 } else { 1 };
+    std::time::Duration::from_secs(secs) / divisor
+}
+
+pub(crate) async fn run_cache_download(
+    app: Option<tauri::AppHandle>,
+    track_id: String,
+    source_type: String,
+    source_hash: String,
+    url: String,
+    file_index: Option<usize>,
+) -> Result<(), String> {
+    let base_name = create_cache_filename_with_index(
+        &track_id,
+        &source_type,
+        &source_hash,
+        file_index,
+    );
+
     // If another caller is currently starting this download, dedupe
     {
         let mut starting = STARTING_DOWNLOADS.lock().unwrap();
         if !starting.insert(base_name.clone()) {
             println!("[cache] Duplicate download request suppressed (starting): {}", base_name);
-            return Ok("Already in progress".to_string());
+            return Err("Already in progress".to_string());
         }
     }
+    // Ensure starting flag is cleared on any exit path
+    let _guard = StartGuard { key: base_name.clone() };
 
     let (tx, _rx) = mpsc::unbounded_channel::<CacheDownloadResult>();
-
-    // spawn background task to avoid blocking the command
-    let app_clone = app.clone();
-    let starting_key = base_name.clone();
-    tokio::spawn(async move {
-        // Ensure starting flag is cleared on any exit path
-        let _guard = StartGuard { key: starting_key };
-        // For torrent downloads, implement retry logic
-        if source_type == "torrent" {
-            let mut retry_count = 0;
-            let max_retries = 10; // Try for up to 10 times
-            let mut last_progress: Option<u64> = None; // Track progress between retries
-
-            // Helper: validate that the tail of the file contains real data (not just sparse zeros)
-            // This helps when the torrent engine preallocates full length and fills pieces out-of-order,
-            // especially for start/end pieces shared with neighbor files.
-            async fn has_nontrivial_tail(p: &std::path::Path, expect_total: Option<u64>) -> bool {
-                use tokio::io::{AsyncReadExt, AsyncSeekExt};
-                use std::io::SeekFrom;
-                let meta = match tokio::fs::metadata(p).await { Ok(m) => m, Err(_) => return false };
-                let len = meta.len();
-                let total = expect_total.unwrap_or(len);
-                if total == 0 { return false; }
-                // Read up to the last 64KB (or less if very small files)
-                let tail_len: u64 = std::cmp::min(64 * 1024, total).max(512);
-                let start = total.saturating_sub(tail_len);
-                if let Ok(mut f) = tokio::fs::File::open(p).await {
-                    if f.seek(SeekFrom::Start(start)).await.is_ok() {
-                        let mut buf = vec![0u8; tail_len as usize];
-                        if let Ok(n) = f.read(&mut buf).await {
-                            if n == 0 { return false; }
-                            buf.truncate(n);
-                            // Consider the tail valid if at least 1% or 512 bytes (whichever larger) are non-zero
-                            let non_zero = buf.iter().filter(|b| **b != 0).count() as u64;
-                            let threshold = std::cmp::max(512u64, (buf.len() as u64) / 100);
-                            return non_zero >= threshold;
-                        }
+    // For torrent downloads, implement retry logic
+    if source_type == "torrent" {
+        let mut retry_count = 0;
+        let max_retries = 10; // Try for up to 10 times
+        let mut last_progress: Option<u64> = None; // Track progress between retries
+
+        // Helper: validate that the tail of the file contains real data (not just sparse zeros)
+        // This helps when the torrent engine preallocates full length and fills pieces out-of-order,
+        // especially for start/end pieces shared with neighbor files.
+        async fn has_nontrivial_tail(p: &std::path::Path, expect_total: Option<u64>) -> bool {
+            use tokio::io::{AsyncReadExt, AsyncSeekExt};
+            use std::io::SeekFrom;
+            let meta = match tokio::fs::metadata(p).await { Ok(m) => m, Err(_) => return false };
+            let len = meta.len();
+            let total = expect_total.unwrap_or(len);
+            if total == 0 { return false; }
+            // Read up to the last 64KB (or less if very small files)
+            let tail_len: u64 = std::cmp::min(64 * 1024, total).max(512);
+            let start = total.saturating_sub(tail_len);
+            if let Ok(mut f) = tokio::fs::File::open(p).await {
+                if f.seek(SeekFrom::Start(start)).await.is_ok() {
+                    let mut buf = vec![0u8; tail_len as usize];
+                    if let Ok(n) = f.read(&mut buf).await {
+                        if n == 0 { return false; }
+                        buf.truncate(n);
+                        // Consider the tail valid if at least 1% or 512 bytes (whichever larger) are non-zero
+                        let non_zero = buf.iter().filter(|b| **b != 0).count() as u64;
+                        let threshold = std::cmp::max(512u64, (buf.len() as u64) / 100);
+                        return non_zero >= threshold;
                     }
                 }
-                false
             }
+            false
+        }
 
-            loop {
-                println!(
-                    "[cache] Torrent download attempt {} of {} for {}",
-                    retry_count + 1,
-                    max_retries,
-                    track_id
-                );
+        loop {
+            println!(
+                "[cache] Torrent download attempt {} of {} for {}",
+                retry_count + 1,
+                max_retries,
+                track_id
+            );
 
-                // Check current progress before attempting download
-                let current_progress = {
-                    let cache_key = create_cache_filename_with_index(
-                        &track_id,
-                        &source_type,
-                        &source_hash,
-                        file_index,
-                    );
-                    let inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
-                    inflight
-                        .get(&cache_key)
-                        .map(|(bytes, _)| *bytes)
-                        .unwrap_or(0)
-                };
+            // Check current progress before attempting download
+            let current_progress = {
+                let cache_key = create_cache_filename_with_index(
+                    &track_id,
+                    &source_type,
+                    &source_hash,
+                    file_index,
+                );
+                let inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
+                inflight
+                    .get(&cache_key)
+                    .map(|(bytes, _)| *bytes)
+                    .unwrap_or(0)
+            };
 
-                // If we have made progress since last attempt, reset retry count to be more patient
-                if let Some(last_bytes) = last_progress {
-                    if current_progress > last_bytes {
-                        let progress_mb =
-                            (current_progress - last_bytes) as f64 / (1024.0 * 1024.0);
-                        println!("[cache] Progress detected: +{:.2}MB downloaded since last attempt, resetting retry patience", progress_mb);
-                        retry_count = 0; // Reset retry count when we see progress
-                    }
+            // If we have made progress since last attempt, reset retry count to be more patient
+            if let Some(last_bytes) = last_progress {
+                if current_progress > last_bytes {
+                    let progress_mb =
+                        (current_progress - last_bytes) as f64 / (1024.0 * 1024.0);
+                    println!("[cache] Progress detected: +{:.2}MB downloaded since last attempt, resetting retry patience", progress_mb);
+                    retry_count = 0; // Reset retry count when we see progress
                 }
-                last_progress = Some(current_progress);
-
-                download_and_cache_audio(
-                    Some(app_clone.clone()),
-                    track_id.clone(),
-                    source_type.clone(),
-                    source_hash.clone(),
-                    url.clone(),
-                    file_index,
-                    tx.clone(),
-                )
-                .await;
-
-                // Check if the file was successfully cached
-                let cache_key = if file_index.is_some() {
-                    format!(
-                        "{}:{}:{}:{}",
-                        track_id,
-                        source_type,
-                        source_hash,
-                        file_index.unwrap()
-                    )
-                } else {
-                    format!("{}:{}:{}", track_id, source_type, source_hash)
-                };
+            }
+            last_progress = Some(current_progress);
 
-                // Quick check if file exists in cache
-                let is_cached = {
-                    let mut cache = crate::cache::CACHE.lock().unwrap();
-                    if let Some(cache_ref) = cache.as_mut() {
-                        cache_ref
-                            .get_cached_file_with_index(
-                                &track_id,
-                                &source_type,
-                                &source_hash,
-                                file_index,
-                            )
-                            .is_some()
-                    } else {
-                        false
-                    }
-                };
+            download_and_cache_audio(
+                app.clone(),
+                track_id.clone(),
+                source_type.clone(),
+                source_hash.clone(),
+                url.clone(),
+                file_index,
+                tx.clone(),
+            )
+            .await;
 
-                if is_cached {
-                    println!(
-                        "[cache] Torrent download completed successfully for {}",
-                        track_id
-                    );
-                    break;
-                }
+            // Removed from the queue while this attempt ran
+            if !downloads::job_exists(&base_name) {
+                break;
+            }
 
-                // Check if we're making progress even if not complete
-                let final_progress = {
-                    let cache_key = create_cache_filename_with_index(
-                        &track_id,
-                        &source_type,
-                        &source_hash,
-                        file_index,
-                    );
-                    let inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
-                    inflight
-                        .get(&cache_key)
-                        .map(|(bytes, _)| *bytes)
-                        .unwrap_or(0)
-                };
+            // Check if the file was successfully cached
+            let cache_key = if file_index.is_some() {
+                format!(
+                    "{}:{}:{}:{}",
+                    track_id,
+                    source_type,
+                    source_hash,
+                    file_index.unwrap()
+                )
+            } else {
+                format!("{}:{}:{}", track_id, source_type, source_hash)
+            };
 
-                // If file is actively downloading (has significant progress), be more patient
-                if final_progress > 1024 * 1024 {
-                    // More than 1MB downloaded
-                    println!(
-                        "[cache] Torrent has significant progress ({:.2}MB), extending patience",
-                        final_progress as f64 / (1024.0 * 1024.0)
-                    );
-                    // Don't increment retry count if we have substantial data
+            // Quick check if file exists in cache
+            let is_cached = {
+                let mut cache = crate::cache::CACHE.lock().unwrap();
+                if let Some(cache_ref) = cache.as_mut() {
+                    cache_ref
+                        .get_cached_file_with_index(
+                            &track_id,
+                            &source_type,
+                            &source_hash,
+                            file_index,
+                        )
+                        .is_some()
                 } else {
-                    retry_count += 1;
+                    false
                 }
+            };
+
+            if is_cached {
+                println!(
+                    "[cache] Torrent download completed successfully for {}",
+                    track_id
+                );
+                break;
+            }
+
+            // Check if we're making progress even if not complete
+            let final_progress = {
+                let cache_key = create_cache_filename_with_index(
+                    &track_id,
+                    &source_type,
+                    &source_hash,
+                    file_index,
+                );
+                let inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
+                inflight
+                    .get(&cache_key)
+                    .map(|(bytes, _)| *bytes)
+                    .unwrap_or(0)
+            };
+
+            // If file is actively downloading (has significant progress), be more patient
+            if final_progress > 1024 * 1024 {
+                // More than 1MB downloaded
+                println!(
+                    "[cache] Torrent has significant progress ({:.2}MB), extending patience",
+                    final_progress as f64 / (1024.0 * 1024.0)
+                );
+                // Don't increment retry count if we have substantial data
+            } else {
+                retry_count += 1;
+            }
 
-                if retry_count >= max_retries {
-                    println!("[cache] Max retries exceeded for torrent download: {} (final progress: {:.2}MB)", track_id, final_progress as f64 / (1024.0 * 1024.0));
-                    let _ = app_clone.emit(
+            if retry_count >= max_retries {
+                println!("[cache] Max retries exceeded for torrent download: {} (final progress: {:.2}MB)", track_id, final_progress as f64 / (1024.0 * 1024.0));
+                let message = downloads::note_error(&base_name, "Torrent download timeout after multiple retries");
+                if let Some(app_ref) = app.as_ref() {
+                    let _ = app_ref.emit(
                         "cache:download:error",
                         serde_json::json!({
                             "trackId": track_id,
                             "sourceType": source_type,
                             "sourceHash": source_hash,
-                            "message": "Torrent download timeout after multiple retries"
+                            "message": message
                         }),
                     );
-                    break;
                 }
-
-                // Wait before retrying (exponential backoff: 2, 4, 8, 16 seconds, then 30 seconds)
-                let delay_secs = if retry_count <= 4 {
-                    2_u64.pow(retry_count as u32)
-                } else {
-                    30
-                };
-                println!(
-                    "[cache] Waiting {} seconds before retry for torrent: {}",
-                    delay_secs, track_id
-                );
-                tokio::time::sleep(std::time::Duration::from_secs(delay_secs)).await;
+                break;
             }
-        } else {
-            // Non-torrent downloads: single attempt
-            download_and_cache_audio(
-                Some(app_clone),
-                track_id,
-                source_type,
-                source_hash,
-                url,
-                file_index,
-                tx,
-            )
-            .await;
+
+            // Wait before retrying (exponential backoff: 2, 4, 8, 16 seconds, then 30 seconds)
+            let delay_secs = if retry_count <= 4 {
+                2_u64.pow(retry_count as u32)
+            } else {
+                30
+            };
+            println!(
+                "[cache] Waiting {} seconds before retry for torrent: {}",
+                delay_secs, track_id
+            );
+            tokio::time::sleep(torrent_clock(delay_secs)).await;
         }
-    });
+    } else {
+        // Non-torrent downloads: single attempt
+        download_and_cache_audio(
+            app,
+            track_id.clone(),
+            source_type.clone(),
+            source_hash.clone(),
+            url,
+            file_index,
+            tx,
+        )
+        .await;
+    }
 
-    // For compatibility, return immediately
-    Ok("Download started".to_string())
+    let is_cached = {
+        let mut cache_guard = CACHE.lock().unwrap();
+        cache_guard
+            .as_mut()
+            .map(|c| {
+                c.get_cached_file_with_index(&track_id, &source_type, &source_hash, file_index)
+                    .is_some()
+            })
+            .unwrap_or(false)
+    };
+    if is_cached {
+        downloads::take_error(&base_name);
+        Ok(())
+    } else {
+        Err(downloads::take_error(&base_name).unwrap_or_else(|| "Download did not complete".to_string()))
+    }
 }
 
 pub async fn download_and_cache_audio(
@@ -1008,75 +1932,91 @@ pub async fn download_and_cache_audio(
     println!("[cache] Starting download for {} ({}:{}) from: {}... (file_index: {:?})", 
         track_id, source_type, source_hash, &url[..50.min(url.len())], file_index);
 
+    // Use shared helper for consistent base name
+    let base_name =
+        create_cache_filename_with_index(&track_id, &source_type, &source_hash, file_index);
+
     // Torrent downloads: use the embedded torrent engine directly instead of localhost server
     if source_type == "torrent" && file_index.is_some() {
-        if let Some(app_ref) = app.as_ref() {
-            use tokio::io::{AsyncReadExt, AsyncSeekExt};
-            use std::io::SeekFrom;
-            let idx = file_index.unwrap() as u32;
-            // Download torrent data into the temporary torrents folder under Roaming
-            let save_dir = app_ref.state::<crate::paths::PathConfig>().torrents_dir.clone();
-            let engine = crate::torrents::get_engine();
-
-            // Start the torrent download via engine (magnet or infohash is accepted)
-            if let Err(e) = tokio::task::spawn_blocking({
-                let url_c = url.clone();
-                let save_dir_c = save_dir.clone();
-                move || engine.start_download(&url_c, idx, &save_dir_c)
-            }).await.unwrap_or_else(|e| Err(format!("join error: {e}"))) {
-                println!(
-                    "[cache] Failed to start torrent download via engine for {} ({}:{}): {}",
-                    track_id, source_type, source_hash, e
-                );
+        use tokio::io::{AsyncReadExt, AsyncSeekExt};
+        use std::io::SeekFrom;
+        let idx = file_index.unwrap() as u32;
+        // Download torrent data into the temporary torrents folder under Roaming
+        let save_dir = crate::torrents::torrents_dir();
+        let engine = crate::torrents::get_engine();
+
+        // Start the torrent download via engine (magnet or infohash is accepted)
+        if let Err(e) = tokio::task::spawn_blocking({
+            let url_c = url.clone();
+            let save_dir_c = save_dir.clone();
+            move || engine.start_download(&url_c, idx, &save_dir_c)
+        }).await.unwrap_or_else(|e| Err(format!("join error: {e}"))) {
+            println!(
+                "[cache] Failed to start torrent download via engine for {} ({}:{}): {}",
+                track_id, source_type, source_hash, e
+            );
+            let message = downloads::note_error(&base_name, format!("Failed to start torrent: {}", e));
+            if let Some(app_ref) = app.as_ref() {
                 let _ = app_ref.emit(
                     "cache:download:error",
                     serde_json::json!({
                         "trackId": track_id,
                         "sourceType": source_type,
                         "sourceHash": source_hash,
-                        "message": format!("Failed to start torrent: {}", e)
+                        "message": message
                     }),
                 );
-                return;
             }
+            return;
+        }
 
-            // Resolve the engine's file path; retry a few times until it becomes available
-            let engine_path = loop {
-                let maybe_path = tokio::task::spawn_blocking({
-                    let url_c = url.clone();
-                    let save_dir_c = save_dir.clone();
-                    move || engine.file_path(&url_c, idx, &save_dir_c)
-                }).await.ok().and_then(|r| r.ok());
-                if let Some(p) = maybe_path.clone() {
-                    if p.exists() { break p; }
-                }
-                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-            };
+        // Resolve the engine's file path; retry a few times until it becomes available
+        let engine_path = loop {
+            let maybe_path = tokio::task::spawn_blocking({
+                let url_c = url.clone();
+                let save_dir_c = save_dir.clone();
+                move || engine.file_path(&url_c, idx, &save_dir_c)
+            }).await.ok().and_then(|r| r.ok());
+            if let Some(p) = maybe_path.clone() {
+                if p.exists() { break p; }
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        };
 
-            // Prepare inflight bookkeeping using our final cache base name
-            let base_name = create_cache_filename_with_index(
-                &track_id,
-                &source_type,
-                &source_hash,
-                file_index,
-            );
-            downloads::ensure_control_for(&base_name);
-            let cache_dir = {
-                let cache_guard = CACHE.lock().unwrap();
-                if let Some(c) = cache_guard.as_ref() { c.cache_dir.clone() } else {
-                    println!("[cache] Cache not initialized, cannot cache {} ({}:{})", track_id, source_type, source_hash);
-                    return;
-                }
-            };
-            let final_path = cache_dir.join(&base_name);
+        // Prepare inflight bookkeeping using our final cache base name
+        downloads::ensure_control_for(&base_name);
+        let cache_dir = {
+            let cache_guard = CACHE.lock().unwrap();
+            if let Some(c) = cache_guard.as_ref() { c.cache_dir.clone() } else {
+                println!("[cache] Cache not initialized, cannot cache {} ({}:{})", track_id, source_type, source_hash);
+                return;
+            }
+        };
+        let final_path = cache_dir.join(&base_name);
+        let part_path = cache_dir.join(format!("{}.part", base_name));
 
-            // Mark inflight and emit initial progress
-            {
-                let mut inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
-                inflight.insert(base_name.clone(), (0u64, None));
-                let mut meta = INFLIGHT_META.lock().unwrap();
-                meta.insert(base_name.clone(), (track_id.clone(), source_type.clone(), source_hash.clone()));
+        // Mirror verified engine data into our own part file so it survives the engine
+        // dropping the torrent; finalization then only copies the remaining gaps
+        let partial = match partial::open(&part_path, &track_id, &source_type, &source_hash, file_index) {
+            Ok(p) => p,
+            Err(e) => {
+                println!("[cache] {} {:?}", e, part_path);
+                downloads::clear_control(&base_name);
+                return;
             }
+        };
+        let mut last_mirror_time = std::time::Instant::now();
+        let mut last_mirror_bytes = 0u64;
+        const MIRROR_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+        // Mark inflight and emit initial progress
+        {
+            let mut inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
+            inflight.insert(base_name.clone(), (0u64, None));
+            let mut meta = INFLIGHT_META.lock().unwrap();
+            meta.insert(base_name.clone(), (track_id.clone(), source_type.clone(), source_hash.clone()));
+        }
+        if let Some(app_ref) = app.as_ref() {
             let _ = app_ref.emit(
                 "cache:download:progress",
                 serde_json::json!({
@@ -1085,118 +2025,147 @@ pub async fn download_and_cache_audio(
                     "sourceHash": source_hash,
                     "bytes_downloaded": 0u64,
                     "total_bytes": null,
+                    "limiter": crate::bandwidth::limiter_state(&source_type),
                     "inflight": true
                 }),
             );
+        }
 
-            // Copy loop: tail the engine file as it grows
-            let mut prefix_buf: Vec<u8> = Vec::with_capacity(8192);
-            let mut ready_emitted = false;
-            let mut last_progress_time = std::time::Instant::now();
-            let mut last_progress_bytes = 0u64;
-            let mut total_opt: Option<u64> = None;
-            const PROGRESS_TIME_THRESHOLD: std::time::Duration = std::time::Duration::from_millis(250);
-            const PROGRESS_BYTES_THRESHOLD: u64 = 512 * 1024;
-
-            // Add timeout mechanism to prevent infinite loops when torrent doesn't start
-            let start_time = std::time::Instant::now();
-            let mut last_activity_time = start_time;
-            const INITIAL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60); // 1 minute for first bytes
-            const STALL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);  // 5 minutes for stalls
-
-            loop {
-                // Respect pause/cancel controls
-                if downloads::is_cancelled(&base_name) {
-                    println!("[cache] Cancel requested for {} ({}:{})", track_id, source_type, source_hash);
-                    let mut inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
-                    inflight.remove(&base_name);
-                    let mut meta = INFLIGHT_META.lock().unwrap();
-                    meta.remove(&base_name);
+        // Copy loop: tail the engine file as it grows
+        let mut prefix_buf: Vec<u8> = Vec::with_capacity(8192);
+        let mut ready_emitted = false;
+        let mut last_progress_time = std::time::Instant::now();
+        let mut last_progress_bytes = 0u64;
+        let mut total_opt: Option<u64> = None;
+        const PROGRESS_TIME_THRESHOLD: std::time::Duration = std::time::Duration::from_millis(250);
+        const PROGRESS_BYTES_THRESHOLD: u64 = 512 * 1024;
+
+        // Add timeout mechanism to prevent infinite loops when torrent doesn't start
+        let start_time = std::time::Instant::now();
+        let mut last_activity_time = start_time;
+        let initial_timeout = torrent_clock(60); // 1 minute for first bytes
+        let stall_timeout = torrent_clock(300);  // 5 minutes for stalls
+
+        loop {
+            // Respect pause/cancel controls
+            if downloads::is_cancelled(&base_name) {
+                println!("[cache] Cancel requested for {} ({}:{})", track_id, source_type, source_hash);
+                partial.lock().unwrap().discard();
+                let mut inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
+                inflight.remove(&base_name);
+                let mut meta = INFLIGHT_META.lock().unwrap();
+                meta.remove(&base_name);
+                let message = downloads::note_error(&base_name, "cancelled");
+                if let Some(app_ref) = app.as_ref() {
                     let _ = app_ref.emit(
                         "cache:download:error",
                         serde_json::json!({
                             "trackId": track_id,
                             "sourceType": source_type,
                             "sourceHash": source_hash,
-                            "message": "cancelled"
+                            "message": message
                         }),
                     );
-                    downloads::clear_control(&base_name);
-                    return;
-                }
-                if downloads::is_paused(&base_name) {
-                    downloads::wait_while_paused_or_until_cancel(&base_name).await;
-                }
-                
-                // Determine available bytes and total via engine
-                let mut downloaded: u64 = match tokio::fs::metadata(&engine_path).await { Ok(m) => m.len(), Err(_) => 0 };
-                if let Ok(p) = tokio::task::spawn_blocking({
-                    let url_c = url.clone();
-                    move || engine.progress(&url_c, idx)
-                }).await.unwrap_or_else(|e| Err(format!("join error: {e}"))) {
-                    total_opt = Some(p.total);
-                    downloaded = p.bytes.max(downloaded);
-                }
-
-                // Check for progress to update activity time
-                if downloaded > last_progress_bytes {
-                    last_activity_time = std::time::Instant::now();
                 }
+                downloads::clear_control(&base_name);
+                return;
+            }
+            if downloads::is_paused(&base_name) {
+                downloads::wait_while_paused_or_until_cancel(&base_name).await;
+            }
+            
+            // Determine available bytes and total via engine
+            let mut downloaded: u64 = match tokio::fs::metadata(&engine_path).await { Ok(m) => m.len(), Err(_) => 0 };
+            if let Ok(p) = tokio::task::spawn_blocking({
+                let url_c = url.clone();
+                move || engine.progress(&url_c, idx)
+            }).await.unwrap_or_else(|e| Err(format!("join error: {e}"))) {
+                total_opt = Some(p.total);
+                downloaded = p.bytes.max(downloaded);
+            }
 
-                // Check for timeouts
-                let now = std::time::Instant::now();
-                let time_since_start = now.duration_since(start_time);
-                let time_since_activity = now.duration_since(last_activity_time);
-                
-                // Timeout if no initial progress within INITIAL_TIMEOUT or no activity for STALL_TIMEOUT
-                let has_initial_progress = downloaded > 0;
-                let should_timeout = if !has_initial_progress {
-                    time_since_start >= INITIAL_TIMEOUT
-                } else {
-                    time_since_activity >= STALL_TIMEOUT
-                };
-
-                if should_timeout {
-                    let timeout_reason = if !has_initial_progress {
-                        format!("No initial progress after {} seconds", INITIAL_TIMEOUT.as_secs())
-                    } else {
-                        format!("Download stalled for {} seconds at {:.2}MB", STALL_TIMEOUT.as_secs(), downloaded as f64 / (1024.0 * 1024.0))
-                    };
-                    
-                    println!("[cache] Torrent download timeout for {} ({}:{}): {}", track_id, source_type, source_hash, timeout_reason);
-                    
-                    // Clean up inflight tracking
-                    let mut inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
-                    inflight.remove(&base_name);
-                    let mut meta = INFLIGHT_META.lock().unwrap();
-                    meta.remove(&base_name);
-                    
-                    // Emit error to notify frontend
+            // Check for progress to update activity time
+            if downloaded > last_progress_bytes {
+                last_activity_time = std::time::Instant::now();
+            }
+
+            // Copy newly verified pieces; the rest of the engine file may be unwritten preallocation
+            if downloaded > last_mirror_bytes && last_mirror_time.elapsed() >= MIRROR_INTERVAL {
+                let mirror = tokio::task::spawn_blocking({
+                    let partial = partial.clone();
+                    let src = engine_path.clone();
+                    let url_c = url.clone();
+                    move || {
+                        let ranges = engine.verified_ranges(&url_c, idx)?;
+                        if let Some(t) = total_opt { partial.lock().unwrap().set_total(t); }
+                        partial::mirror(&partial, &src, &ranges).map_err(|e| e.to_string())
+                    }
+                }).await;
+                if let Ok(Err(e)) = mirror {
+                    println!("[cache] Failed to mirror torrent data into {:?}: {}", part_path, e);
+                }
+                last_mirror_time = std::time::Instant::now();
+                last_mirror_bytes = downloaded;
+            }
+
+            // Check for timeouts
+            let now = std::time::Instant::now();
+            let time_since_start = now.duration_since(start_time);
+            let time_since_activity = now.duration_since(last_activity_time);
+            
+            // Timeout if no initial progress within initial_timeout or no activity for stall_timeout
+            let has_initial_progress = downloaded > 0;
+            let should_timeout = if !has_initial_progress {
+                time_since_start >= initial_timeout
+            } else {
+                time_since_activity >= stall_timeout
+            };
+
+            if should_timeout {
+                let timeout_reason = if !has_initial_progress {
+                    format!("No initial progress after {} seconds", initial_timeout.as_secs())
+                } else {
+                    format!("Download stalled for {} seconds at {:.2}MB", stall_timeout.as_secs(), downloaded as f64 / (1024.0 * 1024.0))
+                };
+                
+                println!("[cache] Torrent download timeout for {} ({}:{}): {}", track_id, source_type, source_hash, timeout_reason);
+                
+                // Clean up inflight tracking
+                let mut inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
+                inflight.remove(&base_name);
+                let mut meta = INFLIGHT_META.lock().unwrap();
+                meta.remove(&base_name);
+                
+                // Emit error to notify frontend
+                let message = downloads::note_error(&base_name, format!("Torrent download timeout: {}", timeout_reason));
+                if let Some(app_ref) = app.as_ref() {
                     let _ = app_ref.emit(
                         "cache:download:error",
                         serde_json::json!({
                             "trackId": track_id,
                             "sourceType": source_type,
                             "sourceHash": source_hash,
-                            "message": format!("Torrent download timeout: {}", timeout_reason)
+                            "message": message
                         }),
                     );
-                    
-                    downloads::clear_control(&base_name);
-                    return;
                 }
+                
+                downloads::clear_control(&base_name);
+                return;
+            }
 
-                // Emit ready once we have enough prefix and it's valid audio; require a bit more than 1KB to reduce false positives
-                if !ready_emitted && downloaded >= 16 * 1024 {
-                    if let Ok(mut in_f) = tokio::fs::File::open(&engine_path).await {
-                        if in_f.seek(SeekFrom::Start(0)).await.is_ok() {
-                            let to_read = 8192usize.min(downloaded as usize);
-                            prefix_buf.resize(to_read, 0);
-                            if let Ok(n) = in_f.read(&mut prefix_buf).await {
-                                prefix_buf.truncate(n);
-                                if is_valid_audio_content(&prefix_buf) {
-                                    ready_emitted = true;
-                                    let tmp_path_str = engine_path.to_string_lossy().to_string();
+            // Emit ready once we have enough prefix and it's valid audio; require a bit more than 1KB to reduce false positives
+            if !ready_emitted && downloaded >= 16 * 1024 {
+                if let Ok(mut in_f) = tokio::fs::File::open(&engine_path).await {
+                    if in_f.seek(SeekFrom::Start(0)).await.is_ok() {
+                        let to_read = 8192usize.min(downloaded as usize);
+                        prefix_buf.resize(to_read, 0);
+                        if let Ok(n) = in_f.read(&mut prefix_buf).await {
+                            prefix_buf.truncate(n);
+                            if is_valid_audio_content(&prefix_buf) {
+                                ready_emitted = true;
+                                let tmp_path_str = engine_path.to_string_lossy().to_string();
+                                if let Some(app_ref) = app.as_ref() {
                                     let _ = app_ref.emit(
                                         "cache:download:ready",
                                         serde_json::json!({
@@ -1214,15 +2183,17 @@ pub async fn download_and_cache_audio(
                         }
                     }
                 }
+            }
 
-                // Throttled progress updates
-                let time_since_last = now.duration_since(last_progress_time);
-                let bytes_since_last = downloaded.saturating_sub(last_progress_bytes);
-                if time_since_last >= PROGRESS_TIME_THRESHOLD || bytes_since_last >= PROGRESS_BYTES_THRESHOLD {
-                    {
-                        let mut inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
-                        if let Some(v) = inflight.get_mut(&base_name) { v.0 = downloaded; v.1 = total_opt; }
-                    }
+            // Throttled progress updates
+            let time_since_last = now.duration_since(last_progress_time);
+            let bytes_since_last = downloaded.saturating_sub(last_progress_bytes);
+            if time_since_last >= PROGRESS_TIME_THRESHOLD || bytes_since_last >= PROGRESS_BYTES_THRESHOLD {
+                {
+                    let mut inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
+                    if let Some(v) = inflight.get_mut(&base_name) { v.0 = downloaded; v.1 = total_opt; }
+                }
+                if let Some(app_ref) = app.as_ref() {
                     let _ = app_ref.emit(
                         "cache:download:progress",
                         serde_json::json!({
@@ -1231,50 +2202,53 @@ pub async fn download_and_cache_audio(
                             "sourceHash": source_hash,
                             "bytes_downloaded": downloaded,
                             "total_bytes": total_opt,
+                            "limiter": crate::bandwidth::limiter_state(&source_type),
                             "inflight": true
                         }),
                     );
-                    last_progress_time = now;
-                    last_progress_bytes = downloaded;
                 }
+                last_progress_time = now;
+                last_progress_bytes = downloaded;
+            }
 
-                // Complete when engine reports full download, but guard against sparse preallocation:
-                // require a non-trivial tail AND non-zero samples in the middle before considering the file complete.
-                if let Some(t) = total_opt {
-                    if t > 0 && downloaded >= t {
-                        // Double-check the tail contains actual data; retry briefly if not
-                        let mut validated = false;
-                        for _ in 0..20 { // up to ~6s (20 * 300ms) of additional waiting
-                            if has_nontrivial_tail(&engine_path, total_opt).await {
-                                // Also verify that the middle of the file is not all zeros (sparse hole)
-                                if has_nonzero_middle_samples(&engine_path, Some(t)).await {
-                                    validated = true;
-                                    break;
-                                }
+            // Complete when engine reports full download, but guard against sparse preallocation:
+            // require a non-trivial tail AND non-zero samples in the middle before considering the file complete.
+            if let Some(t) = total_opt {
+                if t > 0 && downloaded >= t {
+                    // Double-check the tail contains actual data; retry briefly if not
+                    let mut validated = false;
+                    for _ in 0..20 { // up to ~6s (20 * 300ms) of additional waiting
+                        if has_nontrivial_tail(&engine_path, total_opt).await {
+                            // Also verify that the middle of the file is not all zeros (sparse hole)
+                            if has_nonzero_middle_samples(&engine_path, Some(t)).await {
+                                validated = true;
+                                break;
                             }
-                            tokio::time::sleep(std::time::Duration::from_millis(300)).await;
                         }
-                        if validated { break; }
+                        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
                     }
+                    if validated { break; }
                 }
-                tokio::time::sleep(std::time::Duration::from_millis(300)).await;
-            }
-            // Finalize: copy engine file into audio_cache with extensionless base name
-            // Validate prefix (be lenient for larger downloads)
-            if !is_valid_audio_content(&prefix_buf) {
-                println!("[cache] Torrent downloaded content did not pass validation for {} ({}:{}) - proceeding due to engine completion", track_id, source_type, source_hash);
-            }
-            // As an extra safety, ensure the file tail is non-trivial and we have non-zero samples in the middle
-            // before copying. This addresses cases where the engine preallocated size, writing start/end first
-            // and leaving zeros in the middle (sparse holes).
-            let expected_total = total_opt.unwrap_or(0);
-            if expected_total > 0 {
-                let tail_ok = has_nontrivial_tail(&engine_path, total_opt).await;
-                let middle_ok = has_nonzero_middle_samples(&engine_path, Some(expected_total)).await;
-                if !tail_ok || !middle_ok {
-                    println!("[cache] Torrent engine reported completion but tail validation failed for {} ({}:{}) - deferring finalization", track_id, source_type, source_hash);
-                    // Inform UI we're still inflight even though engine signaled completion once
-                    let downloaded_now = match tokio::fs::metadata(&engine_path).await { Ok(m) => Some(m.len()), Err(_) => None };
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        }
+        // Finalize: copy engine file into audio_cache with extensionless base name
+        // Validate prefix (be lenient for larger downloads)
+        if !is_valid_audio_content(&prefix_buf) {
+            println!("[cache] Torrent downloaded content did not pass validation for {} ({}:{}) - proceeding due to engine completion", track_id, source_type, source_hash);
+        }
+        // As an extra safety, ensure the file tail is non-trivial and we have non-zero samples in the middle
+        // before copying. This addresses cases where the engine preallocated size, writing start/end first
+        // and leaving zeros in the middle (sparse holes).
+        let expected_total = total_opt.unwrap_or(0);
+        if expected_total > 0 {
+            let tail_ok = has_nontrivial_tail(&engine_path, total_opt).await;
+            let middle_ok = has_nonzero_middle_samples(&engine_path, Some(expected_total)).await;
+            if !tail_ok || !middle_ok {
+                println!("[cache] Torrent engine reported completion but tail validation failed for {} ({}:{}) - deferring finalization", track_id, source_type, source_hash);
+                // Inform UI we're still inflight even though engine signaled completion once
+                let downloaded_now = match tokio::fs::metadata(&engine_path).await { Ok(m) => Some(m.len()), Err(_) => None };
+                if let Some(app_ref) = app.as_ref() {
                     let _ = app_ref.emit(
                         "cache:download:progress",
                         serde_json::json!({
@@ -1283,97 +2257,127 @@ pub async fn download_and_cache_audio(
                             "sourceHash": source_hash,
                             "bytes_downloaded": downloaded_now,
                             "total_bytes": total_opt,
+                            "limiter": crate::bandwidth::limiter_state(&source_type),
                             "inflight": true,
                             "reason": if !tail_ok { "waiting_for_tail_validation" } else { "waiting_for_middle_pieces" }
                         }),
                     );
-                    // Keep control so pause/cancel still works; do not copy to cache yet
-                    return;
                 }
-            }
-            // Ensure parent exists (already ensured by cache init)
-            if final_path.exists() {
-                let _ = tokio::fs::remove_file(&final_path).await;
-            }
-            if let Err(e) = tokio::fs::copy(&engine_path, &final_path).await {
-                println!("[cache] Failed to copy engine file {:?} -> {:?}: {}", engine_path, final_path, e);
-                downloads::clear_control(&base_name);
+                // Keep control so pause/cancel still works; do not copy to cache yet
                 return;
             }
-
-            // Get final size
-            let file_size = match tokio::fs::metadata(&final_path).await {
-                Ok(m) => m.len(),
-                Err(e) => {
-                    println!("[cache] Failed to stat finalized cache file {:?}: {}", final_path, e);
-                    // Attempt cleanup and abort
-                    let _ = tokio::fs::remove_file(&final_path).await;
-                    downloads::clear_control(&base_name);
-                    return;
-                }
-            };
-
-            // Add to cache index under lock; perform async cleanup after releasing the lock
-            let mut add_failed: Option<String> = None;
-            let cache_missing: bool;
-            {
-                let mut cache_guard = CACHE.lock().unwrap();
-                if let Some(cache) = cache_guard.as_mut() {
-                    if let Err(e) = cache.add_cached_file_with_index(
-                        track_id.clone(),
-                        source_type.clone(),
-                        source_hash.clone(),
-                        base_name.clone(),
-                        file_size,
-                        file_index,
-                    ) {
-                        add_failed = Some(e);
-                        cache_missing = false;
-                    } else {
-                        cache_missing = false;
-                    }
-                } else {
-                    cache_missing = true;
+        }
+        // Ensure parent exists (already ensured by cache init)
+        if final_path.exists() {
+            let _ = tokio::fs::remove_file(&final_path).await;
+        }
+        // Fill whatever the mirror hasn't picked up yet, then move the part file into place
+        let filled = tokio::task::spawn_blocking({
+            let partial = partial.clone();
+            let src = engine_path.clone();
+            move || {
+                let limit = match total_opt {
+                    Some(t) => t,
+                    None => std::fs::metadata(&src).map(|m| m.len())?,
+                };
+                partial.lock().unwrap().set_total(limit);
+                // The engine reported the whole file verified
+                partial::mirror(&partial, &src, &[(0, limit)])?;
+                let mut p = partial.lock().unwrap();
+                if !p.meta().is_complete() {
+                    return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "engine file shorter than expected"));
                 }
+                p.finish();
+                Ok(())
             }
-            if let Some(e) = add_failed {
-                println!(
-                    "[cache] Failed to add torrent-cached file to index for {} ({}:{}): {}",
-                    track_id, source_type, source_hash, e
-                );
-                let _ = tokio::fs::remove_file(&final_path).await;
-                downloads::clear_control(&base_name);
-                return;
-            }
-            if cache_missing {
-                println!("[cache] Cache not initialized while finalizing torrent cache for {}", track_id);
+        }).await.unwrap_or_else(|e| Err(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())));
+        if let Err(e) = filled {
+            println!("[cache] Failed to copy engine file {:?} -> {:?}: {}", engine_path, part_path, e);
+            downloads::clear_control(&base_name);
+            return;
+        }
+        drop(partial);
+        if let Err(e) = tokio::fs::rename(&part_path, &final_path).await {
+            println!("[cache] Failed to move torrent part file {:?} -> {:?}: {}", part_path, final_path, e);
+            downloads::clear_control(&base_name);
+            return;
+        }
+
+        // Get final size
+        let file_size = match tokio::fs::metadata(&final_path).await {
+            Ok(m) => m.len(),
+            Err(e) => {
+                println!("[cache] Failed to stat finalized cache file {:?}: {}", final_path, e);
+                // Attempt cleanup and abort
                 let _ = tokio::fs::remove_file(&final_path).await;
                 downloads::clear_control(&base_name);
                 return;
             }
+        };
 
-            // Send completion notification via channel
-            let cached_path = final_path.to_string_lossy().to_string();
-            let result = CacheDownloadResult {
-                track_id: track_id.clone(),
-                source_type: source_type.clone(),
-                source_hash: source_hash.clone(),
-                cached_path: cached_path.clone(),
-                file_size,
-            };
-            if let Err(e) = tx.send(result) {
-                println!(
-                    "[cache] Failed to send cache completion notification for {} ({}:{}): {}",
-                    track_id, source_type, source_hash, e
-                );
+        // Add to cache index under lock; perform async cleanup after releasing the lock
+        let mut add_failed: Option<String> = None;
+        let cache_missing: bool;
+        {
+            let mut cache_guard = CACHE.lock().unwrap();
+            if let Some(cache) = cache_guard.as_mut() {
+                if let Err(e) = cache.add_cached_file_with_index(
+                    track_id.clone(),
+                    source_type.clone(),
+                    source_hash.clone(),
+                    base_name.clone(),
+                    file_size,
+                    file_index,
+                ) {
+                    add_failed = Some(e);
+                    cache_missing = false;
+                } else {
+                    cache_missing = false;
+                }
             } else {
-                println!(
-                    "[cache] Cache download completed (engine) for {} ({}:{}) -> {}",
-                    track_id, source_type, source_hash, cached_path
-                );
+                cache_missing = true;
             }
+        }
+        if let Some(e) = add_failed {
+            println!(
+                "[cache] Failed to add torrent-cached file to index for {} ({}:{}): {}",
+                track_id, source_type, source_hash, e
+            );
+            let _ = tokio::fs::remove_file(&final_path).await;
+            downloads::clear_control(&base_name);
+            return;
+        }
+        if cache_missing {
+            println!("[cache] Cache not initialized while finalizing torrent cache for {}", track_id);
+            let _ = tokio::fs::remove_file(&final_path).await;
+            downloads::clear_control(&base_name);
+            return;
+        }
+        on_entry_cached(&track_id, &source_type, &source_hash, file_index);
+
+        // Send completion notification via channel
+        let cached_path = final_path.to_string_lossy().to_string();
+        let result = CacheDownloadResult {
+            track_id: track_id.clone(),
+            source_type: source_type.clone(),
+            source_hash: source_hash.clone(),
+            cached_path: cached_path.clone(),
+            file_size,
+        };
+        if let Err(e) = tx.send(result) {
+            println!(
+                "[cache] Failed to send cache completion notification for {} ({}:{}): {}",
+                track_id, source_type, source_hash, e
+            );
+        } else {
+            println!(
+                "[cache] Cache download completed (engine) for {} ({}:{}) -> {}",
+                track_id, source_type, source_hash, cached_path
+            );
+        }
 
-            // Emit completion event
+        // Emit completion event
+        if let Some(app_ref) = app.as_ref() {
             let _ = app_ref.emit(
                 "cache:download:complete",
                 serde_json::json!({
@@ -1384,29 +2388,30 @@ pub async fn download_and_cache_audio(
                     "fileSize": file_size
                 }),
             );
+        }
 
-            // Clear inflight bookkeeping
-            {
-                let mut inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
-                inflight.remove(&base_name);
-                let mut meta = INFLIGHT_META.lock().unwrap();
-                meta.remove(&base_name);
-            }
+        // Clear inflight bookkeeping
+        {
+            let mut inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
+            inflight.remove(&base_name);
+            let mut meta = INFLIGHT_META.lock().unwrap();
+            meta.remove(&base_name);
+        }
 
-            // Clear control flag
-            downloads::clear_control(&base_name);
+        // Clear control flag
+        downloads::clear_control(&base_name);
 
-            // Best-effort cleanup: defer removal of torrent data to avoid races with playback or copy operations
-            // We schedule a delayed cleanup that verifies the cached file exists before attempting to delete engine data.
-            // Prefer the engine-reported total size if available for a stronger stability check
-            schedule_torrent_cleanup(
-                url.clone(),
-                engine_path.clone(),
-                final_path.clone(),
-                total_opt,
-            );
-            return;
-        }
+        // Hand the torrent to the seeding policy. With seeding off this is the old deferred
+        // cleanup, which verifies the cached file exists before deleting engine data.
+        // Prefer the engine-reported total size if available for a stronger stability check
+        crate::seeding::on_torrent_cached(
+            url.clone(),
+            idx,
+            engine_path.clone(),
+            final_path.clone(),
+            total_opt,
+        );
+        return;
     }
 
     // Resolve the provided URL to a direct download URL when possible. This avoids
@@ -1436,132 +2441,18 @@ pub async fn download_and_cache_audio(
         }
     };
 
-    // Build a reqwest client tuned for long-running media downloads:
-    // - No global request timeout (downloads can be long); use a short connect timeout instead
-    // - Disable automatic body decompression to avoid "error decoding response body" when servers mislabel encodings
-    let client = reqwest::Client::builder()
-        .connect_timeout(std::time::Duration::from_secs(10))
-        .tcp_nodelay(true)
-        .tcp_keepalive(Some(std::time::Duration::from_secs(30)))
-        .pool_max_idle_per_host(1)
-        .gzip(false)
-        .brotli(false)
+    // Media client: no overall timeout and no body decompression (see http::media_builder)
+    let client = match crate::http::media_builder(crate::proxy::ProxySubsystem::Downloads)
+        .pool_max_idle_per_host(SEGMENT_MAX_CONNECTIONS)
         .build()
-        .unwrap();
-
-    // Issue request (logging already done above)
-
-    // No localhost server dependency. We avoid the old localhost:9000 flow entirely.
-
-    // Issue request with explicit identity encoding to receive raw bytes as-is
-    // Build request with optional YouTube-specific headers to encourage fast direct CDN responses
-    let mut req = client
-        .get(&resolved)
-        .header("Accept-Encoding", "identity")
-        .header(
-            "User-Agent",
-            // Stable UA to reduce chance of odd server responses
-            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/118.0.0.0 Safari/537.36",
-        );
-
-    let is_youtube_cdn = resolved.contains("googlevideo.com");
-    if is_youtube_cdn {
-        // Many YouTube CDN endpoints deliver faster when using Range requests
-        // Also include Referer/Origin to match browser-like requests
-        req = req
-            .header("Range", "bytes=0-")
-            .header("Connection", "keep-alive")
-            .header("Origin", "https://www.youtube.com")
-            .header("Referer", "https://www.youtube.com/");
-    }
-
-    let resp = match req.send().await {
-        Ok(r) => {
-            println!("[cache] HTTP request successful, status: {}", r.status());
-            r
-        }
+    {
+        Ok(c) => c,
         Err(e) => {
-            println!(
-                "[cache] Download request failed for {} ({}:{}): {}",
-                track_id, source_type, source_hash, e
-            );
-            if let Some(app_ref) = app.as_ref() {
-                let _ = app_ref.emit(
-                    "cache:download:error",
-                    serde_json::json!({
-                        "trackId": track_id,
-                        "sourceType": source_type,
-                        "sourceHash": source_hash,
-                        "message": format!("Request failed: {}", e)
-                    }),
-                );
-            }
+            println!("[cache] Failed to build HTTP client: {}", e);
             return;
         }
     };
 
-    // Handle special case for torrents: 202 means file is downloading but not ready yet
-    if resp.status() == 202 && source_type == "torrent" {
-        println!(
-            "[cache] Torrent file not ready yet (HTTP 202), will retry later for {} ({}:{})",
-            track_id, source_type, source_hash
-        );
-
-        // Try to get progress information from the response
-        if let Ok(response_text) = resp.text().await {
-            if let Ok(response_json) = serde_json::from_str::<serde_json::Value>(&response_text) {
-                if let Some(progress) = response_json.get("progress").and_then(|p| p.as_f64()) {
-                    println!(
-                        "[cache] Torrent file download progress: {:.2}%",
-                        progress * 100.0
-                    );
-                }
-            }
-        }
-
-        // Emit a specific event for torrent download in progress
-        if let Some(app_ref) = app.as_ref() {
-            let _ = app_ref.emit(
-                "cache:download:progress",
-                serde_json::json!({
-                    "trackId": track_id,
-                    "sourceType": source_type,
-                    "sourceHash": source_hash,
-                    "message": "Torrent file downloading, please wait...",
-                    "status": "downloading"
-                }),
-            );
-        }
-        return;
-    }
-
-    if !resp.status().is_success() {
-        println!(
-            "[cache] Download failed with status {} for {} ({}:{})",
-            resp.status(),
-            track_id,
-            source_type,
-            source_hash
-        );
-        if let Some(app_ref) = app.as_ref() {
-            let _ = app_ref.emit(
-                "cache:download:error",
-                serde_json::json!({
-                    "trackId": track_id,
-                    "sourceType": source_type,
-                    "sourceHash": source_hash,
-                    "message": format!("HTTP status {}", resp.status())
-                }),
-            );
-        }
-        return;
-    }
-    // Use shared helper for consistent base name
-    let base_name =
-        create_cache_filename_with_index(&track_id, &source_type, &source_hash, file_index);
-    // Ensure we have a control handle for this download
-    downloads::ensure_control_for(&base_name);
-
     // We'll write to a .part file and then rename to final (extension-less) name on success
     let cache_file_name = format!("{}.part", base_name);
 
@@ -1582,78 +2473,12 @@ pub async fn download_and_cache_audio(
     let cache_path = cache_dir.join(&cache_file_name);
     let final_path = cache_dir.join(&base_name);
 
-    // Stream response into part file while collecting initial bytes for validation
-    // Capture content length before consuming the response
-    let content_len_opt = resp.content_length();
-
-    // Log a few headers to aid diagnostics when downloads fail
-    let ct = resp
-        .headers()
-        .get("content-type")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
-    let ce = resp
-        .headers()
-        .get("content-encoding")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
-    let te = resp
-        .headers()
-        .get("transfer-encoding")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
-    println!(
-        "[cache] Response headers: content-type='{}', content-encoding='{}', transfer-encoding='{}', content-length={:?}",
-        ct, ce, te, content_len_opt
-    );
-
-    // Check for custom X-Expected-Length header for torrents using chunked encoding
-    let expected_length_opt = resp
-        .headers()
-        .get("X-Expected-Length")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|s| s.parse::<u64>().ok());
-
-    // Attempt to parse YouTube's 'clen' query parameter as a total size hint if needed
-    let clen_from_url: Option<u64> = {
-        if let Some(pos) = resolved.find("clen=") {
-            let digits = resolved[pos + 5..]
-                .chars()
-                .take_while(|c| c.is_ascii_digit())
-                .collect::<String>();
-            if !digits.is_empty() {
-                digits.parse::<u64>().ok()
-            } else {
-                None
-            }
-        } else {
-            None
-        }
-    };
-
-    // Use expected length if content length is not available (for chunked transfers)
-    let total_size_opt = content_len_opt.or(expected_length_opt).or(clen_from_url);
-
-    if source_type == "torrent" && content_len_opt.is_none() && expected_length_opt.is_some() {
-        println!(
-            "[cache] Using expected length {} for chunked torrent transfer {} ({}:{})",
-            expected_length_opt.unwrap(),
-            track_id,
-            source_type,
-            source_hash
-        );
-    }
-
-    let mut stream = resp.bytes_stream();
-
-    // Open async file for writing with buffering (64KB buffer for better I/O performance)
-    let mut file = match tokio_fs::File::create(&cache_path).await {
-        Ok(f) => BufWriter::with_capacity(256 * 1024, f), // 256KB buffer for better throughput
+    // Open the part file together with its range map. Bytes that an earlier session or the
+    // playback callback already stored are never requested or written again.
+    let partial = match partial::open(&cache_path, &track_id, &source_type, &source_hash, file_index) {
+        Ok(p) => p,
         Err(e) => {
-            println!(
-                "[cache] Failed to create cache file {:?}: {}",
-                cache_path, e
-            );
+            println!("[cache] {} {:?}", e, cache_path);
             if let Some(app_ref) = app.as_ref() {
                 let _ = app_ref.emit(
                     "cache:download:error",
@@ -1661,21 +2486,43 @@ pub async fn download_and_cache_audio(
                         "trackId": track_id,
                         "sourceType": source_type,
                         "sourceHash": source_hash,
-                        "message": format!("Failed to create cache file: {}", e)
+                        "message": downloads::note_error(&base_name, format!("Failed to create cache file: {}", e))
                     }),
                 );
             }
             return;
         }
     };
+    {
+        let p = partial.lock().unwrap();
+        let covered = p.meta().covered();
+        if covered > 0 {
+            println!(
+                "[cache] Resuming partial download for {} ({}:{}): {} bytes in {} range(s) already on disk",
+                track_id,
+                source_type,
+                source_hash,
+                covered,
+                p.meta().ranges.len()
+            );
+        }
+    }
+
+    let is_youtube_cdn = resolved.contains("googlevideo.com");
 
     const VALIDATION_BYTES: usize = 8192; // collect up to 8KB for validation
+    // Don't abandon an open-ended response for already-present runs shorter than this
+    const SKIP_PRESENT_MIN: u64 = 1024 * 1024;
     let mut prefix_buf: Vec<u8> = Vec::with_capacity(VALIDATION_BYTES);
-    let mut total_written: u64 = 0;
+    let mut total_written: u64 = partial.lock().unwrap().meta().covered();
+    let mut total_size_opt: Option<u64> = partial.lock().unwrap().meta().total;
     let mut ready_emitted = false;
+    let mut inflight_marked = false;
+    // Whether the last response body ran to its end (as opposed to an error/early stop)
+    let mut stream_ended = false;
     #[allow(unused_assignments)]
     let mut download_complete = false; // Track if download completed successfully
-    
+
     // Progress event throttling variables
     let mut last_progress_time = std::time::Instant::now();
     let mut last_progress_bytes = 0u64;
@@ -1684,235 +2531,589 @@ pub async fn download_and_cache_audio(
 
     use futures_util::StreamExt;
 
-    // Mark inflight
-    {
-        let mut inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
-        inflight.insert(base_name.clone(), (0u64, total_size_opt));
-        let mut meta = INFLIGHT_META.lock().unwrap();
-        meta.insert(
-            base_name.clone(),
-            (track_id.clone(), source_type.clone(), source_hash.clone()),
-        );
+    // Split big files across extra connections when the server supports ranges. The sequential
+    // loop below keeps the first `head_limit` bytes so playback and validation get them first.
+    let mut segment_workers = SegmentWorkers(Vec::new());
+    let mut head_limit: Option<u64> = None;
+    if source_type != "torrent" {
+        if let Some(total) = probe_range_total(&client, &resolved, is_youtube_cdn).await {
+            let gaps = {
+                let mut p = partial.lock().unwrap();
+                p.set_total(total);
+                p.meta().uncovered(0, total)
+            };
+            total_size_opt = Some(total);
+            let missing: u64 = gaps.iter().map(|&(s, e)| e - s).sum();
+            if total >= SEGMENT_MIN_TOTAL && missing >= 2 * SEGMENT_MIN_LEN {
+                let head_start = gaps.first().map(|&(s, _)| s).unwrap_or(0);
+                let limit = head_start + (missing / SEGMENT_MAX_CONNECTIONS as u64).max(SEGMENT_MIN_LEN);
+                let plan = plan_segments(&gaps, limit, SEGMENT_MAX_CONNECTIONS - 1);
+                println!(
+                    "[cache] Segmented download for {} ({}:{}): head up to {} plus {} parallel segment(s)",
+                    track_id, source_type, source_hash, limit, plan.len()
+                );
+                downloads::ensure_control_for(&base_name);
+                for ranges in plan {
+                    segment_workers.0.push(tokio::spawn(fetch_segment(
+                        client.clone(),
+                        resolved.clone(),
+                        is_youtube_cdn,
+                        partial.clone(),
+                        ranges,
+                        source_type.clone(),
+                        base_name.clone(),
+                    )));
+                }
+                if !segment_workers.is_empty() {
+                    head_limit = Some(limit);
+                }
+            }
+        }
     }
 
-    // Emit initial progress event
-    if let Some(app_ref) = app.as_ref() {
-        let _ = app_ref.emit(
-            "cache:download:progress",
-            serde_json::json!({
-                "trackId": track_id,
-                "sourceType": source_type,
-                "sourceHash": source_hash,
-                "bytes_downloaded": 0u64,
-                "total_bytes": total_size_opt,
-                "inflight": true
-            }),
+    // One request per missing range; a server that ignores Range gets a single full pass
+    'fetch: loop {
+        let (gap_start, gap_end) = match partial.lock().unwrap().meta().next_gap() {
+            Some(gap) => gap,
+            None => break 'fetch,
+        };
+        // While segment workers run, the sequential loop only covers the head; once that is
+        // done it waits for them and then sweeps up whatever they could not fetch
+        let gap_end = match head_limit {
+            Some(limit) if gap_start >= limit => {
+                total_written = segment_workers
+                    .wait(&partial, app.as_ref(), (&track_id, &source_type, &source_hash), &base_name)
+                    .await;
+                head_limit = None;
+                continue 'fetch;
+            }
+            Some(limit) => Some(gap_end.map_or(limit, |end| end.min(limit))),
+            None => gap_end,
+        };
+
+        // No localhost server dependency. We avoid the old localhost:9000 flow entirely.
+
+        // Issue request with explicit identity encoding to receive raw bytes as-is
+        // Build request with optional YouTube-specific headers to encourage fast direct CDN responses
+        let mut req = media_request(&client, &resolved, is_youtube_cdn);
+
+        if gap_start > 0 || gap_end.is_some() {
+            let range = match gap_end {
+                Some(end) => format!("bytes={}-{}", gap_start, end - 1),
+                None => format!("bytes={}-", gap_start),
+            };
+            req = req.header("Range", range);
+        } else if is_youtube_cdn {
+            // Many YouTube CDN endpoints deliver faster when using Range requests
+            req = req.header("Range", "bytes=0-");
+        }
+
+        // The head is what playback waits on, so it doesn't queue behind the segment workers
+        let sent = if head_limit.is_some() {
+            crate::http::send_unthrottled(req).await.map(|r| (r, None))
+        } else {
+            crate::http::send_held(req).await.map(|(r, slot)| (r, Some(slot)))
+        };
+        let (resp, _slot) = match sent {
+            Ok((r, slot)) => {
+                println!("[cache] HTTP request successful, status: {}", r.status());
+                (r, slot)
+            }
+            Err(e) => {
+                println!(
+                    "[cache] Download request failed for {} ({}:{}): {}",
+                    track_id, source_type, source_hash, e
+                );
+                if let Some(app_ref) = app.as_ref() {
+                    let _ = app_ref.emit(
+                        "cache:download:error",
+                        serde_json::json!({
+                            "trackId": track_id,
+                            "sourceType": source_type,
+                            "sourceHash": source_hash,
+                            "message": downloads::note_error(&base_name, format!("Request failed: {}", e))
+                        }),
+                    );
+                }
+                if inflight_marked {
+                    let mut inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
+                    inflight.remove(&base_name);
+                    let mut meta = INFLIGHT_META.lock().unwrap();
+                    meta.remove(&base_name);
+                    downloads::clear_control(&base_name);
+                }
+                return;
+            }
+        };
+
+        // Handle special case for torrents: 202 means file is downloading but not ready yet
+        if resp.status() == 202 && source_type == "torrent" {
+            println!(
+                "[cache] Torrent file not ready yet (HTTP 202), will retry later for {} ({}:{})",
+                track_id, source_type, source_hash
+            );
+
+            // Try to get progress information from the response
+            if let Ok(response_text) = resp.text().await {
+                if let Ok(response_json) = serde_json::from_str::<serde_json::Value>(&response_text) {
+                    if let Some(progress) = response_json.get("progress").and_then(|p| p.as_f64()) {
+                        println!(
+                            "[cache] Torrent file download progress: {:.2}%",
+                            progress * 100.0
+                        );
+                    }
+                }
+            }
+
+            // Emit a specific event for torrent download in progress
+            if let Some(app_ref) = app.as_ref() {
+                let _ = app_ref.emit(
+                    "cache:download:progress",
+                    serde_json::json!({
+                        "trackId": track_id,
+                        "sourceType": source_type,
+                        "sourceHash": source_hash,
+                        "message": "Torrent file downloading, please wait...",
+                        "status": "downloading"
+                    }),
+                );
+            }
+            if inflight_marked {
+                let mut inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
+                inflight.remove(&base_name);
+                let mut meta = INFLIGHT_META.lock().unwrap();
+                meta.remove(&base_name);
+                downloads::clear_control(&base_name);
+            }
+            return;
+        }
+
+        // Asking for bytes past the end means everything up to the gap start is the whole file
+        if resp.status() == 416 && gap_start > 0 && gap_end.is_none() {
+            println!(
+                "[cache] Server reports range {}- unsatisfiable; treating {} bytes as the full file for {} ({}:{})",
+                gap_start, gap_start, track_id, source_type, source_hash
+            );
+            partial.lock().unwrap().set_total(gap_start);
+            total_size_opt = Some(gap_start);
+            stream_ended = true;
+            break 'fetch;
+        }
+
+        if !resp.status().is_success() {
+            println!(
+                "[cache] Download failed with status {} for {} ({}:{})",
+                resp.status(),
+                track_id,
+                source_type,
+                source_hash
+            );
+            if let Some(app_ref) = app.as_ref() {
+                let _ = app_ref.emit(
+                    "cache:download:error",
+                    serde_json::json!({
+                        "trackId": track_id,
+                        "sourceType": source_type,
+                        "sourceHash": source_hash,
+                        "message": downloads::note_error(&base_name, format!("HTTP status {}", resp.status()))
+                    }),
+                );
+            }
+            if inflight_marked {
+                let mut inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
+                inflight.remove(&base_name);
+                let mut meta = INFLIGHT_META.lock().unwrap();
+                meta.remove(&base_name);
+                downloads::clear_control(&base_name);
+            }
+            return;
+        }
+        // Ensure we have a control handle for this download
+        downloads::ensure_control_for(&base_name);
+
+        // Stream response into part file while collecting initial bytes for validation
+        // Capture content length before consuming the response
+        let content_len_opt = resp.content_length();
+
+        // Log a few headers to aid diagnostics when downloads fail
+        let ct = resp
+            .headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("");
+        let ce = resp
+            .headers()
+            .get("content-encoding")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("");
+        let te = resp
+            .headers()
+            .get("transfer-encoding")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("");
+        println!(
+            "[cache] Response headers: content-type='{}', content-encoding='{}', transfer-encoding='{}', content-length={:?}",
+            ct, ce, te, content_len_opt
         );
-    }
 
-    // Removed obsolete localhost polling. Torrent progress is emitted directly by the engine branch.
+        // A 206 carries "Content-Range: bytes <start>-<end>/<total>"
+        let is_range_response = resp.status() == 206;
+        let content_range = resp
+            .headers()
+            .get("content-range")
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_content_range);
+
+        // Check for custom X-Expected-Length header for torrents using chunked encoding
+        let expected_length_opt = resp
+            .headers()
+            .get("X-Expected-Length")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|s| s.parse::<u64>().ok());
+
+        // Attempt to parse YouTube's 'clen' query parameter as a total size hint if needed
+        let clen_from_url: Option<u64> = {
+            if let Some(pos) = resolved.find("clen=") {
+                let digits = resolved[pos + 5..]
+                    .chars()
+                    .take_while(|c| c.is_ascii_digit())
+                    .collect::<String>();
+                if !digits.is_empty() {
+                    digits.parse::<u64>().ok()
+                } else {
+                    None
+                }
+            } else {
+                None
+            }
+        };
+
+        // Use expected length if content length is not available (for chunked transfers)
+        let response_total = if is_range_response {
+            content_range.and_then(|(_, total)| total).or(clen_from_url)
+        } else {
+            content_len_opt.or(expected_length_opt).or(clen_from_url)
+        };
+        if total_size_opt.is_none() {
+            total_size_opt = response_total;
+        }
+        if let Some(total) = total_size_opt {
+            partial.lock().unwrap().set_total(total);
+        }
 
-    while let Some(item) = stream.next().await {
-        // Respect pause/cancel controls
-        if downloads::is_cancelled(&base_name) {
+        if source_type == "torrent" && content_len_opt.is_none() && expected_length_opt.is_some() {
             println!(
-                "[cache] Cancel requested for {} ({}:{})",
-                track_id, source_type, source_hash
+                "[cache] Using expected length {} for chunked torrent transfer {} ({}:{})",
+                expected_length_opt.unwrap(),
+                track_id,
+                source_type,
+                source_hash
             );
-            let _ = tokio_fs::remove_file(&cache_path).await;
-            let mut inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
-            inflight.remove(&base_name);
+        }
+
+        // Absolute file offset of the first byte in this response
+        let mut pos: u64 = if is_range_response {
+            content_range.map(|(start, _)| start).unwrap_or(gap_start)
+        } else {
+            if gap_start > 0 {
+                println!(
+                    "[cache] Server ignored Range for {} ({}:{}); re-reading from start and skipping {} bytes already on disk",
+                    track_id, source_type, source_hash, gap_start
+                );
+            }
+            0
+        };
+        let written_before = total_written;
+
+        // Mark inflight
+        if !inflight_marked {
+            inflight_marked = true;
+            {
+                let mut inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
+                inflight.insert(base_name.clone(), (total_written, total_size_opt));
+                let mut meta = INFLIGHT_META.lock().unwrap();
+                meta.insert(
+                    base_name.clone(),
+                    (track_id.clone(), source_type.clone(), source_hash.clone()),
+                );
+            }
+
+            // Emit initial progress event
             if let Some(app_ref) = app.as_ref() {
                 let _ = app_ref.emit(
-                    "cache:download:error",
+                    "cache:download:progress",
                     serde_json::json!({
                         "trackId": track_id,
                         "sourceType": source_type,
                         "sourceHash": source_hash,
-                        "message": "cancelled"
+                        "bytes_downloaded": total_written,
+                        "total_bytes": total_size_opt,
+                        "limiter": crate::bandwidth::limiter_state(&source_type),
+                        "inflight": true
                     }),
                 );
             }
-            // Clear control state
-            downloads::clear_control(&base_name);
-            return;
         }
-        // If paused, wait until resumed or cancelled
-        if downloads::is_paused(&base_name) {
-            downloads::wait_while_paused_or_until_cancel(&base_name).await;
+
+        let mut stream = resp.bytes_stream();
+        stream_ended = true;
+
+        // Removed obsolete localhost polling. Torrent progress is emitted directly by the engine branch.
+
+        while let Some(item) = stream.next().await {
+            // Respect pause/cancel controls
             if downloads::is_cancelled(&base_name) {
-                continue;
+                println!(
+                    "[cache] Cancel requested for {} ({}:{})",
+                    track_id, source_type, source_hash
+                );
+                partial.lock().unwrap().discard();
+                let mut inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
+                inflight.remove(&base_name);
+                if let Some(app_ref) = app.as_ref() {
+                    let _ = app_ref.emit(
+                        "cache:download:error",
+                        serde_json::json!({
+                            "trackId": track_id,
+                            "sourceType": source_type,
+                            "sourceHash": source_hash,
+                            "message": downloads::note_error(&base_name, "cancelled")
+                        }),
+                    );
+                }
+                // Clear control state
+                downloads::clear_control(&base_name);
+                return;
             }
-        }
-        match item {
-            Ok(chunk) => {
-                // Collect into prefix buffer until we have enough
-                if prefix_buf.len() < VALIDATION_BYTES {
-                    let need = VALIDATION_BYTES - prefix_buf.len();
-                    let to_take = std::cmp::min(need, chunk.len());
-                    prefix_buf.extend_from_slice(&chunk[..to_take]);
+            // If paused, wait until resumed or cancelled
+            if downloads::is_paused(&base_name) {
+                downloads::wait_while_paused_or_until_cancel(&base_name).await;
+                if downloads::is_cancelled(&base_name) {
+                    continue;
                 }
+            }
+            match item {
+                Ok(chunk) => {
+                    // Collect into prefix buffer until we have enough
+                    if pos == prefix_buf.len() as u64 && prefix_buf.len() < VALIDATION_BYTES {
+                        let need = VALIDATION_BYTES - prefix_buf.len();
+                        let to_take = std::cmp::min(need, chunk.len());
+                        prefix_buf.extend_from_slice(&chunk[..to_take]);
+                    }
 
-                // Write chunk to part file
-                if let Err(e) = file.write_all(&chunk).await {
-                    println!(
-                        "[cache] Failed to write to part file {:?}: {}",
-                        cache_path, e
-                    );
-                    let _ = tokio_fs::remove_file(&cache_path).await;
-                    // clear inflight
-                    let mut inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
-                    inflight.remove(&base_name);
-                    let mut meta = INFLIGHT_META.lock().unwrap();
-                    meta.remove(&base_name);
-                    if let Some(app_ref) = app.as_ref() {
-                        let _ = app_ref.emit(
-                            "cache:download:error",
-                            serde_json::json!({
-                                "trackId": track_id,
-                                "sourceType": source_type,
-                                "sourceHash": source_hash,
-                                "message": format!("Write failed: {}", e)
-                            }),
-                        );
+                    // Write the missing parts of this chunk into the part file
+                    let write_result = write_part(&partial, pos, chunk.to_vec()).await.map(|_| {
+                        let p = partial.lock().unwrap();
+                        (p.meta().covered(), p.meta().run_end_at(pos + chunk.len() as u64))
+                    });
+                    let present_run_end = match write_result {
+                        Ok((covered, run_end)) => {
+                            total_written = covered;
+                            run_end
+                        }
+                        Err(e) => {
+                            println!(
+                                "[cache] Failed to write to part file {:?}: {}",
+                                cache_path, e
+                            );
+                            partial.lock().unwrap().discard();
+                            // clear inflight
+                            let mut inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
+                            inflight.remove(&base_name);
+                            let mut meta = INFLIGHT_META.lock().unwrap();
+                            meta.remove(&base_name);
+                            if let Some(app_ref) = app.as_ref() {
+                                let _ = app_ref.emit(
+                                    "cache:download:error",
+                                    serde_json::json!({
+                                        "trackId": track_id,
+                                        "sourceType": source_type,
+                                        "sourceHash": source_hash,
+                                        "message": downloads::note_error(&base_name, format!("Write failed: {}", e))
+                                    }),
+                                );
+                            }
+                            // Clear control state on error
+                            downloads::clear_control(&base_name);
+                            return;
+                        }
+                    };
+                    pos = pos.saturating_add(chunk.len() as u64);
+                    crate::bandwidth::throttle(&source_type, &base_name, chunk.len()).await;
+
+                    // Emit a "ready" event as soon as we have enough validated prefix bytes
+                    if !ready_emitted && prefix_buf.len() >= 1024 {
+                        if is_valid_audio_content(&prefix_buf) {
+                            ready_emitted = true;
+                            if let Some(app_ref) = app.as_ref() {
+                                let tmp_path_str = cache_path.to_string_lossy().to_string();
+                                let _ = app_ref.emit(
+                                    "cache:download:ready",
+                                    serde_json::json!({
+                                        "trackId": track_id,
+                                        "sourceType": source_type,
+                                        "sourceHash": source_hash,
+                                        "tmpPath": tmp_path_str,
+                                        "bytes_downloaded": total_written,
+                                        "total_bytes": total_size_opt,
+                                        "inflight": true
+                                    }),
+                                );
+                            }
+                        }
                     }
-                    // Clear control state on error
-                    downloads::clear_control(&base_name);
-                    return;
-                }
-                total_written = total_written.saturating_add(chunk.len() as u64);
-                
-                // Emit a "ready" event as soon as we have enough validated prefix bytes
-                if !ready_emitted && prefix_buf.len() >= 1024 {
-                    if is_valid_audio_content(&prefix_buf) {
-                        ready_emitted = true;
+
+                    // Throttled progress and inflight updates - only update if enough time/bytes have passed
+                    let now = std::time::Instant::now();
+                    let time_since_last = now.duration_since(last_progress_time);
+                    let bytes_since_last = total_written.saturating_sub(last_progress_bytes);
+
+                    if time_since_last >= PROGRESS_TIME_THRESHOLD || bytes_since_last >= PROGRESS_BYTES_THRESHOLD {
+                        // Update inflight bytes
+                        {
+                            let mut inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
+                            if let Some(v) = inflight.get_mut(&base_name) {
+                                v.0 = total_written;
+                                v.1 = total_size_opt;
+                            }
+                        }
+
+                        // emit progress event
                         if let Some(app_ref) = app.as_ref() {
-                            let tmp_path_str = cache_path.to_string_lossy().to_string();
                             let _ = app_ref.emit(
-                                "cache:download:ready",
+                                "cache:download:progress",
                                 serde_json::json!({
                                     "trackId": track_id,
                                     "sourceType": source_type,
                                     "sourceHash": source_hash,
-                                    "tmpPath": tmp_path_str,
                                     "bytes_downloaded": total_written,
                                     "total_bytes": total_size_opt,
+                                    "limiter": crate::bandwidth::limiter_state(&source_type),
                                     "inflight": true
                                 }),
                             );
                         }
+
+                        // Update throttling state
+                        last_progress_time = now;
+                        last_progress_bytes = total_written;
                     }
-                }
 
-                // Throttled progress and inflight updates - only update if enough time/bytes have passed
-                let now = std::time::Instant::now();
-                let time_since_last = now.duration_since(last_progress_time);
-                let bytes_since_last = total_written.saturating_sub(last_progress_bytes);
-                
-                if time_since_last >= PROGRESS_TIME_THRESHOLD || bytes_since_last >= PROGRESS_BYTES_THRESHOLD {
-                    // Update inflight bytes
-                    {
-                        let mut inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
-                        if let Some(v) = inflight.get_mut(&base_name) {
-                            v.0 = total_written;
+                    // A bounded range is done once its end is reached
+                    if let Some(end) = gap_end {
+                        if pos >= end {
+                            break;
                         }
                     }
-                    
-                    // emit progress event
-                    if let Some(app_ref) = app.as_ref() {
-                        let _ = app_ref.emit(
-                            "cache:download:progress",
-                            serde_json::json!({
-                                "trackId": track_id,
-                                "sourceType": source_type,
-                                "sourceHash": source_hash,
-                                "bytes_downloaded": total_written,
-                                "total_bytes": total_size_opt,
-                                "inflight": true
-                            }),
-                        );
+                    // An open-ended range that ran into bytes stored by another writer: drop it
+                    // and request the next gap instead of re-downloading what is on disk
+                    if is_range_response {
+                        if let Some(run_end) = present_run_end {
+                            if run_end.saturating_sub(pos) >= SKIP_PRESENT_MIN {
+                                break;
+                            }
+                        }
                     }
-                    
-                    // Update throttling state
-                    last_progress_time = now;
-                    last_progress_bytes = total_written;
                 }
-            }
-            Err(e) => {
-                // For torrents with significant progress, don't immediately fail on streaming errors
-                if source_type == "torrent" && total_written > 1024 * 1024 {
-                    // More than 1MB downloaded
-                    println!("[cache] Streaming error for torrent {} ({}:{}) but have {:.2}MB progress, preserving partial download: {}", 
-                        track_id, source_type, source_hash, total_written as f64 / (1024.0 * 1024.0), e);
-
-                    // Break from stream loop - download_complete remains false
-                    // The partial file will be preserved but not marked as complete
-                    break;
-                } else {
-                    println!(
-                        "[cache] Error while streaming download for {} ({}:{}): {}",
-                        track_id, source_type, source_hash, e
-                    );
-                    let _ = tokio_fs::remove_file(&cache_path).await;
-                    let mut inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
-                    inflight.remove(&base_name);
-                    let mut meta = INFLIGHT_META.lock().unwrap();
-                    meta.remove(&base_name);
+                Err(e) => {
+                    // Everything written so far is recorded in the range map; keep it for a later resume
+                    if total_written > 0 {
+                        println!("[cache] Streaming error for {} ({}:{}) with {:.2}MB on disk, preserving partial download: {}",
+                            track_id, source_type, source_hash, total_written as f64 / (1024.0 * 1024.0), e);
 
-                    // No polling task to cancel
-
-                    if let Some(app_ref) = app.as_ref() {
-                        let _ = app_ref.emit(
-                            "cache:download:error",
-                            serde_json::json!({
-                                "trackId": track_id,
-                                "sourceType": source_type,
-                                "sourceHash": source_hash,
-                                "message": format!("Streaming error: {}", e)
-                            }),
+                        stream_ended = false;
+                        break 'fetch;
+                    } else {
+                        println!(
+                            "[cache] Error while streaming download for {} ({}:{}): {}",
+                            track_id, source_type, source_hash, e
                         );
+                        partial.lock().unwrap().discard();
+                        let mut inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
+                        inflight.remove(&base_name);
+                        let mut meta = INFLIGHT_META.lock().unwrap();
+                        meta.remove(&base_name);
+
+                        // No polling task to cancel
+
+                        if let Some(app_ref) = app.as_ref() {
+                            let _ = app_ref.emit(
+                                "cache:download:error",
+                                serde_json::json!({
+                                    "trackId": track_id,
+                                    "sourceType": source_type,
+                                    "sourceHash": source_hash,
+                                    "message": downloads::note_error(&base_name, format!("Streaming error: {}", e))
+                                }),
+                            );
+                        }
+                        // Clear control state on error
+                        downloads::clear_control(&base_name);
+                        return;
                     }
-                    // Clear control state on error
-                    downloads::clear_control(&base_name);
-                    return;
                 }
             }
         }
+
+        // Without a known total, or after a full (non-Range) pass, there is nothing left to ask for
+        if total_size_opt.is_none() || !is_range_response {
+            break 'fetch;
+        }
+        // Guard against servers that answer but deliver nothing new
+        if total_written == written_before {
+            break 'fetch;
+        }
+    }
+    if !segment_workers.is_empty() {
+        total_written = segment_workers
+            .wait(&partial, app.as_ref(), (&track_id, &source_type, &source_hash), &base_name)
+            .await;
     }
 
-    // If we reach here, the stream ended normally - check if download is actually complete
+    // Check whether the file is actually complete.
     // For chunked transfers, server might close early even if not all data was sent
     if let Some(expected_size) = total_size_opt {
         println!(
             "[cache] Checking download completion: got {} bytes, expected {} bytes for {} ({}:{})",
             total_written, expected_size, track_id, source_type, source_hash
         );
-        if total_written < expected_size {
-            println!("[cache] Stream ended early - expected {} bytes but only got {} bytes for {} ({}:{})", 
+        if partial.lock().unwrap().meta().is_complete() {
+            download_complete = true; // Complete download
+        } else {
+            println!("[cache] Stream ended early - expected {} bytes but only have {} bytes for {} ({}:{})",
                 expected_size, total_written, track_id, source_type, source_hash);
             download_complete = false; // Treat as incomplete
-        } else {
-            download_complete = true; // Complete download
         }
     } else {
         // No content length or expected length available
         if source_type == "torrent" {
-            println!("[cache] No size information available for torrent stream that ended normally - likely chunked transfer that ended early for {} ({}:{})", 
+            println!("[cache] No size information available for torrent stream that ended normally - likely chunked transfer that ended early for {} ({}:{})",
                 track_id, source_type, source_hash);
             download_complete = false; // Treat torrent streams without size info as incomplete
-        } else {
+        } else if stream_ended && partial.lock().unwrap().meta().ranges.len() == 1 {
             println!(
                 "[cache] No content length available, assuming complete for {} ({}:{})",
                 track_id, source_type, source_hash
             );
+            let mut p = partial.lock().unwrap();
+            let len = p.meta().prefix_len();
+            p.set_total(len);
             download_complete = true;
+        } else {
+            download_complete = false;
         }
     }
 
-    // Ensure file is flushed to disk
-    if let Err(e) = file.flush().await {
+    // Ensure file is flushed to disk along with its range map
+    let persisted = tokio::task::spawn_blocking({
+        let partial = partial.clone();
+        move || partial::persist(&partial)
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("join error: {e}")));
+    if let Err(e) = persisted {
         println!("[cache] Failed to flush cache file {:?}: {}", cache_path, e);
-        let _ = tokio_fs::remove_file(&cache_path).await;
+        partial.lock().unwrap().discard();
         // Clear control on validation failure
         downloads::clear_control(&base_name);
         return;
@@ -1941,22 +3142,25 @@ pub async fn download_and_cache_audio(
         // Clear control state
         downloads::clear_control(&base_name);
 
-        // Don't mark as completed - preserve partial file for future retry
-        // The partial file remains at cache_path for potential resume
+        // Don't mark as completed - the part file and its range map remain for a later resume
         return;
-    } // Validate collected prefix bytes
+    }
+    // Validate the head of the file (read back from disk when it was fetched in an earlier session)
+    if prefix_buf.len() < VALIDATION_BYTES {
+        prefix_buf = partial.lock().unwrap().read_head(VALIDATION_BYTES);
+    }
     if !is_valid_audio_content(&prefix_buf) {
         // For torrents with substantial progress, be more lenient on validation
         if source_type == "torrent" && total_written > 5 * 1024 * 1024 {
             // More than 5MB
-            println!("[cache] Content validation failed for {} ({}:{}) but torrent has substantial progress ({:.2}MB), proceeding anyway", 
+            println!("[cache] Content validation failed for {} ({}:{}) but torrent has substantial progress ({:.2}MB), proceeding anyway",
                 track_id, source_type, source_hash, total_written as f64 / (1024.0 * 1024.0));
         } else {
             println!(
                 "[cache] Downloaded content is not valid audio for {} ({}:{}), size: {} bytes",
                 track_id, source_type, source_hash, total_written
             );
-            let _ = tokio_fs::remove_file(&cache_path).await;
+            partial.lock().unwrap().discard();
             let mut inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
             inflight.remove(&base_name);
             let mut meta = INFLIGHT_META.lock().unwrap();
@@ -1968,7 +3172,7 @@ pub async fn download_and_cache_audio(
                         "trackId": track_id,
                         "sourceType": source_type,
                         "sourceHash": source_hash,
-                        "message": "Validation failed: content is not valid audio"
+                        "message": downloads::note_error(&base_name, "Validation failed: content is not valid audio")
                     }),
                 );
             }
@@ -1978,6 +3182,10 @@ pub async fn download_and_cache_audio(
         }
     }
 
+    // The range map is no longer needed once the part file becomes a cache entry
+    partial.lock().unwrap().finish();
+    drop(partial);
+
     // Atomically rename temp file to final name
     if let Err(e) = tokio_fs::rename(&cache_path, &final_path).await {
         println!(
@@ -2050,7 +3258,7 @@ pub async fn download_and_cache_audio(
                             "trackId": track_id,
                             "sourceType": source_type,
                             "sourceHash": source_hash,
-                            "message": format!("Failed to add cache index: {}", e)
+                            "message": downloads::note_error(&base_name, format!("Failed to add cache index: {}", e))
                         }),
                     );
                 }
@@ -2116,7 +3324,7 @@ pub async fn download_and_cache_audio(
                     "trackId": track_id,
                     "sourceType": source_type,
                     "sourceHash": source_hash,
-                    "message": "Failed to finalize cache file"
+                    "message": downloads::note_error(&base_name, "Failed to finalize cache file")
                 }),
             );
         }
@@ -2163,6 +3371,8 @@ pub async fn download_and_cache_audio(
         }
     }
 
+    on_entry_cached(&track_id, &source_type, &source_hash, file_index);
+
     let cached_path = final_path.to_string_lossy().to_string();
 
     // Send completion notification
@@ -2216,7 +3426,7 @@ pub async fn download_and_cache_audio(
 
 /// Schedule a deferred cleanup of torrent engine data after a successful cache finalization.
 /// This avoids deleting the original engine files while any late readers or copies might still occur.
-fn schedule_torrent_cleanup(url: String, engine_path: std::path::PathBuf, final_path: std::path::PathBuf, expected_size: Option<u64>) {
+pub(crate) fn schedule_torrent_cleanup(url: String, engine_path: std::path::PathBuf, final_path: std::path::PathBuf, expected_size: Option<u64>) {
     // Try a few times with exponential backoff to ensure the final file is stable and present
     tokio::spawn(async move {
         use tokio::time::{sleep, Duration};
@@ -2232,6 +3442,17 @@ fn schedule_torrent_cleanup(url: String, engine_path: std::path::PathBuf, final_
                 Err(_) => false,
             };
             if final_ok {
+                // Other files of the torrent (album imports) still download into the same engine
+                // data; the cleanup after the last of them removes it
+                let pending = crate::torrents::info_hash_from_key(&url)
+                    .map_or(0, |ih| downloads::torrent_files_pending(&ih));
+                if pending > 0 {
+                    println!(
+                        "[cache] Keeping engine data for {}: {} other file(s) still downloading",
+                        url, pending
+                    );
+                    return;
+                }
                 // Try to remove via engine now that final is confirmed
                 let engine = crate::torrents::get_engine();
                 match engine.remove(&url, true) {
@@ -2351,6 +3572,95 @@ pub async fn cache_get_stats() -> Result<serde_json::Value, String> {
     Err("Cache not initialized".to_string())
 }
 
+// Default and maximum page size for cache_list
+const CACHE_LIST_DEFAULT_LIMIT: usize = 50;
+const CACHE_LIST_MAX_LIMIT: usize = 500;
+
+// Paginated listing of cached entries for an offline library view
+#[tauri::command]
+pub async fn cache_list(
+    filter: Option<CacheListFilter>,
+    sort: Option<CacheListSort>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<serde_json::Value, String> {
+    let filter = filter.unwrap_or_default();
+    let sort = sort.unwrap_or_default();
+    let offset = offset.unwrap_or(0);
+    let limit = limit
+        .unwrap_or(CACHE_LIST_DEFAULT_LIMIT)
+        .clamp(1, CACHE_LIST_MAX_LIMIT);
+
+    let cache_guard = CACHE.lock().unwrap();
+    let cache = cache_guard.as_ref().ok_or("Cache not initialized")?;
+
+    let matched = cache.list_entries(&filter, &sort);
+    let matched_size: u64 = matched.iter().map(|(_, e)| e.file_size).sum();
+    let items: Vec<serde_json::Value> = matched
+        .iter()
+        .skip(offset)
+        .take(limit)
+        .map(|(key, entry)| {
+            let mut v = serde_json::to_value(entry).unwrap_or_default();
+            if let Some(obj) = v.as_object_mut() {
+                obj.insert("key".to_string(), serde_json::json!(key));
+            }
+            v
+        })
+        .collect();
+
+    let mut by_source_type = serde_json::Map::new();
+    for (source_type, (count, size)) in cache.size_by_source_type() {
+        by_source_type.insert(
+            source_type,
+            serde_json::json!({ "count": count, "size": size }),
+        );
+    }
+
+    Ok(serde_json::json!({
+        "items": items,
+        "offset": offset,
+        "limit": limit,
+        "matched_count": matched.len(),
+        "matched_size": matched_size,
+        "total_size": cache.index.total_size,
+        "entry_count": cache.index.entries.len(),
+        "by_source_type": by_source_type
+    }))
+}
+
+/// Embedded tags of a cached track, with the absolute path of its cover art thumbnail.
+/// Returns null when the track isn't cached or hasn't been probed yet.
+#[tauri::command]
+pub async fn cache_get_metadata(
+    track_id: String,
+    source_type: String,
+    source_hash: String,
+    file_index: Option<usize>,
+) -> Result<serde_json::Value, String> {
+    let cache_key =
+        AudioCache::generate_cache_key_with_index(&track_id, &source_type, &source_hash, file_index);
+    let (metadata, cache_dir) = {
+        let cache_guard = CACHE.lock().unwrap();
+        let cache = cache_guard.as_ref().ok_or("Cache not initialized")?;
+        let metadata = cache.index.entries.get(&cache_key).and_then(|e| e.metadata.clone());
+        (metadata, cache.cache_dir.clone())
+    };
+    let metadata = match metadata {
+        Some(m) => m,
+        None => return Ok(serde_json::Value::Null),
+    };
+    let cover_art_path = metadata
+        .cover_art
+        .as_deref()
+        .and_then(|name| tags::cover_art_path(&cache_dir, name))
+        .map(|p| p.to_string_lossy().to_string());
+    let mut value = serde_json::to_value(&metadata)
+        .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
+    value["coverArtPath"] = serde_json::json!(cover_art_path);
+    Ok(value)
+}
+
 #[tauri::command]
 pub async fn cache_clear() -> Result<(), String> {
     let mut cache_guard = CACHE.lock().unwrap();
@@ -2361,9 +3671,154 @@ pub async fn cache_clear() -> Result<(), String> {
     Err("Cache not initialized".to_string())
 }
 
+/// Pin or unpin a cached track. Pinned tracks are never evicted and keep their original after a transcode.
+#[tauri::command]
+pub async fn cache_set_pinned(
+    track_id: String,
+    source_type: String,
+    source_hash: String,
+    file_index: Option<usize>,
+    pinned: bool,
+) -> Result<bool, String> {
+    let cache_key =
+        AudioCache::generate_cache_key_with_index(&track_id, &source_type, &source_hash, file_index);
+    let obsolete = {
+        let mut cache_guard = CACHE.lock().unwrap();
+        let cache = cache_guard.as_mut().ok_or("Cache not initialized")?;
+        let cache_dir = cache.cache_dir.clone();
+        let entry = match cache.index.entries.get_mut(&cache_key) {
+            Some(e) => e,
+            None => return Ok(false),
+        };
+        entry.pinned = pinned;
+        // Unpinning drops a kept original; the transcoded copy stays playable
+        let obsolete = if !pinned {
+            entry.original_file_path.take().map(|name| cache_dir.join(name))
+        } else {
+            None
+        };
+        if let Some(path) = obsolete.as_ref() {
+            let freed = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+            entry.file_size = entry.file_size.saturating_sub(freed);
+            cache.index.total_size = cache.index.total_size.saturating_sub(freed);
+        }
+        cache.save_index()?;
+        obsolete
+    };
+    if let Some(path) = obsolete {
+        crate::transcode::remove_when_released(path);
+    }
+    Ok(true)
+}
+
+#[tauri::command]
+pub async fn cache_get_policy() -> Result<serde_json::Value, String> {
+    policy_json(crate::cache_policy::get_cache_policy())
+}
+
+// Policy plus whether ffmpeg is around to act on it
+fn policy_json(policy: crate::cache_policy::CachePolicy) -> Result<serde_json::Value, String> {
+    let mut value = serde_json::to_value(policy)
+        .map_err(|e| format!("Failed to serialize cache policy: {}", e))?;
+    if let Some(obj) = value.as_object_mut() {
+        obj.insert(
+            "ffmpegAvailable".to_string(),
+            serde_json::Value::Bool(crate::transcode::ffmpeg_available()),
+        );
+    }
+    Ok(value)
+}
+
+/// Merge the provided fields into the cache policy. Enabling transcoding queues existing entries.
+#[tauri::command]
+pub async fn cache_set_policy(policy: serde_json::Value) -> Result<serde_json::Value, String> {
+    let mut merged = serde_json::to_value(crate::cache_policy::get_cache_policy())
+        .map_err(|e| format!("Failed to serialize cache policy: {}", e))?;
+    if let (Some(dst), Some(src)) = (merged.as_object_mut(), policy.as_object()) {
+        for (k, v) in src {
+            dst.insert(k.clone(), v.clone());
+        }
+    }
+    let next: crate::cache_policy::CachePolicy = serde_json::from_value(merged)
+        .map_err(|e| format!("Invalid cache policy: {}", e))?;
+    if next.transcode_enabled && !crate::transcode::ffmpeg_available() {
+        return Err("Transcoding needs ffmpeg, which could not be found".to_string());
+    }
+    let updated = crate::cache_policy::update_cache_policy(|p| *p = next)?;
+    if updated.transcode_enabled {
+        crate::transcode::sweep();
+    }
+    policy_json(updated)
+}
+
+/// Export cached tracks (or everything when `track_ids` is omitted) into a single bundle file
+#[tauri::command]
+pub async fn cache_export(
+    track_ids: Option<Vec<String>>,
+    dest_path: String,
+) -> Result<serde_json::Value, String> {
+    tokio::task::spawn_blocking(move || {
+        // Copying can take minutes; only the snapshot is taken under the cache lock
+        let (cache_dir, selected) = {
+            let cache_guard = CACHE.lock().unwrap();
+            let cache = cache_guard.as_ref().ok_or("Cache not initialized")?;
+            (cache.cache_dir.clone(), cache.export_selection(track_ids.as_deref()))
+        };
+        let count = AudioCache::export_bundle(&cache_dir, selected, Path::new(&dest_path))?;
+        Ok(serde_json::json!({ "path": dest_path, "exported": count }))
+    })
+    .await
+    .map_err(|e| format!("join error: {e}"))?
+}
+
+/// Import a bundle created by `cache_export`. `on_conflict` is "skip" (default), "overwrite" or "newer".
+#[tauri::command]
+pub async fn cache_import(
+    path: String,
+    on_conflict: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let policy = ImportConflictPolicy::parse(on_conflict.as_deref())?;
+    let summary = tokio::task::spawn_blocking(move || -> Result<serde_json::Value, String> {
+        let (mut archive, entries) = AudioCache::open_bundle(Path::new(&path))?;
+        let (cache_dir, selected, conflicts) = {
+            let cache_guard = CACHE.lock().unwrap();
+            let cache = cache_guard.as_ref().ok_or("Cache not initialized")?;
+            let (selected, conflicts) = cache.import_selection(entries, policy);
+            (cache.cache_dir.clone(), selected, conflicts)
+        };
+        // Extracting can take a while for a large bundle; keep the cache usable meanwhile
+        let (staged, unusable) = AudioCache::extract_bundle(&cache_dir, &mut archive, selected)?;
+        let merged = {
+            let mut cache_guard = CACHE.lock().unwrap();
+            match cache_guard.as_mut() {
+                Some(cache) => cache.merge_import(&mut archive, staged, policy),
+                None => {
+                    for s in &staged {
+                        let _ = fs::remove_file(&s.tmp);
+                    }
+                    Err("Cache not initialized".to_string())
+                }
+            }
+        };
+        NEGATIVE_CACHE.lock().unwrap().clear();
+        let (imported, late_conflicts) = merged?;
+        let skipped = conflicts + unusable + late_conflicts;
+        println!("[cache] Imported bundle {}: {} imported, {} skipped", path, imported, skipped);
+        Ok(serde_json::json!({ "imported": imported, "skipped": skipped }))
+    })
+    .await
+    .map_err(|e| format!("join error: {e}"))??;
+    // Bundles written before tags were extracted carry no metadata
+    tags::sweep();
+    Ok(summary)
+}
+
 // Enumerate current inflight downloads for UI sync
 #[tauri::command]
 pub async fn cache_list_inflight() -> Result<serde_json::Value, String> {
+    let partials = get_cache_dir()
+        .map(|dir| partial::list(&dir))
+        .unwrap_or_default();
     let inflight = INFLIGHT_DOWNLOADS.lock().unwrap();
     let meta = INFLIGHT_META.lock().unwrap();
     let mut arr: Vec<serde_json::Value> = Vec::new();
@@ -2387,5 +3842,343 @@ pub async fn cache_list_inflight() -> Result<serde_json::Value, String> {
             }));
         }
     }
+    // Partial downloads from earlier sessions that can be resumed
+    for (part_path, pm) in partials {
+        let base = part_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_string();
+        if inflight.contains_key(&base) {
+            continue;
+        }
+        arr.push(serde_json::json!({
+            "id": base,
+            "trackId": pm.track_id,
+            "sourceType": pm.source_type,
+            "sourceHash": pm.source_hash,
+            "fileIndex": pm.file_index,
+            "bytes_downloaded": pm.covered(),
+            "total_bytes": pm.total,
+            "ranges": pm.ranges,
+            "inflight": false,
+            "resumable": true
+        }));
+    }
     Ok(serde_json::json!({ "items": arr }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::downloads::JobSpec;
+
+    const LEN: usize = 256 * 1024;
+    const WAIT: std::time::Duration = std::time::Duration::from_secs(20);
+
+    // The cache index and the torrent engine are process-wide, so every test shares one cache
+    // folder and one fixture folder (the fake engine reads it on first use)
+    fn fixtures() -> &'static Path {
+        static BASE: Lazy<PathBuf> = Lazy::new(|| {
+            let base = std::env::temp_dir().join(format!("freely-cache-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&base);
+            fs::create_dir_all(base.join("fixtures")).unwrap();
+            std::env::set_var(crate::torrent_fake::FIXTURE_ENV, base.join("fixtures"));
+            init_cache(&base.join("cache")).unwrap();
+            base.join("fixtures")
+        });
+        &BASE
+    }
+
+    // A fixture torrent with the given files; leftovers of an earlier run are removed
+    fn torrent(info_hash: &str, files: &[(&str, &[u8])], profile: &str) {
+        let root = fixtures().join(info_hash);
+        let _ = fs::remove_dir_all(&root);
+        let _ = fs::remove_dir_all(crate::torrents::torrents_dir().join(info_hash));
+        fs::create_dir_all(&root).unwrap();
+        for (name, data) in files {
+            fs::write(root.join(name), data).unwrap();
+        }
+        fs::write(root.join("fake.json"), profile).unwrap();
+    }
+
+    // Non-zero bytes, like encoded audio
+    fn audio(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8 + 1).collect()
+    }
+
+    fn job(info_hash: &str, file_index: usize) -> (JobSpec, String) {
+        let spec = JobSpec {
+            track_id: format!("track-{}-{}", &info_hash[..8], file_index),
+            source_type: "torrent".to_string(),
+            source_hash: info_hash.to_string(),
+            url: info_hash.to_string(),
+            file_index: Some(file_index),
+        };
+        let id = crate::downloads::queue_unstarted(spec.clone());
+        (spec, id)
+    }
+
+    async fn run(spec: &JobSpec) -> Result<(), String> {
+        run_cache_download(
+            None,
+            spec.track_id.clone(),
+            spec.source_type.clone(),
+            spec.source_hash.clone(),
+            spec.url.clone(),
+            spec.file_index,
+        )
+        .await
+    }
+
+    // One pass of the torrent branch, without the retry loop around it
+    async fn attempt(spec: &JobSpec) {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        download_and_cache_audio(
+            None,
+            spec.track_id.clone(),
+            spec.source_type.clone(),
+            spec.source_hash.clone(),
+            spec.url.clone(),
+            spec.file_index,
+            tx,
+        )
+        .await;
+    }
+
+    fn cached(spec: &JobSpec) -> Option<PathBuf> {
+        get_cached_file_path_with_index(
+            &spec.track_id,
+            &spec.source_type,
+            &spec.source_hash,
+            spec.file_index,
+        )
+    }
+
+    #[tokio::test]
+    async fn torrent_download_retries_failed_starts() {
+        let ih = "a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1";
+        let data = audio(LEN);
+        torrent(ih, &[("01 - Track.flac", &data)], r#"{"fail_starts": 2}"#);
+        let (spec, id) = job(ih, 0);
+
+        run(&spec).await.unwrap();
+        assert_eq!(crate::torrent_fake::failed_starts(ih), 2);
+        assert_eq!(fs::read(cached(&spec).unwrap()).unwrap(), data);
+        crate::downloads::forget_job(&id);
+    }
+
+    #[tokio::test]
+    async fn torrent_download_gives_up_after_max_retries() {
+        let ih = "a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2";
+        torrent(ih, &[("01 - Track.flac", &audio(LEN))], r#"{"fail_starts": 1000}"#);
+        let (spec, id) = job(ih, 0);
+
+        let err = run(&spec).await.unwrap_err();
+        assert!(err.contains("multiple retries"), "{}", err);
+        assert_eq!(crate::torrent_fake::failed_starts(ih), 10);
+        assert!(cached(&spec).is_none());
+        crate::downloads::forget_job(&id);
+    }
+
+    #[tokio::test]
+    async fn torrent_download_waits_out_a_short_stall() {
+        let ih = "a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3";
+        let data = audio(LEN);
+        // Shorter than the (test clock) stall timeout of 3s
+        torrent(ih, &[("01 - Track.flac", &data)], r#"{"stall_at": 65536, "stall_ms": 1500}"#);
+        let (spec, id) = job(ih, 0);
+
+        let download = tokio::spawn({
+            let spec = spec.clone();
+            async move { run(&spec).await }
+        });
+        let deadline = std::time::Instant::now() + WAIT;
+        while get_inflight_status_by_base(&id).map(|(bytes, _)| bytes) != Some(65536) {
+            assert!(std::time::Instant::now() < deadline, "download never reached the stall");
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert!(cached(&spec).is_none());
+
+        download.await.unwrap().unwrap();
+        assert_eq!(crate::torrent_fake::failed_starts(ih), 0);
+        assert_eq!(fs::read(cached(&spec).unwrap()).unwrap(), data);
+        crate::downloads::forget_job(&id);
+    }
+
+    #[tokio::test]
+    async fn torrent_download_times_out_when_stalled() {
+        let ih = "a4a4a4a4a4a4a4a4a4a4a4a4a4a4a4a4a4a4a4a4";
+        torrent(ih, &[("01 - Track.flac", &audio(LEN))], r#"{"stall_at": 65536, "stall_ms": 0}"#);
+        let (spec, id) = job(ih, 0);
+
+        attempt(&spec).await;
+        let err = crate::downloads::take_error(&id).unwrap();
+        assert!(err.contains("stalled"), "{}", err);
+        assert!(cached(&spec).is_none());
+        // What arrived before the stall is kept for the next attempt
+        let part = get_cache_dir().unwrap().join(format!("{}.part", id));
+        assert_eq!(partial::load(&part).unwrap().covered(), 65536);
+        crate::torrents::get_engine().remove(ih, true).unwrap();
+        crate::downloads::forget_job(&id);
+    }
+
+    #[tokio::test]
+    async fn torrent_download_rejects_a_zeroed_tail() {
+        let ih = "a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5";
+        // Reported complete, yet the last pieces were never written
+        let profile = format!(
+            r#"{{"zero_ranges": [{{"file": 0, "offset": {}, "len": 65536}}]}}"#,
+            LEN - 65536
+        );
+        torrent(ih, &[("01 - Track.flac", &audio(LEN))], &profile);
+        let (spec, id) = job(ih, 0);
+
+        attempt(&spec).await;
+        assert!(cached(&spec).is_none());
+        let err = crate::downloads::take_error(&id).unwrap();
+        assert!(err.contains("timeout"), "{}", err);
+        crate::torrents::get_engine().remove(ih, true).unwrap();
+        crate::downloads::forget_job(&id);
+    }
+
+    #[tokio::test]
+    async fn torrent_cleanup_waits_for_every_queued_file() {
+        let ih = "a6a6a6a6a6a6a6a6a6a6a6a6a6a6a6a6a6a6a6a6";
+        let (first, second) = (audio(LEN), audio(LEN / 2));
+        torrent(ih, &[("01 - One.flac", &first), ("02 - Two.flac", &second)], "{}");
+        let (spec_a, id_a) = job(ih, 0);
+        let (spec_b, id_b) = job(ih, 1);
+        let engine_dir = crate::torrents::torrents_dir().join(ih);
+
+        run(&spec_a).await.unwrap();
+        crate::downloads::forget_job(&id_a);
+        // Past the cleanup's first check, with the second file still queued
+        tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+        assert!(engine_dir.join("01 - One.flac").exists(), "engine data removed while a file was queued");
+
+        run(&spec_b).await.unwrap();
+        let deadline = std::time::Instant::now() + WAIT;
+        while engine_dir.exists() {
+            assert!(std::time::Instant::now() < deadline, "engine data kept after the last file was cached");
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        assert_eq!(fs::read(cached(&spec_a).unwrap()).unwrap(), first);
+        assert_eq!(fs::read(cached(&spec_b).unwrap()).unwrap(), second);
+        crate::downloads::forget_job(&id_b);
+    }
+
+    #[test]
+    fn bundle_import_merges_and_saves_index() {
+        let base = fixtures().parent().unwrap().join("bundle");
+        let _ = fs::remove_dir_all(&base);
+        let data = audio(LEN);
+        let mut from = AudioCache::new(&base.join("from")).unwrap();
+        fs::write(base.join("from").join("song.flac"), &data).unwrap();
+        from.add_cached_file_with_index(
+            "song".to_string(),
+            "http".to_string(),
+            "h1".to_string(),
+            "song.flac".to_string(),
+            LEN as u64,
+            None,
+        )
+        .unwrap();
+        let bundle = base.join("bundle.zip");
+        AudioCache::export_bundle(&from.cache_dir, from.export_selection(None), &bundle).unwrap();
+
+        let dir = base.join("to");
+        let mut to = AudioCache::new(&dir).unwrap();
+        let (mut archive, entries) = AudioCache::open_bundle(&bundle).unwrap();
+        let (selected, conflicts) = to.import_selection(entries, ImportConflictPolicy::Skip);
+        assert_eq!((selected.len(), conflicts), (1, 0));
+        let (staged, unusable) = AudioCache::extract_bundle(&dir, &mut archive, selected).unwrap();
+        assert_eq!(unusable, 0);
+        // Extracted, but not visible in the cache until merged
+        assert!(to.get_cached_file_with_index("song", "http", "h1", None).is_none());
+
+        let merged = to.merge_import(&mut archive, staged, ImportConflictPolicy::Skip).unwrap();
+        assert_eq!(merged, (1, 0));
+        assert_eq!(fs::read(dir.join("song.flac")).unwrap(), data);
+        let leftovers = fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .filter(|e| e.file_name().to_string_lossy().ends_with(".import"))
+            .count();
+        assert_eq!(leftovers, 0);
+
+        // The merge saved the index: a fresh instance sees the entry
+        let mut reloaded = AudioCache::new(&dir).unwrap();
+        assert!(reloaded.get_cached_file_with_index("song", "http", "h1", None).is_some());
+
+        // Importing again under "skip" leaves the existing entry alone
+        let (_, entries) = AudioCache::open_bundle(&bundle).unwrap();
+        let (selected, conflicts) = reloaded.import_selection(entries, ImportConflictPolicy::Skip);
+        assert_eq!((selected.len(), conflicts), (0, 1));
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    const MB: u64 = 1024 * 1024;
+
+    fn lens(plan: &[Vec<(u64, u64)>]) -> Vec<u64> {
+        plan.iter().map(|g| g.iter().map(|&(s, e)| e - s).sum()).collect()
+    }
+
+    #[test]
+    fn content_range_parsing() {
+        assert_eq!(parse_content_range("bytes 0-0/1234"), Some((0, Some(1234))));
+        assert_eq!(parse_content_range(" bytes 100-199/ 200 "), Some((100, Some(200))));
+        // Total not known yet
+        assert_eq!(parse_content_range("bytes 5-9/*"), Some((5, None)));
+        // Unsatisfiable range carries no start offset
+        assert_eq!(parse_content_range("bytes */1234"), None);
+        // Malformed headers
+        assert_eq!(parse_content_range(""), None);
+        assert_eq!(parse_content_range("bytes 0-9"), None);
+        assert_eq!(parse_content_range("items 0-9/10"), None);
+        assert_eq!(parse_content_range("bytes x-9/10"), None);
+        assert_eq!(parse_content_range("bytes 10/10"), None);
+    }
+
+    #[test]
+    fn segment_plan_small_total_is_one_group() {
+        // Below the minimum segment length everything stays in a single group
+        assert_eq!(plan_segments(&[(0, MB)], 0, 3), vec![vec![(0, MB)]]);
+        // Groups never drop under the minimum, so fewer workers are used
+        assert_eq!(lens(&plan_segments(&[(0, 5 * MB)], 0, 4)), vec![2 * MB, 2 * MB, MB]);
+        assert!(plan_segments(&[(0, MB)], 0, 0).is_empty());
+        assert!(plan_segments(&[], 0, 3).is_empty());
+    }
+
+    #[test]
+    fn segment_plan_last_group_takes_remainder() {
+        let total = 10 * MB + 3;
+        let plan = plan_segments(&[(0, total)], 0, 4);
+        let target = total / 4;
+        assert_eq!(lens(&plan), vec![target, target, target, total - 3 * target]);
+        // Contiguous and covering everything
+        assert_eq!(plan[0][0].0, 0);
+        assert_eq!(plan[3][0].1, total);
+        for pair in plan.windows(2) {
+            assert_eq!(pair[0].last().unwrap().1, pair[1][0].0);
+        }
+    }
+
+    #[test]
+    fn segment_plan_starts_after_resumed_head() {
+        // Resumed partial: 0-3MB and 6-7MB already on disk, head kept by the main loop up to 4MB
+        let gaps = [(3 * MB, 6 * MB), (7 * MB, 14 * MB)];
+        let plan = plan_segments(&gaps, 4 * MB, 3);
+        // 9MB missing past the head, split 3MB each; the first group spans the gap boundary
+        assert_eq!(
+            plan,
+            vec![
+                vec![(4 * MB, 6 * MB), (7 * MB, 8 * MB)],
+                vec![(8 * MB, 11 * MB)],
+                vec![(11 * MB, 14 * MB)],
+            ]
+        );
+        // Head already past every gap
+        assert!(plan_segments(&gaps, 14 * MB, 3).is_empty());
+    }
+}
@@ -0,0 +1,138 @@
+use once_cell::sync::Lazy;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Target format for transcode-on-cache
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscodeFormat {
+    Opus,
+    Aac,
+}
+
+impl TranscodeFormat {
+    /// Codec name stored in CacheEntry.codec (matches codec_from_tags naming)
+    pub fn codec_name(&self) -> &'static str {
+        match self {
+            TranscodeFormat::Opus => "opus",
+            TranscodeFormat::Aac => "aac",
+        }
+    }
+
+    /// File extension used for the transcoded copy in the cache dir
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TranscodeFormat::Opus => "opus",
+            TranscodeFormat::Aac => "m4a",
+        }
+    }
+}
+
+// Cache policy with persistence support (same storage location as audio settings)
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct CachePolicy {
+    // Transcode fully cached lossless files in the background to save space
+    pub transcode_enabled: bool,
+    pub transcode_format: TranscodeFormat,
+    pub transcode_bitrate_kbps: u32,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        Self {
+            transcode_enabled: false,
+            transcode_format: TranscodeFormat::Opus,
+            transcode_bitrate_kbps: 160,
+        }
+    }
+}
+
+impl CachePolicy {
+    /// Load cache policy from disk, using defaults if file doesn't exist
+    pub fn load() -> Self {
+        match Self::get_policy_path() {
+            Ok(path) => {
+                if path.exists() {
+                    match std::fs::read_to_string(&path) {
+                        Ok(content) => match serde_json::from_str::<CachePolicy>(&content) {
+                            Ok(mut policy) => {
+                                policy.validate();
+                                println!("[cache] Loaded cache policy from: {}", path.display());
+                                return policy;
+                            }
+                            Err(e) => {
+                                println!("[cache] Failed to parse cache policy: {}, using defaults", e);
+                            }
+                        },
+                        Err(e) => {
+                            println!("[cache] Failed to read cache policy: {}, using defaults", e);
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                println!("[cache] Failed to get cache policy path: {}, using defaults", e);
+            }
+        }
+        Self::default()
+    }
+
+    /// Save cache policy to disk
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::get_policy_path()
+            .map_err(|e| format!("Failed to get cache policy path: {}", e))?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+        }
+
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize cache policy: {}", e))?;
+
+        std::fs::write(&path, content)
+            .map_err(|e| format!("Failed to write cache policy: {}", e))?;
+
+        println!("[cache] Saved cache policy to: {}", path.display());
+        Ok(())
+    }
+
+    fn get_policy_path() -> Result<PathBuf, String> {
+        let data_dir = dirs::data_dir()
+            .ok_or("Failed to get system data directory")?;
+
+        let mut path = data_dir;
+        path.push("com.freely.player");
+        path.push("cache_policy.json");
+        Ok(path)
+    }
+
+    /// Clamp values to ranges the encoders accept
+    pub fn validate(&mut self) {
+        self.transcode_bitrate_kbps = match self.transcode_format {
+            TranscodeFormat::Opus => self.transcode_bitrate_kbps.max(32).min(512),
+            TranscodeFormat::Aac => self.transcode_bitrate_kbps.max(64).min(320),
+        };
+    }
+}
+
+// Global cache policy instance
+static CACHE_POLICY: Lazy<Mutex<CachePolicy>> = Lazy::new(|| Mutex::new(CachePolicy::load()));
+
+/// Get a snapshot of the current cache policy
+pub fn get_cache_policy() -> CachePolicy {
+    CACHE_POLICY.lock().unwrap().clone()
+}
+
+/// Update cache policy and save to disk
+pub fn update_cache_policy<F>(updater: F) -> Result<CachePolicy, String>
+where
+    F: FnOnce(&mut CachePolicy),
+{
+    let mut policy = CACHE_POLICY.lock().unwrap();
+    updater(&mut policy);
+    policy.validate();
+    policy.save()?;
+    Ok(policy.clone())
+}
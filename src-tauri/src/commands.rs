@@ -58,6 +58,9 @@ pub mod torrent {
     use tokio::time::{timeout, Duration};
     use serde_json::json;
 
+    // Search results whose metadata is resolved ahead of the user opening them
+    const PREFETCH_TOP_RESULTS: usize = 3;
+
     #[tauri::command]
     pub async fn torrent_get_files(id: String, timeout_ms: Option<u64>, _config: State<'_, PathConfig>) -> Result<serde_json::Value, serde_json::Value> {
         let engine = get_engine();
@@ -96,6 +99,28 @@ pub mod torrent {
         res
     }
 
+    // Register a .torrent given as raw bytes (file dialog), a local path, or a URL fetched with the
+    // indexer plugin's headers plus optional cookies. The returned info hash is the torrent's key.
+    #[tauri::command]
+    pub async fn torrent_add_file(bytes: Option<Vec<u8>>, path: Option<String>, url: Option<String>, provider: Option<String>, cookies: Option<String>, config: State<'_, PathConfig>) -> Result<serde_json::Value, String> {
+        let bytes = match (bytes, path, url) {
+            (Some(b), _, _) => b,
+            (None, Some(p), _) => tokio::fs::read(&p).await.map_err(|e| format!("read {p}: {e}"))?,
+            (None, None, Some(u)) => {
+                let spec = provider.as_deref().and_then(|p| crate::plugins::find_indexer_spec(&config, p));
+                crate::scrape::fetch_torrent_file(spec.as_ref(), &u, cookies).await?
+            }
+            (None, None, None) => return Err("one of bytes, path or url is required".to_string()),
+        };
+        let (info_hash, files) = tokio::task::spawn_blocking(move || {
+            let engine = get_engine();
+            let info_hash = engine.add_torrent_bytes(bytes)?;
+            let files = engine.list_files(&info_hash)?;
+            Ok::<_, String>((info_hash, files))
+        }).await.map_err(|e| format!("join error: {e}"))??;
+        Ok(json!({"status":"ok","infoHash": info_hash, "data": files.into_iter().map(|f: TorrentFileInfo| json!({ "index": f.index, "name": f.name, "length": f.length })).collect::<Vec<_>>() }))
+    }
+
     #[tauri::command]
     pub async fn torrent_start_download(app: tauri::AppHandle, magnet: String, index: u32, config: State<'_, PathConfig>) -> Result<serde_json::Value, String> {
         let dir = &config.torrents_dir;
@@ -114,7 +139,7 @@ pub mod torrent {
         let key = hash_or_magnet.clone();
         let res = tokio::task::spawn_blocking(move || engine_progress(&key, index)).await.map_err(|e| format!("join error: {e}"))?;
         match res {
-            Ok(ProgressData { bytes, verified_bytes, on_disk_bytes, total, peers, down_speed }) => Ok(json!({"status":"ok","data": {"bytes": bytes, "verifiedBytes": verified_bytes, "onDiskBytes": on_disk_bytes, "total": total, "peers": peers, "downSpeed": down_speed }})),
+            Ok(ProgressData { bytes, verified_bytes, on_disk_bytes, total, peers, down_speed, uploaded, up_speed }) => Ok(json!({"status":"ok","data": {"bytes": bytes, "verifiedBytes": verified_bytes, "onDiskBytes": on_disk_bytes, "total": total, "peers": peers, "downSpeed": down_speed, "uploaded": uploaded, "upSpeed": up_speed }})),
             Err(e) => Err(e)
         }
     }
@@ -127,6 +152,46 @@ pub mod torrent {
         Ok(p.to_string_lossy().to_string())
     }
 
+    // Move streaming priority to `offset` bytes, or to `fraction` (0..1) of the file
+    #[tauri::command]
+    pub async fn torrent_prioritize(hash_or_magnet: String, index: u32, offset: Option<u64>, fraction: Option<f64>) -> Result<serde_json::Value, String> {
+        let res = tokio::task::spawn_blocking(move || match offset {
+            Some(o) => get_engine().prioritize(&hash_or_magnet, index, o).map(|_| o),
+            None => crate::torrents::prioritize_position(&hash_or_magnet, index, fraction.unwrap_or(0.0)),
+        }).await.map_err(|e| format!("join error: {e}"))?;
+        res.map(|o| json!({"status":"ok","offset": o}))
+    }
+
+    // Every torrent in the engine session with transfer totals, plus seeding state where seeding
+    #[tauri::command]
+    pub async fn torrent_list() -> Result<serde_json::Value, String> {
+        let torrents = tokio::task::spawn_blocking(|| get_engine().list_torrents()).await.map_err(|e| format!("join error: {e}"))??;
+        let items: Vec<serde_json::Value> = torrents.into_iter().map(|t| {
+            let ratio = if t.downloaded > 0 { t.uploaded as f64 / t.downloaded as f64 } else { 0.0 };
+            let seed = crate::seeding::seed_for(&t.info_hash);
+            let mut v = json!(t);
+            v["ratio"] = json!(ratio);
+            v["seeding"] = json!(seed);
+            v
+        }).collect();
+        Ok(json!({"status":"ok","data": items}))
+    }
+
+    #[tauri::command]
+    pub async fn torrent_details(hash_or_magnet: String) -> Result<serde_json::Value, String> {
+        let details = tokio::task::spawn_blocking(move || get_engine().torrent_details(&hash_or_magnet)).await.map_err(|e| format!("join error: {e}"))??;
+        let seed = crate::seeding::seed_for(&details.summary.info_hash);
+        let mut v = json!(details);
+        v["seeding"] = json!(seed);
+        Ok(json!({"status":"ok","data": v}))
+    }
+
+    #[tauri::command]
+    pub async fn torrent_peers(hash_or_magnet: String) -> Result<serde_json::Value, String> {
+        let peers = tokio::task::spawn_blocking(move || get_engine().peer_list(&hash_or_magnet)).await.map_err(|e| format!("join error: {e}"))??;
+        Ok(json!({"status":"ok","data": peers}))
+    }
+
     #[tauri::command]
     pub async fn torrent_list_scrapers(config: State<'_, PathConfig>) -> Result<serde_json::Value, String> { Ok(serde_json::json!(crate::plugins::list_public_info(&config))) }
 
@@ -136,8 +201,23 @@ pub mod torrent {
     pub async fn torrent_search(payload: TorrentSearchPayload, config: State<'_, PathConfig>) -> Result<serde_json::Value, String> {
         let provider = payload.provider.as_deref();
         let items = crate::plugins::search_plugins(&config, &payload.query, provider).await.map_err(|e| format!("search failed: {e}"))?;
+        // Resolve the first results' file lists in the background so opening one is instant
+        crate::torrents::prefetch_metadata(items.iter().filter_map(result_key).take(PREFETCH_TOP_RESULTS).collect());
         Ok(json!(items))
     }
+
+    // Resolve and persist file lists for the given magnets/info hashes in the background
+    #[tauri::command]
+    pub async fn torrent_prefetch_metadata(ids: Vec<String>) -> Result<serde_json::Value, String> {
+        let started = crate::torrents::prefetch_metadata(ids);
+        Ok(json!({"status":"ok","started": started}))
+    }
+
+    // Magnet link or info hash of a search result; indexer plugins name their fields freely
+    fn result_key(item: &serde_json::Value) -> Option<String> {
+        let keys: Vec<&str> = item.as_object()?.values().filter_map(|v| v.as_str()).filter(|v| crate::torrents::info_hash_from_key(v).is_some()).collect();
+        keys.iter().find(|v| v.starts_with("magnet:")).or(keys.first()).map(|v| v.to_string())
+    }
 }
 
 /// Search operations
@@ -571,12 +651,10 @@ pub mod external {
             .and_then(|v| v.as_str())
             .unwrap_or(&endpoint);
 
-        reqwest::get(url)
+        let client = crate::http::client(crate::proxy::ProxySubsystem::Metadata)?;
+        crate::http::json(client.get(url))
             .await
-            .map_err(|e| format!("Charts request failed: {}", e))?
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse charts response: {}", e))
+            .map_err(|e| format!("Charts request failed: {}", e))
     }
 
     #[tauri::command]
@@ -589,15 +667,10 @@ pub mod external {
             urlencoding::encode(&query)
         );
 
-        reqwest::Client::new()
-            .get(&url)
-            .bearer_auth(token)
-            .send()
-            .await
-            .map_err(|e| format!("Genius request failed: {}", e))?
-            .json()
+        let client = crate::http::client(crate::proxy::ProxySubsystem::Metadata)?;
+        crate::http::json(client.get(&url).bearer_auth(token))
             .await
-            .map_err(|e| format!("Failed to parse Genius response: {}", e))
+            .map_err(|e| format!("Genius request failed: {}", e))
     }
 
     #[tauri::command]
@@ -609,12 +682,10 @@ pub mod external {
             .map_err(|_| "SPOTIFY_TOKEN_ENDPOINT not configured")?;
 
         // Get access token
-        let token_response: serde_json::Value = reqwest::get(&token_endpoint)
+        let client = crate::http::client(crate::proxy::ProxySubsystem::Metadata)?;
+        let token_response: serde_json::Value = crate::http::json(client.get(&token_endpoint))
             .await
-            .map_err(|e| format!("Failed to get Spotify token: {}", e))?
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse token response: {}", e))?;
+            .map_err(|e| format!("Failed to get Spotify token: {}", e))?;
 
         let token = token_response["access_token"]
             .as_str()
@@ -627,15 +698,9 @@ pub mod external {
             type_or_types
         );
 
-        reqwest::Client::new()
-            .get(&url)
-            .bearer_auth(token)
-            .send()
-            .await
-            .map_err(|e| format!("Spotify search failed: {}", e))?
-            .json()
+        crate::http::json(client.get(&url).bearer_auth(token))
             .await
-            .map_err(|e| format!("Failed to parse Spotify response: {}", e))
+            .map_err(|e| format!("Spotify search failed: {}", e))
     }
 
     #[tauri::command]
@@ -672,11 +737,10 @@ pub mod external {
                 return Ok(json);
             }
         }
-        use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, USER_AGENT};
+        use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT};
 
         // Build client with browser-like headers + cookies (as used by onetagger)
         let mut headers = HeaderMap::new();
-        headers.insert(USER_AGENT, HeaderValue::from_static("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/118.0 Safari/537.36"));
         headers.insert(
             ACCEPT,
             HeaderValue::from_static("application/json, text/plain, */*"),
@@ -701,47 +765,19 @@ pub mod external {
             HeaderName::from_static("x-requested-with"),
             HeaderValue::from_static("XMLHttpRequest"),
         );
-        let client = reqwest::Client::builder()
+        let client = crate::http::builder(crate::proxy::ProxySubsystem::Metadata)
             .default_headers(headers)
             .build()
             .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
 
-        // Simple redirect-following GET -> JSON helper (limit 5 hops)
-        async fn get_json_follow_redirects(
+        // The shared client follows redirects and keeps cookies across them
+        async fn get_json(
             client: &reqwest::Client,
-            mut url: reqwest::Url,
+            url: reqwest::Url,
         ) -> Result<serde_json::Value, String> {
-            let mut hops = 0usize;
-            loop {
-                let resp = client
-                    .get(url.clone())
-                    .send()
-                    .await
-                    .map_err(|e| format!("request failed: {}", e))?;
-                if resp.status().is_redirection() {
-                    if hops >= 5 {
-                        return Err("too many redirects".into());
-                    }
-                    let location = resp
-                        .headers()
-                        .get(reqwest::header::LOCATION)
-                        .and_then(|v| v.to_str().ok())
-                        .ok_or_else(|| "redirect without Location".to_string())?;
-                    // Build next URL relative to previous
-                    url = url
-                        .join(location)
-                        .map_err(|e| format!("redirect URL join failed: {}", e))?;
-                    hops += 1;
-                    continue;
-                }
-                if !resp.status().is_success() {
-                    return Err(format!("HTTP {}", resp.status()));
-                }
-                return resp
-                    .json::<serde_json::Value>()
-                    .await
-                    .map_err(|e| format!("response parse failed: {}", e));
-            }
+            crate::http::json(client.get(url))
+                .await
+                .map_err(|e| e.to_string())
         }
 
         async fn get_token(client: &reqwest::Client) -> Result<String, String> {
@@ -757,7 +793,7 @@ pub mod external {
                 .append_pair("user_language", "en")
                 .append_pair("app_id", "web-desktop-app-v1.0")
                 .append_pair("t", &t_ms);
-            let resp = get_json_follow_redirects(client, url)
+            let resp = get_json(client, url)
                 .await
                 .map_err(|e| format!("Musixmatch token request failed: {}", e))?;
             let status = resp["message"]["header"]["status_code"]
@@ -797,7 +833,7 @@ pub mod external {
                 .append_pair("app_id", "web-desktop-app-v1.0")
                 .append_pair("usertoken", token)
                 .append_pair("t", &t_ms);
-            let resp = get_json_follow_redirects(client, url)
+            let resp = get_json(client, url)
                 .await
                 .map_err(|e| format!("Musixmatch macro request failed: {}", e))?;
             Ok(resp)
@@ -1015,8 +1051,9 @@ pub mod downloads {
         track_id: String,
         source_type: String,
         source_hash: String,
+        file_index: Option<usize>,
     ) -> Result<bool, String> {
-        dl::downloads_pause(app, track_id, source_type, source_hash).await
+        dl::downloads_pause(app, track_id, source_type, source_hash, file_index).await
     }
 
     #[tauri::command]
@@ -1025,8 +1062,9 @@ pub mod downloads {
         track_id: String,
         source_type: String,
         source_hash: String,
+        file_index: Option<usize>,
     ) -> Result<bool, String> {
-        dl::downloads_resume(app, track_id, source_type, source_hash).await
+        dl::downloads_resume(app, track_id, source_type, source_hash, file_index).await
     }
 
     #[tauri::command]
@@ -1035,7 +1073,69 @@ pub mod downloads {
         track_id: String,
         source_type: String,
         source_hash: String,
+        file_index: Option<usize>,
+    ) -> Result<bool, String> {
+        dl::downloads_remove(app, track_id, source_type, source_hash, file_index).await
+    }
+
+    /// Queued, running, paused and failed download jobs in run order
+    #[tauri::command]
+    pub async fn downloads_list() -> Result<serde_json::Value, String> {
+        Ok(dl::list_jobs())
+    }
+
+    /// Set a job's priority and/or move it directly ahead of job `before`
+    #[tauri::command]
+    pub async fn downloads_reprioritize(
+        app: tauri::AppHandle,
+        id: String,
+        priority: Option<i32>,
+        before: Option<String>,
     ) -> Result<bool, String> {
-        dl::downloads_remove(app, track_id, source_type, source_hash).await
+        dl::reprioritize(&app, &id, priority, before.as_deref())?;
+        Ok(true)
+    }
+
+    /// Change how many downloads may run at once; returns the applied limit
+    #[tauri::command]
+    pub async fn downloads_set_concurrency(
+        app: tauri::AppHandle,
+        max_concurrent: usize,
+    ) -> Result<usize, String> {
+        Ok(dl::set_max_concurrent(&app, max_concurrent))
+    }
+
+    /// Queue an album/playlist as one batch; progress arrives as `downloads:batch` events
+    #[tauri::command]
+    pub async fn downloads_enqueue_batch(
+        app: tauri::AppHandle,
+        name: String,
+        specs: Vec<dl::JobSpec>,
+        priority: Option<i32>,
+    ) -> Result<String, String> {
+        dl::enqueue_batch(&app, name, specs, priority.unwrap_or(dl::PRIORITY_NORMAL))
+    }
+
+    #[tauri::command]
+    pub async fn downloads_list_batches() -> Result<serde_json::Value, String> {
+        Ok(dl::list_batches())
+    }
+
+    #[tauri::command]
+    pub async fn downloads_pause_batch(app: tauri::AppHandle, id: String) -> Result<bool, String> {
+        dl::set_batch_paused(&app, &id, true)?;
+        Ok(true)
+    }
+
+    #[tauri::command]
+    pub async fn downloads_resume_batch(app: tauri::AppHandle, id: String) -> Result<bool, String> {
+        dl::set_batch_paused(&app, &id, false)?;
+        Ok(true)
+    }
+
+    #[tauri::command]
+    pub async fn downloads_cancel_batch(app: tauri::AppHandle, id: String) -> Result<bool, String> {
+        dl::cancel_batch(&app, &id)?;
+        Ok(true)
     }
 }
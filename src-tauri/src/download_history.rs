@@ -0,0 +1,406 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DEFAULT_QUERY_LIMIT: usize = 100;
+const MAX_QUERY_LIMIT: usize = 1000;
+// Records arriving within this window are written to disk together
+const SAVE_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryStatus {
+    Completed,
+    // Attempt failed; the queue will try again
+    Retrying,
+    // Attempt failed and the job gave up
+    Failed,
+    // Interrupted by a pause; resuming starts a new attempt
+    Paused,
+    // Job was removed while downloading
+    Cancelled,
+}
+
+/// One download attempt
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    pub id: u64,
+    pub job_id: String,
+    pub track_id: String,
+    pub source_type: String,
+    pub source_hash: String,
+    pub file_index: Option<usize>,
+    #[serde(default)]
+    pub batch_id: Option<String>,
+    pub attempt: u32,
+    pub status: HistoryStatus,
+    pub error: Option<String>,
+    // Bytes fetched during this attempt (resumed bytes are not counted again)
+    pub bytes: u64,
+    // Size of the cached file (completed) or of the whole download when known
+    pub total_bytes: Option<u64>,
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub duration_ms: u64,
+    pub avg_speed_bps: u64,
+}
+
+/// How much history is kept
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistorySettings {
+    pub enabled: bool,
+    // Oldest records beyond this count are dropped; 0 = no limit
+    pub max_records: usize,
+    // Records older than this are dropped; 0 = keep forever
+    pub max_age_days: u32,
+}
+
+impl Default for HistorySettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_records: 5000,
+            max_age_days: 90,
+        }
+    }
+}
+
+impl HistorySettings {
+    pub fn load() -> Self {
+        let path = match settings_path("download_history_settings.json") {
+            Ok(p) => p,
+            Err(e) => {
+                println!("[history] {}, using default retention settings", e);
+                return Self::default();
+            }
+        };
+        match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                println!(
+                    "[history] Failed to parse history settings: {}, using defaults",
+                    e
+                );
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize history settings: {}", e))?;
+        write_settings_file("download_history_settings.json", content)?;
+        println!("[history] Saved retention settings");
+        Ok(())
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct HistoryLog {
+    next_id: u64,
+    records: Vec<HistoryRecord>,
+}
+
+impl HistoryLog {
+    fn load() -> Self {
+        let path = match settings_path("download_history.json") {
+            Ok(p) => p,
+            Err(e) => {
+                println!("[history] {}, starting empty", e);
+                return Self::default();
+            }
+        };
+        match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                println!(
+                    "[history] Failed to parse download history, starting empty: {}",
+                    e
+                );
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self)
+            .map_err(|e| format!("Failed to serialize download history: {}", e))
+    }
+
+    fn save(&self) {
+        if let Err(e) = self
+            .to_json()
+            .and_then(|content| write_settings_file("download_history.json", content))
+        {
+            println!("[history] {}", e);
+        }
+    }
+
+    // Apply the retention settings; records are kept oldest first
+    fn prune(&mut self, settings: &HistorySettings) {
+        if settings.max_age_days > 0 {
+            let cutoff = now_secs().saturating_sub(settings.max_age_days as u64 * 24 * 60 * 60);
+            self.records.retain(|r| r.finished_at >= cutoff);
+        }
+        if settings.max_records > 0 && self.records.len() > settings.max_records {
+            let excess = self.records.len() - settings.max_records;
+            self.records.drain(..excess);
+        }
+    }
+}
+
+fn settings_path(name: &str) -> Result<PathBuf, String> {
+    let data_dir = dirs::data_dir().ok_or("Failed to get system data directory")?;
+
+    let mut path = data_dir;
+    path.push("com.freely.player");
+    path.push(name);
+    Ok(path)
+}
+
+fn write_settings_file(name: &str, content: String) -> Result<(), String> {
+    let path = settings_path(name)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+    std::fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", name, e))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+static SETTINGS: Lazy<Mutex<HistorySettings>> = Lazy::new(|| Mutex::new(HistorySettings::load()));
+static LOG: Lazy<Mutex<HistoryLog>> = Lazy::new(|| Mutex::new(HistoryLog::load()));
+// A delayed write is already scheduled
+static SAVE_PENDING: AtomicBool = AtomicBool::new(false);
+
+// Write the log once the current burst of records has settled. Only serialization happens
+// under the lock; the file write runs on its own thread.
+fn schedule_save() {
+    if SAVE_PENDING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    std::thread::spawn(|| {
+        std::thread::sleep(SAVE_DELAY);
+        flush();
+    });
+}
+
+/// Write pending history records now (e.g. before the app closes)
+pub fn flush() {
+    if !SAVE_PENDING.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    let content = LOG.lock().unwrap().to_json();
+    if let Err(e) = content.and_then(|c| write_settings_file("download_history.json", c)) {
+        println!("[history] {}", e);
+    }
+}
+
+pub fn get_history_settings() -> HistorySettings {
+    SETTINGS.lock().unwrap().clone()
+}
+
+/// Update retention settings and apply them to the stored history right away
+pub fn update_history_settings<F>(updater: F) -> Result<HistorySettings, String>
+where
+    F: FnOnce(&mut HistorySettings),
+{
+    let updated = {
+        let mut settings = SETTINGS.lock().unwrap();
+        updater(&mut settings);
+        settings.save()?;
+        settings.clone()
+    };
+    let mut log = LOG.lock().unwrap();
+    log.prune(&updated);
+    log.save();
+    Ok(updated)
+}
+
+/// Append a finished attempt; `id` is assigned here
+pub fn record(mut entry: HistoryRecord) {
+    let settings = get_history_settings();
+    if !settings.enabled {
+        return;
+    }
+    let mut log = LOG.lock().unwrap();
+    log.next_id += 1;
+    entry.id = log.next_id;
+    log.records.push(entry);
+    log.prune(&settings);
+    drop(log);
+    schedule_save();
+}
+
+/// Filter for `downloads_history`; every field is optional
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct HistoryQuery {
+    pub status: Option<Vec<HistoryStatus>>,
+    pub source_type: Option<String>,
+    pub track_id: Option<String>,
+    pub batch_id: Option<String>,
+    // Case-insensitive match against track id, source hash and error text
+    pub search: Option<String>,
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    pub offset: usize,
+    pub limit: Option<usize>,
+    // Oldest first instead of newest first
+    pub ascending: bool,
+}
+
+impl HistoryQuery {
+    fn matches(&self, r: &HistoryRecord) -> bool {
+        if let Some(statuses) = &self.status {
+            if !statuses.contains(&r.status) {
+                return false;
+            }
+        }
+        if self
+            .source_type
+            .as_ref()
+            .is_some_and(|t| *t != r.source_type)
+            || self.track_id.as_ref().is_some_and(|t| *t != r.track_id)
+            || self.batch_id.is_some() && self.batch_id != r.batch_id
+            || self.since.is_some_and(|t| r.finished_at < t)
+            || self.until.is_some_and(|t| r.finished_at > t)
+        {
+            return false;
+        }
+        if let Some(needle) = self.search.as_ref().map(|s| s.to_lowercase()) {
+            let hit = r.track_id.to_lowercase().contains(&needle)
+                || r.source_hash.to_lowercase().contains(&needle)
+                || r.error
+                    .as_ref()
+                    .is_some_and(|e| e.to_lowercase().contains(&needle));
+            if !hit {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Reliability figures for one source type over the matched records
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceStats {
+    pub attempts: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub retried: usize,
+    // Paused or cancelled mid-download
+    pub interrupted: usize,
+    pub success_rate: f64,
+    pub bytes: u64,
+    pub avg_speed_bps: u64,
+    // Most frequent error messages with their counts
+    pub top_errors: Vec<(String, usize)>,
+}
+
+fn source_stats(records: &[&HistoryRecord]) -> BTreeMap<String, SourceStats> {
+    let mut grouped: BTreeMap<String, Vec<&HistoryRecord>> = BTreeMap::new();
+    for r in records {
+        grouped.entry(r.source_type.clone()).or_default().push(r);
+    }
+    grouped
+        .into_iter()
+        .map(|(source_type, items)| {
+            let mut stats = SourceStats {
+                attempts: items.len(),
+                ..Default::default()
+            };
+            let mut errors: BTreeMap<String, usize> = BTreeMap::new();
+            let (mut busy_ms, mut busy_bytes) = (0u64, 0u64);
+            for r in &items {
+                match r.status {
+                    HistoryStatus::Completed => stats.completed += 1,
+                    HistoryStatus::Failed => stats.failed += 1,
+                    HistoryStatus::Retrying => stats.retried += 1,
+                    HistoryStatus::Paused | HistoryStatus::Cancelled => stats.interrupted += 1,
+                }
+                stats.bytes += r.bytes;
+                if r.duration_ms > 0 {
+                    busy_ms += r.duration_ms;
+                    busy_bytes += r.bytes;
+                }
+                if let Some(e) = &r.error {
+                    *errors.entry(e.clone()).or_default() += 1;
+                }
+            }
+            let decided = stats.completed + stats.failed + stats.retried;
+            if decided > 0 {
+                stats.success_rate = stats.completed as f64 / decided as f64;
+            }
+            if busy_ms > 0 {
+                stats.avg_speed_bps = busy_bytes * 1000 / busy_ms;
+            }
+            let mut errors: Vec<(String, usize)> = errors.into_iter().collect();
+            errors.sort_by(|a, b| b.1.cmp(&a.1));
+            errors.truncate(5);
+            stats.top_errors = errors;
+            (source_type, stats)
+        })
+        .collect()
+}
+
+/// Matching records (newest first by default) plus per-source statistics over all matches
+pub fn query(q: &HistoryQuery) -> serde_json::Value {
+    let log = LOG.lock().unwrap();
+    let mut matched: Vec<&HistoryRecord> = log.records.iter().filter(|r| q.matches(r)).collect();
+    if !q.ascending {
+        matched.reverse();
+    }
+    let limit = q.limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT);
+    let page: Vec<&HistoryRecord> = matched.iter().skip(q.offset).take(limit).copied().collect();
+    serde_json::json!({
+        "total": matched.len(),
+        "offset": q.offset,
+        "limit": limit,
+        "records": page,
+        "bySource": source_stats(&matched),
+    })
+}
+
+#[tauri::command]
+pub async fn downloads_history(query: Option<HistoryQuery>) -> Result<serde_json::Value, String> {
+    Ok(self::query(&query.unwrap_or_default()))
+}
+
+#[tauri::command]
+pub async fn downloads_history_clear() -> Result<bool, String> {
+    let mut log = LOG.lock().unwrap();
+    log.records.clear();
+    log.save();
+    Ok(true)
+}
+
+#[tauri::command]
+pub async fn downloads_history_get_settings() -> Result<serde_json::Value, String> {
+    serde_json::to_value(get_history_settings())
+        .map_err(|e| format!("Failed to serialize history settings: {}", e))
+}
+
+#[tauri::command]
+pub async fn downloads_history_set_settings(
+    settings: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let incoming: HistorySettings =
+        serde_json::from_value(settings).map_err(|e| format!("Invalid history settings: {}", e))?;
+    let updated = update_history_settings(|s| *s = incoming)?;
+    serde_json::to_value(updated)
+        .map_err(|e| format!("Failed to serialize history settings: {}", e))
+}
@@ -1,12 +1,16 @@
-use crate::cache::{create_cache_filename, get_cache_dir};
+use crate::cache::{create_cache_filename_with_index, get_cache_dir};
+use crate::download_history::{self, HistoryRecord, HistoryStatus};
 use once_cell::sync::Lazy;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
 use tauri::Emitter; // for app.emit
 use tokio::sync::Notify;
 
-// Control state for a single download (keyed by base name from create_cache_filename)
+// Control state for a single download (keyed by base name from create_cache_filename_with_index)
 pub struct DownloadControl {
     paused: AtomicBool,
     cancel: AtomicBool,
@@ -99,15 +103,1075 @@ pub async fn wait_while_paused_or_until_cancel(base: &str) {
     }
 }
 
+// ---- Download queue ----
+//
+// Every cache download goes through a persisted job table. At most `max_concurrent`
+// jobs run at once; the rest wait in priority order (higher first, then FIFO).
+// Job ids are the cache base name, so the pause/cancel controls above apply to them.
+
+/// Default priority for user-initiated downloads
+pub const PRIORITY_NORMAL: i32 = 0;
+/// Priority for downloads the current playback is waiting on
+pub const PRIORITY_HIGH: i32 = 100;
+const DEFAULT_MAX_CONCURRENT: usize = 2;
+const MAX_ATTEMPTS: u32 = 3;
+const RETRY_BASE_DELAY_SECS: u64 = 30;
+const RETRY_MAX_DELAY_SECS: u64 = 600;
+// Finished jobs are kept (with their hook outcomes) only while there are at most this many
+const MAX_COMPLETED_KEPT: usize = 100;
+
+/// What to download; mirrors the arguments of `cache_download_and_store`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobSpec {
+    pub track_id: String,
+    pub source_type: String,
+    pub source_hash: String,
+    pub url: String,
+    pub file_index: Option<usize>,
+}
+
+impl JobSpec {
+    /// Direct YouTube CDN links expire within hours, so a queued or resumed job would fail on
+    /// them; keep the video id instead and let each run resolve a fresh stream URL
+    fn with_stable_source(mut self) -> Self {
+        let direct =
+            self.url.contains("googlevideo.com") || self.url.contains("youtube.com/api/manifest");
+        let is_video_id = self.source_hash.len() == 11
+            && self
+                .source_hash
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+        if self.source_type == "youtube" && direct && is_video_id {
+            self.url = self.source_hash.clone();
+        }
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Paused,
+    Failed,
+    // Downloaded; kept in the table while post-download hooks run and report
+    Completed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadJob {
+    pub id: String,
+    #[serde(flatten)]
+    pub spec: JobSpec,
+    pub priority: i32,
+    // Queue position within a priority; lower runs first
+    pub seq: u64,
+    pub status: JobStatus,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+    // Earliest time a failed attempt may be retried
+    #[serde(default)]
+    pub retry_at: Option<u64>,
+    // Batch (album/playlist download) this job was queued with
+    #[serde(default)]
+    pub batch_id: Option<String>,
+    // Post-download hook results, in the order the hooks ran
+    #[serde(default)]
+    pub hooks: Vec<crate::hooks::HookOutcome>,
+}
+
+/// A group of jobs queued together; progress is reported for the group as a whole
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadBatch {
+    pub id: String,
+    pub name: String,
+    pub job_ids: Vec<String>,
+    // Jobs that finished, with their cached size in bytes
+    #[serde(default)]
+    pub completed: HashMap<String, u64>,
+    pub created_at: u64,
+}
+
+// Persisted queue (same storage location as the other settings files)
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+struct JobTable {
+    max_concurrent: usize,
+    next_seq: u64,
+    jobs: Vec<DownloadJob>,
+    batches: Vec<DownloadBatch>,
+}
+
+impl Default for JobTable {
+    fn default() -> Self {
+        Self {
+            max_concurrent: DEFAULT_MAX_CONCURRENT,
+            next_seq: 0,
+            jobs: Vec::new(),
+            batches: Vec::new(),
+        }
+    }
+}
+
+impl JobTable {
+    fn load() -> Self {
+        let path = match Self::get_path() {
+            Ok(p) => p,
+            Err(e) => {
+                println!("[downloads] Failed to get job table path: {}, starting empty", e);
+                return Self::default();
+            }
+        };
+        if !path.exists() {
+            return Self::default();
+        }
+        match std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|c| serde_json::from_str::<JobTable>(&c).map_err(|e| e.to_string()))
+        {
+            Ok(mut table) => {
+                table.max_concurrent = table.max_concurrent.max(1);
+                for job in table.jobs.iter_mut() {
+                    job.spec = job.spec.clone().with_stable_source();
+                }
+                println!("[downloads] Loaded {} queued jobs from: {}", table.jobs.len(), path.display());
+                table
+            }
+            Err(e) => {
+                println!("[downloads] Failed to load job table: {}, starting empty", e);
+                Self::default()
+            }
+        }
+    }
+
+    fn save(&self) {
+        let result = Self::get_path().and_then(|path| {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+            }
+            let content = serde_json::to_string_pretty(self)
+                .map_err(|e| format!("Failed to serialize job table: {}", e))?;
+            std::fs::write(&path, content).map_err(|e| format!("Failed to write job table: {}", e))
+        });
+        if let Err(e) = result {
+            println!("[downloads] {}", e);
+        }
+    }
+
+    fn get_path() -> Result<PathBuf, String> {
+        // Tests keep their jobs in memory, away from the user's queue
+        if cfg!(test) {
+            return Err("not persisted in tests".to_string());
+        }
+        let data_dir = dirs::data_dir().ok_or("Failed to get system data directory")?;
+
+        let mut path = data_dir;
+        path.push("com.freely.player");
+        path.push("download_jobs.json");
+        Ok(path)
+    }
+
+    fn get_mut(&mut self, id: &str) -> Option<&mut DownloadJob> {
+        self.jobs.iter_mut().find(|j| j.id == id)
+    }
+
+    fn take_seq(&mut self) -> u64 {
+        self.next_seq += 1;
+        self.next_seq
+    }
+
+    /// Insert a job, or refresh the existing one with the same id. Returns whether
+    /// the job (re)entered the queue.
+    fn upsert(&mut self, id: &str, spec: JobSpec, priority: i32, batch_id: Option<&str>) -> (bool, DownloadJob) {
+        let spec = spec.with_stable_source();
+        let now = now_secs();
+        let seq = self.take_seq();
+        match self.get_mut(id) {
+            Some(job) => {
+                let added = matches!(job.status, JobStatus::Failed | JobStatus::Completed);
+                if added {
+                    // Explicit re-request of a failed (or evicted) download starts a fresh round of attempts
+                    job.status = JobStatus::Queued;
+                    job.attempts = 0;
+                    job.retry_at = None;
+                    job.last_error = None;
+                    job.hooks.clear();
+                    job.seq = seq;
+                    job.spec.url = spec.url;
+                }
+                job.priority = job.priority.max(priority);
+                if job.batch_id.is_none() {
+                    job.batch_id = batch_id.map(str::to_string);
+                }
+                job.updated_at = now;
+                (added, job.clone())
+            }
+            None => {
+                let job = DownloadJob {
+                    id: id.to_string(),
+                    spec,
+                    priority,
+                    seq,
+                    status: JobStatus::Queued,
+                    attempts: 0,
+                    last_error: None,
+                    created_at: now,
+                    updated_at: now,
+                    retry_at: None,
+                    batch_id: batch_id.map(str::to_string),
+                    hooks: Vec::new(),
+                };
+                self.jobs.push(job.clone());
+                (true, job)
+            }
+        }
+    }
+
+    // Drop the oldest finished jobs beyond the retention limit
+    fn prune_completed(&mut self) {
+        let mut finished: Vec<(u64, String)> = self
+            .jobs
+            .iter()
+            .filter(|j| j.status == JobStatus::Completed)
+            .map(|j| (j.updated_at, j.id.clone()))
+            .collect();
+        if finished.len() <= MAX_COMPLETED_KEPT {
+            return;
+        }
+        finished.sort();
+        let stale: HashSet<String> = finished[..finished.len() - MAX_COMPLETED_KEPT]
+            .iter()
+            .map(|(_, id)| id.clone())
+            .collect();
+        self.jobs.retain(|j| !stale.contains(&j.id));
+    }
+
+    /// Jobs in the order the queue would run them
+    fn ordered(&self) -> Vec<&DownloadJob> {
+        let rank = |s: JobStatus| match s {
+            JobStatus::Running => 0,
+            JobStatus::Queued => 1,
+            JobStatus::Paused => 2,
+            JobStatus::Failed => 3,
+            JobStatus::Completed => 4,
+        };
+        let mut jobs: Vec<&DownloadJob> = self.jobs.iter().collect();
+        jobs.sort_by(|a, b| {
+            rank(a.status)
+                .cmp(&rank(b.status))
+                .then(b.priority.cmp(&a.priority))
+                .then(a.seq.cmp(&b.seq))
+        });
+        jobs
+    }
+}
+
+static JOBS: Lazy<Mutex<JobTable>> = Lazy::new(|| Mutex::new(JobTable::load()));
+// Job ids with a live download task (a paused job keeps its task but not its slot)
+static ACTIVE: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+// Last error reported by the downloader for each job id
+static LAST_ERRORS: Lazy<Mutex<HashMap<String, String>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn emit_job(app: &tauri::AppHandle, job: &DownloadJob) {
+    let _ = app.emit("downloads:job", job);
+}
+
+/// Remember an error message for the job and hand it back for the event payload
+pub fn note_error(base: &str, message: impl Into<String>) -> String {
+    let message = message.into();
+    LAST_ERRORS
+        .lock()
+        .unwrap()
+        .insert(base.to_string(), message.clone());
+    message
+}
+
+pub fn take_error(base: &str) -> Option<String> {
+    LAST_ERRORS.lock().unwrap().remove(base)
+}
+
+/// Attach a post-download hook result to its job
+pub fn record_hook_outcome(app: &tauri::AppHandle, id: &str, outcome: crate::hooks::HookOutcome) {
+    let job = {
+        let mut table = JOBS.lock().unwrap();
+        let job = match table.get_mut(id) {
+            Some(j) => j,
+            None => return,
+        };
+        job.hooks.push(outcome);
+        job.updated_at = now_secs();
+        let job = job.clone();
+        table.save();
+        job
+    };
+    emit_job(app, &job);
+}
+
+pub fn job_exists(id: &str) -> bool {
+    JOBS.lock().unwrap().jobs.iter().any(|j| j.id == id)
+}
+
+/// Queued, running or paused jobs for files of the torrent with `info_hash` that aren't
+/// cached yet; the engine's data for the torrent is still needed while any remain
+pub fn torrent_files_pending(info_hash: &str) -> usize {
+    let candidates: Vec<JobSpec> = JOBS
+        .lock()
+        .unwrap()
+        .jobs
+        .iter()
+        .filter(|j| {
+            matches!(
+                j.status,
+                JobStatus::Queued | JobStatus::Running | JobStatus::Paused
+            )
+        })
+        .filter(|j| j.spec.source_type == "torrent")
+        .filter(|j| {
+            j.spec.source_hash.eq_ignore_ascii_case(info_hash)
+                || crate::torrents::info_hash_from_key(&j.spec.url).as_deref() == Some(info_hash)
+        })
+        .map(|j| j.spec.clone())
+        .collect();
+    // A job whose file just landed in the cache may not be marked completed yet
+    candidates
+        .iter()
+        .filter(|spec| {
+            crate::cache::get_cached_file_path_with_index(
+                &spec.track_id,
+                &spec.source_type,
+                &spec.source_hash,
+                spec.file_index,
+            )
+            .is_none()
+        })
+        .count()
+}
+
+/// Add a download to the queue. Returns false when an equivalent job is already
+/// queued or running (its priority is raised if the new request asks for more).
+pub fn enqueue(app: &tauri::AppHandle, spec: JobSpec, priority: i32) -> Result<bool, String> {
+    let id = create_cache_filename_with_index(
+        &spec.track_id,
+        &spec.source_type,
+        &spec.source_hash,
+        spec.file_index,
+    );
+    let (added, job) = {
+        let mut table = JOBS.lock().unwrap();
+        let (added, job) = table.upsert(&id, spec, priority, None);
+        table.save();
+        (added, job)
+    };
+    if added {
+        println!("[downloads] Queued {} (priority {})", id, job.priority);
+    }
+    emit_job(app, &job);
+    pump(app);
+    Ok(added)
+}
+
+/// Start queued jobs until the concurrency limit is reached
+fn pump(app: &tauri::AppHandle) {
+    let now = now_secs();
+    let mut started = Vec::new();
+    {
+        let mut table = JOBS.lock().unwrap();
+        let mut active = ACTIVE.lock().unwrap();
+        let mut running = table
+            .jobs
+            .iter()
+            .filter(|j| j.status == JobStatus::Running && active.contains(&j.id))
+            .count();
+        let candidates: Vec<String> = table
+            .ordered()
+            .into_iter()
+            .filter(|j| j.status == JobStatus::Queued)
+            .filter(|j| j.retry_at.map_or(true, |t| t <= now))
+            .filter(|j| !active.contains(&j.id))
+            .map(|j| j.id.clone())
+            .collect();
+        for id in candidates {
+            if running >= table.max_concurrent {
+                break;
+            }
+            if let Some(job) = table.get_mut(&id) {
+                job.status = JobStatus::Running;
+                job.attempts += 1;
+                job.retry_at = None;
+                job.updated_at = now;
+                active.insert(id);
+                running += 1;
+                started.push(job.clone());
+            }
+        }
+        if !started.is_empty() {
+            table.save();
+        }
+    }
+    for job in started {
+        emit_job(app, &job);
+        tauri::async_runtime::spawn(run_job(app.clone(), job));
+    }
+}
+
+async fn run_job(app: tauri::AppHandle, job: DownloadJob) {
+    println!("[downloads] Starting {} (attempt {})", job.id, job.attempts);
+    let spec = job.spec.clone();
+    let started = std::time::Instant::now();
+    let started_at = now_secs();
+    let start_covered = partial_state(&job.id).map(|m| m.covered()).unwrap_or(0);
+    let result = crate::cache::run_cache_download(
+        Some(app.clone()),
+        spec.track_id,
+        spec.source_type,
+        spec.source_hash,
+        spec.url,
+        spec.file_index,
+    )
+    .await;
+    ACTIVE.lock().unwrap().remove(&job.id);
+
+    let mut retry_in: Option<u64> = None;
+    let finished_size = if result.is_ok() { cached_size(&job.spec) } else { 0 };
+    let run_hooks = result.is_ok() && crate::hooks::has_hooks_for(&job.spec.source_type);
+    let error = result.as_ref().err().cloned();
+    let updated = {
+        let mut table = JOBS.lock().unwrap();
+        let now = now_secs();
+        let updated = match result {
+            // Removed while running
+            _ if table.get_mut(&job.id).is_none() => None,
+            Ok(()) => {
+                println!("[downloads] Finished {}", job.id);
+                let completed = if run_hooks {
+                    // Keep the job so the hook outcomes have somewhere to go
+                    let entry = table.get_mut(&job.id).unwrap();
+                    entry.status = JobStatus::Completed;
+                    entry.last_error = None;
+                    entry.updated_at = now;
+                    Some(entry.clone())
+                } else {
+                    table.jobs.retain(|j| j.id != job.id);
+                    None
+                };
+                table.prune_completed();
+                // The same track may have been queued by several batches
+                for batch in table
+                    .batches
+                    .iter_mut()
+                    .filter(|b| b.job_ids.contains(&job.id))
+                {
+                    batch.completed.insert(job.id.clone(), finished_size);
+                }
+                completed
+            }
+            Err(e) => {
+                let entry = table.get_mut(&job.id).unwrap();
+                println!("[downloads] {} failed (attempt {}): {}", entry.id, entry.attempts, e);
+                entry.last_error = Some(e);
+                entry.updated_at = now;
+                if entry.status == JobStatus::Paused {
+                    // Stays paused; resuming requeues it
+                } else if entry.attempts < MAX_ATTEMPTS {
+                    let delay = (RETRY_BASE_DELAY_SECS << (entry.attempts - 1).min(5)).min(RETRY_MAX_DELAY_SECS);
+                    entry.status = JobStatus::Queued;
+                    entry.retry_at = Some(now + delay);
+                    retry_in = Some(delay);
+                } else {
+                    entry.status = JobStatus::Failed;
+                }
+                Some(entry.clone())
+            }
+        };
+        table.save();
+        updated
+    };
+    let status = match (&error, updated.as_ref()) {
+        (None, _) => HistoryStatus::Completed,
+        (Some(_), None) => HistoryStatus::Cancelled,
+        (Some(_), Some(j)) if j.status == JobStatus::Failed => HistoryStatus::Failed,
+        (Some(_), Some(j)) if j.status == JobStatus::Paused => HistoryStatus::Paused,
+        (Some(_), Some(_)) => HistoryStatus::Retrying,
+    };
+    record_history(&job, status, error, finished_size, start_covered, started, started_at);
+    if let Some(job) = updated.as_ref() {
+        emit_job(&app, job);
+        if job.status == JobStatus::Completed {
+            tauri::async_runtime::spawn(crate::hooks::run_for_job(app.clone(), job.clone()));
+        }
+    }
+    if let Some(delay) = retry_in {
+        let app_retry = app.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_secs(delay)).await;
+            pump(&app_retry);
+        });
+    }
+    pump(&app);
+}
+
+fn partial_state(id: &str) -> Option<crate::partial::PartialMeta> {
+    let cache_dir = get_cache_dir()?;
+    crate::partial::load(&cache_dir.join(format!("{}.part", id)))
+}
+
+fn record_history(
+    job: &DownloadJob,
+    status: HistoryStatus,
+    error: Option<String>,
+    finished_size: u64,
+    start_covered: u64,
+    started: std::time::Instant,
+    started_at: u64,
+) {
+    let duration_ms = started.elapsed().as_millis() as u64;
+    // A finished download no longer has a .part; otherwise count what this attempt added to it
+    let (bytes, total_bytes) = if status == HistoryStatus::Completed {
+        (finished_size.saturating_sub(start_covered), Some(finished_size))
+    } else {
+        let meta = partial_state(&job.id);
+        let covered = meta.as_ref().map(|m| m.covered()).unwrap_or(start_covered);
+        (covered.saturating_sub(start_covered), meta.and_then(|m| m.total))
+    };
+    download_history::record(HistoryRecord {
+        id: 0,
+        job_id: job.id.clone(),
+        track_id: job.spec.track_id.clone(),
+        source_type: job.spec.source_type.clone(),
+        source_hash: job.spec.source_hash.clone(),
+        file_index: job.spec.file_index,
+        batch_id: job.batch_id.clone(),
+        attempt: job.attempts,
+        status,
+        error,
+        bytes,
+        total_bytes,
+        started_at,
+        finished_at: now_secs(),
+        duration_ms,
+        avg_speed_bps: if duration_ms > 0 { bytes * 1000 / duration_ms } else { 0 },
+    });
+}
+
+/// Requeue jobs interrupted by the last shutdown and start the queue
+pub fn resume_queue(app: tauri::AppHandle) {
+    let pending_retry = {
+        let mut table = JOBS.lock().unwrap();
+        let now = now_secs();
+        for job in table.jobs.iter_mut() {
+            if job.status == JobStatus::Running {
+                job.status = JobStatus::Queued;
+                // The interrupted attempt doesn't count against the job
+                job.attempts = job.attempts.saturating_sub(1);
+            }
+        }
+        table.save();
+        if !table.batches.is_empty() {
+            ensure_batch_ticker(app.clone());
+        }
+        table
+            .jobs
+            .iter()
+            .filter(|j| j.status == JobStatus::Queued)
+            .filter_map(|j| j.retry_at)
+            .filter(|t| *t > now)
+            .map(|t| t - now)
+            .min()
+    };
+    pump(&app);
+    if let Some(delay) = pending_retry {
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_secs(delay)).await;
+            pump(&app);
+        });
+    }
+}
+
+fn set_job_paused(app: &tauri::AppHandle, id: &str, pause: bool) {
+    let job = {
+        let mut table = JOBS.lock().unwrap();
+        let active = ACTIVE.lock().unwrap().contains(id);
+        let job = match table.get_mut(id) {
+            Some(j) => j,
+            None => return,
+        };
+        job.status = match (pause, job.status) {
+            (true, JobStatus::Queued | JobStatus::Running) => JobStatus::Paused,
+            (false, JobStatus::Paused) if active => JobStatus::Running,
+            (false, JobStatus::Paused) => JobStatus::Queued,
+            (_, status) => status,
+        };
+        job.updated_at = now_secs();
+        let job = job.clone();
+        table.save();
+        job
+    };
+    emit_job(app, &job);
+    // A paused job frees its slot; a resumed one may claim one again
+    pump(app);
+}
+
+fn remove_job(app: &tauri::AppHandle, id: &str) {
+    let removed = {
+        let mut table = JOBS.lock().unwrap();
+        let before = table.jobs.len();
+        table.jobs.retain(|j| j.id != id);
+        let removed = table.jobs.len() != before;
+        if removed {
+            table.save();
+        }
+        removed
+    };
+    // No task will consume the cancel flag, so don't let it leak into a later re-download
+    if !ACTIVE.lock().unwrap().contains(id) {
+        clear_control(id);
+    }
+    if removed {
+        pump(app);
+    }
+}
+
+/// Snapshot of the queue in run order, with live byte counts for running jobs
+pub fn list_jobs() -> serde_json::Value {
+    let table = JOBS.lock().unwrap();
+    let jobs: Vec<serde_json::Value> = table
+        .ordered()
+        .into_iter()
+        .map(|job| {
+            let mut v = serde_json::to_value(job).unwrap_or(serde_json::Value::Null);
+            if let Some((bytes, total)) = crate::cache::get_inflight_status_by_base(&job.id) {
+                v["bytes_downloaded"] = serde_json::json!(bytes);
+                v["total_bytes"] = serde_json::json!(total);
+            }
+            v
+        })
+        .collect();
+    serde_json::json!({
+        "max_concurrent": table.max_concurrent,
+        "jobs": jobs,
+    })
+}
+
+/// Change a job's priority and/or move it in front of another job
+pub fn reprioritize(
+    app: &tauri::AppHandle,
+    id: &str,
+    priority: Option<i32>,
+    before: Option<&str>,
+) -> Result<(), String> {
+    let job = {
+        let mut table = JOBS.lock().unwrap();
+        let anchor = match before {
+            Some(other) => {
+                let other = table
+                    .jobs
+                    .iter()
+                    .find(|j| j.id == other)
+                    .ok_or_else(|| format!("Unknown download job: {}", other))?;
+                Some((other.priority, other.seq))
+            }
+            None => None,
+        };
+        if table.get_mut(id).is_none() {
+            return Err(format!("Unknown download job: {}", id));
+        }
+        if let Some((anchor_priority, anchor_seq)) = anchor {
+            // Make room directly ahead of the anchor
+            for j in table.jobs.iter_mut() {
+                if j.seq >= anchor_seq {
+                    j.seq += 1;
+                }
+            }
+            table.next_seq += 1;
+            let job = table.get_mut(id).unwrap();
+            job.priority = priority.unwrap_or(anchor_priority);
+            job.seq = anchor_seq;
+        } else if let Some(p) = priority {
+            table.get_mut(id).unwrap().priority = p;
+        }
+        let job = table.get_mut(id).unwrap();
+        job.updated_at = now_secs();
+        let job = job.clone();
+        table.save();
+        job
+    };
+    emit_job(app, &job);
+    pump(app);
+    Ok(())
+}
+
+pub fn set_max_concurrent(app: &tauri::AppHandle, max: usize) -> usize {
+    let max = max.clamp(1, 16);
+    {
+        let mut table = JOBS.lock().unwrap();
+        table.max_concurrent = max;
+        table.save();
+    }
+    pump(app);
+    max
+}
+
+// ---- Batches ----
+//
+// An album or playlist is queued as one batch. The jobs run through the normal queue;
+// the batch only aggregates their progress and fans out pause/resume/cancel.
+
+const BATCH_TICK: std::time::Duration = std::time::Duration::from_secs(1);
+
+static BATCH_TICKER: AtomicBool = AtomicBool::new(false);
+// Smoothed transfer rate per batch: (last sample time, bytes at that time, bytes/s)
+static BATCH_RATES: Lazy<Mutex<HashMap<String, (std::time::Instant, u64, f64)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Aggregate state of a batch, emitted as `downloads:batch`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchProgress {
+    pub id: String,
+    pub name: String,
+    pub total_tracks: usize,
+    pub done_tracks: usize,
+    pub failed_tracks: usize,
+    pub running_tracks: usize,
+    pub paused_tracks: usize,
+    // Jobs removed from the queue individually
+    pub removed_tracks: usize,
+    pub bytes_downloaded: u64,
+    // Best estimate of the batch size; unknown track sizes are extrapolated from known ones
+    pub total_bytes: Option<u64>,
+    pub speed_bps: u64,
+    pub eta_secs: Option<u64>,
+    pub failures: Vec<serde_json::Value>,
+    pub finished: bool,
+}
+
+fn cached_size(spec: &JobSpec) -> u64 {
+    crate::cache::get_cached_file_path_with_index(
+        &spec.track_id,
+        &spec.source_type,
+        &spec.source_hash,
+        spec.file_index,
+    )
+    .and_then(|p| std::fs::metadata(p).ok())
+    .map(|m| m.len())
+    .unwrap_or(0)
+}
+
+fn batch_progress(table: &JobTable, batch: &DownloadBatch) -> BatchProgress {
+    let mut progress = BatchProgress {
+        id: batch.id.clone(),
+        name: batch.name.clone(),
+        total_tracks: batch.job_ids.len(),
+        done_tracks: 0,
+        failed_tracks: 0,
+        running_tracks: 0,
+        paused_tracks: 0,
+        removed_tracks: 0,
+        bytes_downloaded: 0,
+        total_bytes: None,
+        speed_bps: 0,
+        eta_secs: None,
+        failures: Vec::new(),
+        finished: false,
+    };
+    let mut known_total = 0u64;
+    let mut known_tracks = 0usize;
+    let mut pending_tracks = 0usize;
+    for id in &batch.job_ids {
+        if let Some(size) = batch.completed.get(id) {
+            progress.done_tracks += 1;
+            progress.bytes_downloaded += size;
+            known_total += size;
+            known_tracks += 1;
+            continue;
+        }
+        let job = match table.jobs.iter().find(|j| &j.id == id) {
+            Some(j) => j,
+            None => {
+                progress.removed_tracks += 1;
+                continue;
+            }
+        };
+        match job.status {
+            JobStatus::Failed => {
+                progress.failed_tracks += 1;
+                progress.failures.push(serde_json::json!({
+                    "id": job.id,
+                    "trackId": job.spec.track_id,
+                    "error": job.last_error,
+                }));
+                continue;
+            }
+            JobStatus::Completed => {
+                progress.done_tracks += 1;
+                continue;
+            }
+            JobStatus::Running => progress.running_tracks += 1,
+            JobStatus::Paused => progress.paused_tracks += 1,
+            JobStatus::Queued => {}
+        }
+        match crate::cache::get_inflight_status_by_base(&job.id) {
+            Some((bytes, Some(total))) => {
+                progress.bytes_downloaded += bytes;
+                known_total += total;
+                known_tracks += 1;
+            }
+            Some((bytes, None)) => {
+                progress.bytes_downloaded += bytes;
+                pending_tracks += 1;
+            }
+            None => pending_tracks += 1,
+        }
+    }
+    if known_tracks > 0 {
+        let average = known_total / known_tracks as u64;
+        progress.total_bytes = Some(known_total + average * pending_tracks as u64);
+    }
+    progress.finished =
+        progress.done_tracks + progress.failed_tracks + progress.removed_tracks >= progress.total_tracks;
+    progress
+}
+
+// Fold the latest byte count into the batch's smoothed rate and derive an ETA
+fn apply_rate(progress: &mut BatchProgress) {
+    let now = std::time::Instant::now();
+    let mut rates = BATCH_RATES.lock().unwrap();
+    let entry = rates
+        .entry(progress.id.clone())
+        .or_insert((now, progress.bytes_downloaded, 0.0));
+    let elapsed = now.duration_since(entry.0).as_secs_f64();
+    if elapsed >= 0.5 {
+        let delta = progress.bytes_downloaded.saturating_sub(entry.1) as f64;
+        let sample = delta / elapsed;
+        entry.2 = if entry.2 == 0.0 { sample } else { entry.2 * 0.7 + sample * 0.3 };
+        entry.0 = now;
+        entry.1 = progress.bytes_downloaded;
+    }
+    progress.speed_bps = entry.2 as u64;
+    if let (Some(total), true) = (progress.total_bytes, entry.2 > 0.0) {
+        let remaining = total.saturating_sub(progress.bytes_downloaded) as f64;
+        progress.eta_secs = Some((remaining / entry.2).ceil() as u64);
+    }
+}
+
+/// Emit progress for every batch; finished batches get a last event and are dropped.
+/// Returns whether any batch is still in progress.
+fn emit_batches(app: &tauri::AppHandle) -> bool {
+    let mut updates = {
+        let mut table = JOBS.lock().unwrap();
+        let updates: Vec<BatchProgress> = table
+            .batches
+            .iter()
+            .map(|b| batch_progress(&table, b))
+            .collect();
+        let before = table.batches.len();
+        table
+            .batches
+            .retain(|b| !updates.iter().any(|u| u.id == b.id && u.finished));
+        if table.batches.len() != before {
+            table.save();
+        }
+        updates
+    };
+    let mut active = false;
+    for progress in updates.iter_mut() {
+        apply_rate(progress);
+        if progress.finished {
+            BATCH_RATES.lock().unwrap().remove(&progress.id);
+            println!(
+                "[downloads] Batch '{}' finished: {} done, {} failed",
+                progress.name, progress.done_tracks, progress.failed_tracks
+            );
+        } else {
+            active = true;
+        }
+        let _ = app.emit("downloads:batch", &*progress);
+    }
+    active
+}
+
+// One background task emits progress for all batches while any is unfinished
+fn ensure_batch_ticker(app: tauri::AppHandle) {
+    if BATCH_TICKER.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(BATCH_TICK).await;
+            if emit_batches(&app) {
+                continue;
+            }
+            BATCH_TICKER.store(false, Ordering::SeqCst);
+            // A batch queued between the check and the store would otherwise go unreported
+            if JOBS.lock().unwrap().batches.is_empty() || BATCH_TICKER.swap(true, Ordering::SeqCst) {
+                break;
+            }
+        }
+    });
+}
+
+/// Queue a set of downloads as one batch and return its id. Tracks that are
+/// already cached count as done right away.
+pub fn enqueue_batch(
+    app: &tauri::AppHandle,
+    name: String,
+    specs: Vec<JobSpec>,
+    priority: i32,
+) -> Result<String, String> {
+    if specs.is_empty() {
+        return Err("Batch has no tracks".to_string());
+    }
+    // Look up cached sizes before taking the queue lock
+    let specs: Vec<(String, JobSpec, Option<u64>)> = specs
+        .into_iter()
+        .map(|spec| {
+            let id = create_cache_filename_with_index(
+                &spec.track_id,
+                &spec.source_type,
+                &spec.source_hash,
+                spec.file_index,
+            );
+            let cached = match cached_size(&spec) {
+                0 => None,
+                size => Some(size),
+            };
+            (id, spec, cached)
+        })
+        .collect();
+
+    let (batch_id, jobs) = {
+        let mut table = JOBS.lock().unwrap();
+        let batch_id = format!("batch-{}", table.take_seq());
+        let mut batch = DownloadBatch {
+            id: batch_id.clone(),
+            name: name.clone(),
+            job_ids: Vec::new(),
+            completed: HashMap::new(),
+            created_at: now_secs(),
+        };
+        let mut jobs = Vec::new();
+        for (id, spec, cached) in specs {
+            if batch.job_ids.contains(&id) {
+                continue;
+            }
+            batch.job_ids.push(id.clone());
+            match cached {
+                Some(size) => {
+                    batch.completed.insert(id, size);
+                }
+                None => jobs.push(table.upsert(&id, spec, priority, Some(&batch_id)).1),
+            }
+        }
+        println!(
+            "[downloads] Queued batch '{}' ({}): {} tracks, {} already cached",
+            name,
+            batch_id,
+            batch.job_ids.len(),
+            batch.completed.len()
+        );
+        table.batches.push(batch);
+        table.save();
+        (batch_id, jobs)
+    };
+    for job in &jobs {
+        emit_job(app, job);
+    }
+    pump(app);
+    emit_batches(app);
+    ensure_batch_ticker(app.clone());
+    Ok(batch_id)
+}
+
+fn batch_job_ids(batch_id: &str) -> Result<Vec<String>, String> {
+    JOBS.lock()
+        .unwrap()
+        .batches
+        .iter()
+        .find(|b| b.id == batch_id)
+        .map(|b| b.job_ids.clone())
+        .ok_or_else(|| format!("Unknown download batch: {}", batch_id))
+}
+
+/// Pause or resume every unfinished job of a batch
+pub fn set_batch_paused(app: &tauri::AppHandle, batch_id: &str, pause: bool) -> Result<(), String> {
+    for id in batch_job_ids(batch_id)? {
+        if !job_exists(&id) {
+            continue;
+        }
+        let _ = set_paused(&id, pause);
+        set_job_paused(app, &id, pause);
+    }
+    emit_batches(app);
+    Ok(())
+}
+
+/// Cancel every unfinished job of a batch and forget the batch
+pub fn cancel_batch(app: &tauri::AppHandle, batch_id: &str) -> Result<(), String> {
+    let ids = batch_job_ids(batch_id)?;
+    // Jobs another unfinished batch still wants keep running; this batch just lets go of them
+    let shared: HashSet<String> = {
+        let mut table = JOBS.lock().unwrap();
+        let shared: HashSet<String> = table
+            .batches
+            .iter()
+            .filter(|b| b.id != batch_id)
+            .flat_map(|b| b.job_ids.iter().filter(|id| !b.completed.contains_key(*id)))
+            .filter(|id| ids.contains(id))
+            .cloned()
+            .collect();
+        if let Some(batch) = table.batches.iter_mut().find(|b| b.id == batch_id) {
+            batch.job_ids.retain(|id| !shared.contains(id));
+        }
+        shared
+    };
+    for id in &ids {
+        if shared.contains(id) || !job_exists(id) {
+            continue;
+        }
+        let _ = request_cancel(id);
+        remove_job(app, id);
+        if let Some(dir) = get_cache_dir() {
+            crate::partial::discard(&dir.join(format!("{}.part", id)));
+        }
+    }
+    // Every job is gone now, so this emits the final event and drops the batch
+    emit_batches(app);
+    println!("[downloads] Cancelled batch {}", batch_id);
+    Ok(())
+}
+
+pub fn list_batches() -> serde_json::Value {
+    let table = JOBS.lock().unwrap();
+    let batches: Vec<BatchProgress> = table
+        .batches
+        .iter()
+        .map(|b| batch_progress(&table, b))
+        .collect();
+    serde_json::to_value(batches).unwrap_or(serde_json::Value::Null)
+}
+
 // Tauri commands for controlling manual cache downloads
 pub async fn downloads_pause(
     app: tauri::AppHandle,
     track_id: String,
     source_type: String,
     source_hash: String,
+    file_index: Option<usize>,
 ) -> Result<bool, String> {
-    let base = create_cache_filename(&track_id, &source_type, &source_hash);
+    let base = create_cache_filename_with_index(&track_id, &source_type, &source_hash, file_index);
     let _ = set_paused(&base, true);
+    set_job_paused(&app, &base, true);
     let _ = app.emit(
         "cache:download:paused",
         serde_json::json!({
@@ -125,9 +1189,11 @@ pub async fn downloads_resume(
     track_id: String,
     source_type: String,
     source_hash: String,
+    file_index: Option<usize>,
 ) -> Result<bool, String> {
-    let base = create_cache_filename(&track_id, &source_type, &source_hash);
+    let base = create_cache_filename_with_index(&track_id, &source_type, &source_hash, file_index);
     let _ = set_paused(&base, false);
+    set_job_paused(&app, &base, false);
     // Notify waiters
     {
         let g = CONTROLS.lock().unwrap();
@@ -152,15 +1218,14 @@ pub async fn downloads_remove(
     track_id: String,
     source_type: String,
     source_hash: String,
+    file_index: Option<usize>,
 ) -> Result<bool, String> {
-    let base = create_cache_filename(&track_id, &source_type, &source_hash);
+    let base = create_cache_filename_with_index(&track_id, &source_type, &source_hash, file_index);
     let _ = request_cancel(&base);
-    // Best-effort removal of .part file (actual download loop will also honor cancel)
+    remove_job(&app, &base);
+    // Best-effort removal of the .part and its range map (the download loop also honors cancel)
     if let Some(dir) = get_cache_dir() {
-        let part = dir.join(format!("{}.part", base));
-        if part.exists() {
-            let _ = std::fs::remove_file(&part);
-        }
+        crate::partial::discard(&dir.join(format!("{}.part", base)));
     }
     let _ = app.emit(
         "cache:download:removed",
@@ -173,3 +1238,22 @@ pub async fn downloads_remove(
     );
     Ok(true)
 }
+
+/// Queue a job without starting it; tests run the download themselves
+#[cfg(test)]
+pub(crate) fn queue_unstarted(spec: JobSpec) -> String {
+    let id = create_cache_filename_with_index(
+        &spec.track_id,
+        &spec.source_type,
+        &spec.source_hash,
+        spec.file_index,
+    );
+    JOBS.lock().unwrap().upsert(&id, spec, 0, None);
+    id
+}
+
+/// Drop a job the way a finished run does
+#[cfg(test)]
+pub(crate) fn forget_job(id: &str) {
+    JOBS.lock().unwrap().jobs.retain(|j| j.id != id);
+}
@@ -0,0 +1,527 @@
+use crate::downloads::{DownloadJob, JobSpec};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::Emitter;
+use tokio::process::Command;
+use tokio::sync::oneshot;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 120;
+// Only the tail of a hook's output is kept on the job
+const OUTPUT_TAIL_BYTES: usize = 2048;
+// How long to wait for tag extraction so hooks see the track's metadata
+const METADATA_WAIT: Duration = Duration::from_secs(10);
+
+/// What a hook runs
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HookAction {
+    /// Local program. `{file}` in an argument is replaced with the cached file's path;
+    /// everything else is passed through `FREELY_*` environment variables. Only configured by
+    /// editing the settings file; the frontend can toggle or remove these but not create them.
+    Command {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    /// Script plugin; it receives `downloads:hook:plugin` and answers with `downloads_hook_result`
+    Plugin { plugin: String },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PostDownloadHook {
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub action: HookAction,
+    // Source types the hook applies to; empty means all
+    #[serde(default)]
+    pub source_types: Vec<String>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl PostDownloadHook {
+    fn applies_to(&self, source_type: &str) -> bool {
+        self.enabled
+            && (self.source_types.is_empty() || self.source_types.iter().any(|s| s == source_type))
+    }
+}
+
+/// Result of one hook run, recorded on the download job
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HookOutcome {
+    pub name: String,
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub output: Option<String>,
+    pub duration_ms: u64,
+    pub finished_at: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HookSettings {
+    pub hooks: Vec<PostDownloadHook>,
+}
+
+impl HookSettings {
+    pub fn load() -> Self {
+        let path = match Self::get_settings_path() {
+            Ok(p) => p,
+            Err(e) => {
+                println!("[hooks] Failed to get settings path: {}, using defaults", e);
+                return Self::default();
+            }
+        };
+        if !path.exists() {
+            return Self::default();
+        }
+        match std::fs::read_to_string(&path) {
+            Ok(content) => match serde_json::from_str::<HookSettings>(&content) {
+                Ok(settings) => {
+                    println!(
+                        "[hooks] Loaded {} hook(s) from: {}",
+                        settings.hooks.len(),
+                        path.display()
+                    );
+                    settings
+                }
+                Err(e) => {
+                    println!("[hooks] Failed to parse settings: {}, using defaults", e);
+                    Self::default()
+                }
+            },
+            Err(e) => {
+                println!("[hooks] Failed to read settings: {}, using defaults", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::get_settings_path()
+            .map_err(|e| format!("Failed to get hook settings path: {}", e))?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+        }
+
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize hook settings: {}", e))?;
+
+        std::fs::write(&path, content)
+            .map_err(|e| format!("Failed to write hook settings: {}", e))?;
+
+        println!("[hooks] Saved settings to: {}", path.display());
+        Ok(())
+    }
+
+    fn get_settings_path() -> Result<PathBuf, String> {
+        let data_dir = dirs::data_dir().ok_or("Failed to get system data directory")?;
+
+        let mut path = data_dir;
+        path.push("com.freely.player");
+        path.push("download_hooks.json");
+        Ok(path)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for hook in &self.hooks {
+            if hook.name.trim().is_empty() {
+                return Err("Hook name must not be empty".to_string());
+            }
+            match &hook.action {
+                HookAction::Command { program, .. } if hook.enabled => {
+                    check_program(program).map_err(|e| format!("Hook '{}': {}", hook.name, e))?;
+                }
+                HookAction::Plugin { plugin } if plugin.trim().is_empty() => {
+                    return Err(format!("Hook '{}' has no plugin", hook.name));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Settings coming from the frontend may keep, toggle or drop command hooks, but every
+    /// command they contain must already be in the settings file unchanged
+    fn check_commands_known(&self, current: &HookSettings) -> Result<(), String> {
+        for hook in &self.hooks {
+            if !matches!(hook.action, HookAction::Command { .. }) {
+                continue;
+            }
+            let known = current
+                .hooks
+                .iter()
+                .any(|h| h.name == hook.name && h.action == hook.action);
+            if !known {
+                let path = Self::get_settings_path().unwrap_or_default();
+                return Err(format!(
+                    "Command hook '{}' can only be added or changed by editing {}",
+                    hook.name,
+                    path.display()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Command hooks must name an existing executable by absolute path; nothing is looked up on PATH
+fn check_program(program: &str) -> Result<(), String> {
+    let path = std::path::Path::new(program);
+    if !path.is_absolute() {
+        return Err(format!("program must be an absolute path: {}", program));
+    }
+    let meta = std::fs::metadata(path).map_err(|e| format!("{}: {}", program, e))?;
+    if !meta.is_file() {
+        return Err(format!("program is not a file: {}", program));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if meta.permissions().mode() & 0o111 == 0 {
+            return Err(format!("program is not executable: {}", program));
+        }
+    }
+    Ok(())
+}
+
+static SETTINGS: Lazy<Mutex<HookSettings>> = Lazy::new(|| Mutex::new(HookSettings::load()));
+// Plugin hook runs waiting for the frontend to report back
+static PLUGIN_WAITERS: Lazy<Mutex<HashMap<String, oneshot::Sender<(bool, Option<String>)>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static NEXT_REQUEST: AtomicU64 = AtomicU64::new(1);
+
+pub fn get_hook_settings() -> HookSettings {
+    SETTINGS.lock().unwrap().clone()
+}
+
+/// Re-read the settings file, picking up command hooks edited by hand while the app runs
+pub fn reload_hook_settings() -> HookSettings {
+    let mut settings = SETTINGS.lock().unwrap();
+    *settings = HookSettings::load();
+    settings.clone()
+}
+
+pub fn update_hook_settings<F>(updater: F) -> Result<HookSettings, String>
+where
+    F: FnOnce(&mut HookSettings),
+{
+    let mut settings = SETTINGS.lock().unwrap();
+    let mut next = settings.clone();
+    updater(&mut next);
+    next.validate()?;
+    next.save()?;
+    *settings = next;
+    Ok(settings.clone())
+}
+
+/// Whether any enabled hook would run for a finished download of this source type
+pub fn has_hooks_for(source_type: &str) -> bool {
+    SETTINGS
+        .lock()
+        .unwrap()
+        .hooks
+        .iter()
+        .any(|h| h.applies_to(source_type))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Environment handed to hooks: the app's path configuration plus the finished download
+fn hook_env_vars(spec: &JobSpec, cache_key: &str, path: &std::path::Path) -> Vec<(String, String)> {
+    let mut vars = crate::utils::get_path_config_clone()
+        .map(|c| c.export_as_env_vars())
+        .unwrap_or_default();
+    let entry = crate::cache::get_entry_snapshot(cache_key).map(|(e, _)| e);
+    let meta = entry
+        .as_ref()
+        .and_then(|e| e.metadata.clone())
+        .unwrap_or_default();
+    let opt = |v: Option<String>| v.unwrap_or_default();
+    let num = |v: Option<u32>| v.map(|n| n.to_string()).unwrap_or_default();
+    let cover_art = match (meta.cover_art.as_deref(), crate::cache::get_cache_dir()) {
+        (Some(name), Some(dir)) => crate::tags::cover_art_path(&dir, name),
+        _ => None,
+    };
+    vars.extend([
+        (
+            "FREELY_FILE_PATH".to_string(),
+            path.to_string_lossy().to_string(),
+        ),
+        ("FREELY_CACHE_KEY".to_string(), cache_key.to_string()),
+        ("FREELY_TRACK_ID".to_string(), spec.track_id.clone()),
+        ("FREELY_SOURCE_TYPE".to_string(), spec.source_type.clone()),
+        ("FREELY_SOURCE_HASH".to_string(), spec.source_hash.clone()),
+        ("FREELY_SOURCE_URL".to_string(), spec.url.clone()),
+        (
+            "FREELY_FILE_INDEX".to_string(),
+            spec.file_index.map(|i| i.to_string()).unwrap_or_default(),
+        ),
+        (
+            "FREELY_FILE_SIZE".to_string(),
+            entry
+                .as_ref()
+                .map(|e| e.file_size.to_string())
+                .unwrap_or_default(),
+        ),
+        (
+            "FREELY_CODEC".to_string(),
+            opt(entry.as_ref().and_then(|e| e.codec.clone())),
+        ),
+        (
+            "FREELY_SAMPLE_RATE".to_string(),
+            num(entry.as_ref().and_then(|e| e.sample_rate)),
+        ),
+        (
+            "FREELY_BITS_PER_SAMPLE".to_string(),
+            num(entry.as_ref().and_then(|e| e.bits_per_sample)),
+        ),
+        ("FREELY_TITLE".to_string(), opt(meta.title.clone())),
+        ("FREELY_ARTIST".to_string(), opt(meta.artist.clone())),
+        ("FREELY_ALBUM".to_string(), opt(meta.album.clone())),
+        (
+            "FREELY_ALBUM_ARTIST".to_string(),
+            opt(meta.album_artist.clone()),
+        ),
+        ("FREELY_GENRE".to_string(), opt(meta.genre.clone())),
+        ("FREELY_DATE".to_string(), opt(meta.date.clone())),
+        ("FREELY_TRACK_NUMBER".to_string(), num(meta.track_number)),
+        ("FREELY_DISC_NUMBER".to_string(), num(meta.disc_number)),
+        (
+            "FREELY_COVER_ART_PATH".to_string(),
+            cover_art
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        ),
+        (
+            "FREELY_METADATA_JSON".to_string(),
+            serde_json::to_string(&meta).unwrap_or_default(),
+        ),
+    ]);
+    vars
+}
+
+fn output_tail(stdout: &[u8], stderr: &[u8]) -> Option<String> {
+    let mut text = String::from_utf8_lossy(stdout).trim().to_string();
+    let err = String::from_utf8_lossy(stderr);
+    if !err.trim().is_empty() {
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(err.trim());
+    }
+    if text.is_empty() {
+        return None;
+    }
+    if text.len() > OUTPUT_TAIL_BYTES {
+        let mut start = text.len() - OUTPUT_TAIL_BYTES;
+        while !text.is_char_boundary(start) {
+            start += 1;
+        }
+        text = text[start..].to_string();
+    }
+    Some(text)
+}
+
+async fn run_command(
+    program: &str,
+    args: &[String],
+    path: &std::path::Path,
+    env: &[(String, String)],
+    timeout: Duration,
+) -> (bool, Option<i32>, Option<String>) {
+    if let Err(e) = check_program(program) {
+        return (false, None, Some(e));
+    }
+    let file = path.to_string_lossy();
+    let mut cmd = Command::new(program);
+    cmd.args(args.iter().map(|a| a.replace("{file}", &file)))
+        .envs(env.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true);
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+    match tokio::time::timeout(timeout, cmd.output()).await {
+        Ok(Ok(output)) => (
+            output.status.success(),
+            output.status.code(),
+            output_tail(&output.stdout, &output.stderr),
+        ),
+        Ok(Err(e)) => (
+            false,
+            None,
+            Some(format!("Failed to start {}: {}", program, e)),
+        ),
+        Err(_) => (
+            false,
+            None,
+            Some(format!("Timed out after {}s", timeout.as_secs())),
+        ),
+    }
+}
+
+async fn run_plugin(
+    app: &tauri::AppHandle,
+    plugin: &str,
+    hook_name: &str,
+    job_id: &str,
+    env: &[(String, String)],
+    timeout: Duration,
+) -> (bool, Option<i32>, Option<String>) {
+    let request_id = format!("hook-{}", NEXT_REQUEST.fetch_add(1, Ordering::SeqCst));
+    let (tx, rx) = oneshot::channel();
+    PLUGIN_WAITERS
+        .lock()
+        .unwrap()
+        .insert(request_id.clone(), tx);
+    let env: serde_json::Map<String, serde_json::Value> = env
+        .iter()
+        .map(|(k, v)| (k.clone(), serde_json::json!(v)))
+        .collect();
+    let _ = app.emit(
+        "downloads:hook:plugin",
+        serde_json::json!({
+            "requestId": request_id,
+            "plugin": plugin,
+            "hook": hook_name,
+            "jobId": job_id,
+            "env": env,
+        }),
+    );
+    let result = tokio::time::timeout(timeout, rx).await;
+    PLUGIN_WAITERS.lock().unwrap().remove(&request_id);
+    match result {
+        Ok(Ok((success, message))) => (success, None, message),
+        Ok(Err(_)) => (false, None, Some("Plugin hook was dropped".to_string())),
+        Err(_) => (
+            false,
+            None,
+            Some(format!(
+                "Plugin did not answer within {}s",
+                timeout.as_secs()
+            )),
+        ),
+    }
+}
+
+/// Run the configured hooks for a finished job, one after another, recording each outcome
+pub async fn run_for_job(app: tauri::AppHandle, job: DownloadJob) {
+    let spec = &job.spec;
+    let hooks: Vec<PostDownloadHook> = get_hook_settings()
+        .hooks
+        .into_iter()
+        .filter(|h| h.applies_to(&spec.source_type))
+        .collect();
+    if hooks.is_empty() {
+        return;
+    }
+    let cache_key = crate::cache::AudioCache::generate_cache_key_with_index(
+        &spec.track_id,
+        &spec.source_type,
+        &spec.source_hash,
+        spec.file_index,
+    );
+
+    // Tags are read in the background right after caching; give that a moment to land
+    let waited = Instant::now();
+    let path = loop {
+        match crate::cache::get_entry_snapshot(&cache_key) {
+            Some((entry, path))
+                if entry.metadata.is_some() || waited.elapsed() >= METADATA_WAIT =>
+            {
+                break Some(path)
+            }
+            None => break None,
+            Some(_) => tokio::time::sleep(Duration::from_millis(500)).await,
+        }
+    };
+    let path = match path {
+        Some(p) => p,
+        None => {
+            println!("[hooks] {} is no longer cached, skipping hooks", job.id);
+            return;
+        }
+    };
+    let env = hook_env_vars(spec, &cache_key, &path);
+
+    for hook in hooks {
+        let timeout = Duration::from_secs(hook.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+        let started = Instant::now();
+        let (success, exit_code, output) = match &hook.action {
+            HookAction::Command { program, args } => {
+                run_command(program, args, &path, &env, timeout).await
+            }
+            HookAction::Plugin { plugin } => {
+                run_plugin(&app, plugin, &hook.name, &job.id, &env, timeout).await
+            }
+        };
+        println!(
+            "[hooks] '{}' for {} {} in {}ms",
+            hook.name,
+            job.id,
+            if success { "succeeded" } else { "failed" },
+            started.elapsed().as_millis()
+        );
+        crate::downloads::record_hook_outcome(
+            &app,
+            &job.id,
+            HookOutcome {
+                name: hook.name.clone(),
+                success,
+                exit_code,
+                output,
+                duration_ms: started.elapsed().as_millis() as u64,
+                finished_at: now_secs(),
+            },
+        );
+    }
+}
+
+#[tauri::command]
+pub async fn hooks_get_settings() -> Result<serde_json::Value, String> {
+    serde_json::to_value(reload_hook_settings())
+        .map_err(|e| format!("Failed to serialize hook settings: {}", e))
+}
+
+#[tauri::command]
+pub async fn hooks_set_settings(settings: serde_json::Value) -> Result<serde_json::Value, String> {
+    let incoming: HookSettings =
+        serde_json::from_value(settings).map_err(|e| format!("Invalid hook settings: {}", e))?;
+    incoming.check_commands_known(&reload_hook_settings())?;
+    let updated = update_hook_settings(|s| *s = incoming)?;
+    serde_json::to_value(updated).map_err(|e| format!("Failed to serialize hook settings: {}", e))
+}
+
+/// Report the result of a plugin hook run (answer to `downloads:hook:plugin`)
+#[tauri::command]
+pub async fn downloads_hook_result(
+    request_id: String,
+    success: bool,
+    message: Option<String>,
+) -> Result<bool, String> {
+    match PLUGIN_WAITERS.lock().unwrap().remove(&request_id) {
+        Some(tx) => Ok(tx.send((success, message)).is_ok()),
+        None => Ok(false),
+    }
+}
@@ -0,0 +1,458 @@
+// Shared HTTP client subsystem: every network caller builds its client and sends requests here
+use crate::proxy::ProxySubsystem;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+pub const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+
+/// Timeouts, retry and politeness policy shared by all clients
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpSettings {
+    pub connect_timeout_secs: u64,
+    // Longest silence while waiting for response data
+    pub read_timeout_secs: u64,
+    // Whole-request limit for API calls; media downloads have none
+    pub request_timeout_secs: u64,
+    // Extra attempts for idempotent requests that fail transiently
+    pub max_retries: u32,
+    pub retry_base_delay_ms: u64,
+    pub retry_max_delay_ms: u64,
+    // Requests in flight per host; 0 = unlimited
+    pub max_per_host: usize,
+    pub max_redirects: usize,
+    pub user_agent: String,
+    pub cookies: bool,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: 10,
+            read_timeout_secs: 30,
+            request_timeout_secs: 30,
+            max_retries: 3,
+            retry_base_delay_ms: 500,
+            retry_max_delay_ms: 10_000,
+            max_per_host: 6,
+            max_redirects: 10,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            cookies: true,
+        }
+    }
+}
+
+impl HttpSettings {
+    pub fn load() -> Self {
+        let path = match get_http_settings_path() {
+            Ok(p) => p,
+            Err(_) => return Self::default(),
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                println!(
+                    "[http] Failed to parse HTTP settings: {}, using defaults",
+                    e
+                );
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = get_http_settings_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+        }
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize HTTP settings: {}", e))?;
+        std::fs::write(&path, content)
+            .map_err(|e| format!("Failed to write HTTP settings: {}", e))?;
+        println!("[http] Saved HTTP settings to {:?}", path);
+        Ok(())
+    }
+
+    pub fn validate(&mut self) {
+        self.connect_timeout_secs = self.connect_timeout_secs.clamp(1, 120);
+        self.read_timeout_secs = self.read_timeout_secs.clamp(5, 600);
+        self.request_timeout_secs = self.request_timeout_secs.clamp(5, 600);
+        self.max_retries = self.max_retries.min(10);
+        self.retry_base_delay_ms = self.retry_base_delay_ms.clamp(50, 60_000);
+        self.retry_max_delay_ms = self.retry_max_delay_ms.max(self.retry_base_delay_ms);
+        self.max_redirects = self.max_redirects.min(30);
+        if self.user_agent.trim().is_empty() {
+            self.user_agent = DEFAULT_USER_AGENT.to_string();
+        }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .retry_base_delay_ms
+            .saturating_mul(1u64 << attempt.min(16));
+        Duration::from_millis(delay.min(self.retry_max_delay_ms))
+    }
+}
+
+fn get_http_settings_path() -> Result<PathBuf, String> {
+    let data_dir = dirs::data_dir().ok_or("Failed to get system data directory")?;
+
+    let mut path = data_dir;
+    path.push("com.freely.player");
+    path.push("http_settings.json");
+    Ok(path)
+}
+
+static HTTP_SETTINGS: Lazy<Mutex<HttpSettings>> = Lazy::new(|| Mutex::new(HttpSettings::load()));
+
+pub fn get_http_settings() -> HttpSettings {
+    HTTP_SETTINGS.lock().unwrap().clone()
+}
+
+pub fn update_http_settings<F>(updater: F) -> Result<HttpSettings, String>
+where
+    F: FnOnce(&mut HttpSettings),
+{
+    let updated = {
+        let mut settings = HTTP_SETTINGS.lock().unwrap();
+        updater(&mut settings);
+        settings.validate();
+        settings.save()?;
+        settings.clone()
+    };
+    invalidate_clients();
+    HOST_SLOTS.lock().unwrap().clear();
+    Ok(updated)
+}
+
+/// Typed failure of a request, serialized with a `kind` tag for the frontend
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HttpError {
+    // The client or request could not be built (bad URL, header, proxy)
+    Build { message: String },
+    Connect { url: String, message: String },
+    Timeout { url: String },
+    Status { url: String, status: u16 },
+    // The connection broke while reading the body
+    Body { url: String, message: String },
+    // The body arrived but was not what the caller expected
+    Decode { url: String, message: String },
+    Request { url: String, message: String },
+}
+
+impl HttpError {
+    fn from_reqwest(e: reqwest::Error, url: &str) -> Self {
+        let url = e
+            .url()
+            .map(|u| u.to_string())
+            .unwrap_or_else(|| url.to_string());
+        let message = e.to_string();
+        if e.is_timeout() {
+            HttpError::Timeout { url }
+        } else if e.is_connect() {
+            HttpError::Connect { url, message }
+        } else if let Some(status) = e.status() {
+            HttpError::Status {
+                url,
+                status: status.as_u16(),
+            }
+        } else if e.is_builder() {
+            HttpError::Build { message }
+        } else if e.is_decode() {
+            HttpError::Decode { url, message }
+        } else if e.is_body() {
+            HttpError::Body { url, message }
+        } else {
+            HttpError::Request { url, message }
+        }
+    }
+
+    /// Worth another attempt for an idempotent request
+    pub fn is_transient(&self) -> bool {
+        match self {
+            HttpError::Connect { .. } | HttpError::Timeout { .. } | HttpError::Body { .. } => true,
+            HttpError::Status { status, .. } => is_transient_status(*status),
+            HttpError::Request { .. } => true,
+            HttpError::Build { .. } | HttpError::Decode { .. } => false,
+        }
+    }
+}
+
+impl std::fmt::Display for HttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HttpError::Build { message } => write!(f, "invalid request: {}", message),
+            HttpError::Connect { url, message } => {
+                write!(f, "connection to {} failed: {}", url, message)
+            }
+            HttpError::Timeout { url } => write!(f, "request to {} timed out", url),
+            HttpError::Status { url, status } => write!(f, "HTTP {} from {}", status, url),
+            HttpError::Body { url, message } => {
+                write!(f, "reading response from {} failed: {}", url, message)
+            }
+            HttpError::Decode { url, message } => {
+                write!(f, "unexpected response from {}: {}", url, message)
+            }
+            HttpError::Request { url, message } => {
+                write!(f, "request to {} failed: {}", url, message)
+            }
+        }
+    }
+}
+
+impl From<HttpError> for String {
+    fn from(e: HttpError) -> Self {
+        e.to_string()
+    }
+}
+
+fn is_transient_status(status: u16) -> bool {
+    matches!(status, 408 | 425 | 429 | 500 | 502 | 503 | 504)
+}
+
+// Shared across every client so sessions set by one request carry over to the next
+static COOKIES: Lazy<Mutex<Arc<reqwest::cookie::Jar>>> =
+    Lazy::new(|| Mutex::new(Arc::new(reqwest::cookie::Jar::default())));
+
+fn base_builder(settings: &HttpSettings, subsystem: ProxySubsystem) -> reqwest::ClientBuilder {
+    let mut builder = crate::proxy::configure_client(reqwest::Client::builder(), subsystem)
+        .user_agent(settings.user_agent.clone())
+        .connect_timeout(Duration::from_secs(settings.connect_timeout_secs))
+        .read_timeout(Duration::from_secs(settings.read_timeout_secs))
+        .redirect(reqwest::redirect::Policy::limited(settings.max_redirects));
+    if settings.cookies {
+        builder = builder.cookie_provider(COOKIES.lock().unwrap().clone());
+    }
+    builder
+}
+
+/// Builder for API-style clients; callers may add default headers before `build()`
+pub fn builder(subsystem: ProxySubsystem) -> reqwest::ClientBuilder {
+    let settings = get_http_settings();
+    base_builder(&settings, subsystem).timeout(Duration::from_secs(settings.request_timeout_secs))
+}
+
+/// Builder for long-running media downloads: no overall timeout and raw bodies,
+/// since servers often mislabel the encoding of audio files
+pub fn media_builder(subsystem: ProxySubsystem) -> reqwest::ClientBuilder {
+    let settings = get_http_settings();
+    base_builder(&settings, subsystem)
+        .tcp_nodelay(true)
+        .tcp_keepalive(Some(Duration::from_secs(30)))
+        .gzip(false)
+        .brotli(false)
+}
+
+// Built clients per subsystem; dropped whenever HTTP or proxy settings change
+static CLIENTS: Lazy<Mutex<HashMap<ProxySubsystem, reqwest::Client>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Shared API client for `subsystem`
+pub fn client(subsystem: ProxySubsystem) -> Result<reqwest::Client, HttpError> {
+    let mut clients = CLIENTS.lock().unwrap();
+    if let Some(client) = clients.get(&subsystem) {
+        return Ok(client.clone());
+    }
+    let client = builder(subsystem).build().map_err(|e| HttpError::Build {
+        message: e.to_string(),
+    })?;
+    clients.insert(subsystem, client.clone());
+    Ok(client)
+}
+
+pub fn invalidate_clients() {
+    CLIENTS.lock().unwrap().clear();
+}
+
+static HOST_SLOTS: Lazy<Mutex<HashMap<String, Arc<Semaphore>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// A slot in a host's concurrency limit; the slot frees when this is dropped
+pub struct HostPermit {
+    _slot: Option<OwnedSemaphorePermit>,
+}
+
+async fn acquire_host(url: &reqwest::Url, limit: usize) -> HostPermit {
+    let host = match url.host_str() {
+        // Local servers (e.g. the torrent stream endpoint) aren't throttled
+        Some(h) if limit > 0 && h != "localhost" && h != "127.0.0.1" && h != "[::1]" => {
+            h.to_string()
+        }
+        _ => return HostPermit { _slot: None },
+    };
+    let semaphore = HOST_SLOTS
+        .lock()
+        .unwrap()
+        .entry(host)
+        .or_insert_with(|| Arc::new(Semaphore::new(limit)))
+        .clone();
+    HostPermit {
+        _slot: semaphore.acquire_owned().await.ok(),
+    }
+}
+
+fn retry_after(resp: &reqwest::Response) -> Option<Duration> {
+    resp.headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// Send `req`, retrying idempotent requests on connection errors, timeouts and
+/// 408/429/5xx answers with exponential backoff (honouring Retry-After).
+/// The returned permit holds the host's concurrency slot; keep it while reading the body.
+pub async fn send_held(
+    req: reqwest::RequestBuilder,
+) -> Result<(reqwest::Response, HostPermit), HttpError> {
+    send_with(req, true).await
+}
+
+/// `send_held` outside the host limit, for the one request a download can't do without
+/// (the head of a segmented download) so its own segment workers can't starve it
+pub async fn send_unthrottled(req: reqwest::RequestBuilder) -> Result<reqwest::Response, HttpError> {
+    send_with(req, false).await.map(|(resp, _)| resp)
+}
+
+async fn send_with(
+    req: reqwest::RequestBuilder,
+    throttled: bool,
+) -> Result<(reqwest::Response, HostPermit), HttpError> {
+    let settings = get_http_settings();
+    let (client, request) = req.build_split();
+    let request = request.map_err(|e| HttpError::Build {
+        message: e.to_string(),
+    })?;
+    let url = request.url().clone();
+    let idempotent = matches!(
+        *request.method(),
+        reqwest::Method::GET
+            | reqwest::Method::HEAD
+            | reqwest::Method::OPTIONS
+            | reqwest::Method::PUT
+            | reqwest::Method::DELETE
+    );
+    let retries = if idempotent && request.try_clone().is_some() {
+        settings.max_retries
+    } else {
+        0
+    };
+
+    let mut pending = Some(request);
+    let mut attempt = 0u32;
+    loop {
+        let current = if attempt < retries {
+            pending.as_ref().and_then(|r| r.try_clone())
+        } else {
+            pending.take()
+        };
+        let current = match current {
+            Some(r) => r,
+            None => {
+                return Err(HttpError::Build {
+                    message: "request body can't be resent".to_string(),
+                })
+            }
+        };
+        let permit = if throttled {
+            acquire_host(&url, settings.max_per_host).await
+        } else {
+            HostPermit { _slot: None }
+        };
+        let delay = match client.execute(current).await {
+            Ok(resp) if attempt < retries && is_transient_status(resp.status().as_u16()) => {
+                let delay = retry_after(&resp)
+                    .map(|d| d.min(Duration::from_millis(settings.retry_max_delay_ms)))
+                    .unwrap_or_else(|| settings.backoff(attempt));
+                println!(
+                    "[http] {} answered {}, retrying in {:?}",
+                    url,
+                    resp.status(),
+                    delay
+                );
+                delay
+            }
+            Ok(resp) => return Ok((resp, permit)),
+            Err(e) => {
+                let error = HttpError::from_reqwest(e, url.as_str());
+                if attempt >= retries || !error.is_transient() {
+                    return Err(error);
+                }
+                let delay = settings.backoff(attempt);
+                println!("[http] {}, retrying in {:?}", error, delay);
+                delay
+            }
+        };
+        drop(permit);
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+async fn send_ok(
+    req: reqwest::RequestBuilder,
+) -> Result<(reqwest::Response, HostPermit), HttpError> {
+    let (resp, permit) = send_held(req).await?;
+    if !resp.status().is_success() {
+        return Err(HttpError::Status {
+            url: resp.url().to_string(),
+            status: resp.status().as_u16(),
+        });
+    }
+    Ok((resp, permit))
+}
+
+/// Send and decode a successful JSON response
+pub async fn json<T: serde::de::DeserializeOwned>(
+    req: reqwest::RequestBuilder,
+) -> Result<T, HttpError> {
+    let (resp, _permit) = send_ok(req).await?;
+    let url = resp.url().to_string();
+    let bytes = resp
+        .bytes()
+        .await
+        .map_err(|e| HttpError::from_reqwest(e, &url))?;
+    serde_json::from_slice(&bytes).map_err(|e| HttpError::Decode {
+        url,
+        message: e.to_string(),
+    })
+}
+
+/// Send and read a successful text response
+pub async fn text(req: reqwest::RequestBuilder) -> Result<String, HttpError> {
+    let (resp, _permit) = send_ok(req).await?;
+    let url = resp.url().to_string();
+    resp.text()
+        .await
+        .map_err(|e| HttpError::from_reqwest(e, &url))
+}
+
+#[tauri::command]
+pub async fn http_get_settings() -> Result<serde_json::Value, String> {
+    serde_json::to_value(get_http_settings())
+        .map_err(|e| format!("Failed to serialize HTTP settings: {}", e))
+}
+
+#[tauri::command]
+pub async fn http_set_settings(settings: serde_json::Value) -> Result<serde_json::Value, String> {
+    let incoming: HttpSettings =
+        serde_json::from_value(settings).map_err(|e| format!("Invalid HTTP settings: {}", e))?;
+    let updated = update_http_settings(|s| *s = incoming)?;
+    crate::playback::apply_network_settings();
+    serde_json::to_value(updated).map_err(|e| format!("Failed to serialize HTTP settings: {}", e))
+}
+
+#[tauri::command]
+pub async fn http_clear_cookies() -> Result<bool, String> {
+    // The jar can't be emptied in place; clients built from now on get a fresh one
+    *COOKIES.lock().unwrap() = Arc::new(reqwest::cookie::Jar::default());
+    invalidate_clients();
+    Ok(true)
+}
@@ -0,0 +1,696 @@
+use crate::cache::CacheEntry;
+use crate::tags::TrackMetadata;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Emitter;
+use tokio::process::Command;
+
+pub const DEFAULT_TEMPLATE: &str = "{artist}/{album}/{track:02} - {title}.{ext}";
+// Keeps generated names well inside common filesystem limits
+const MAX_COMPONENT_LEN: usize = 120;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportMode {
+    /// Leave the cached file in place
+    #[default]
+    Copy,
+    /// Drop the track from the cache once it is in the library (pinned entries stay)
+    Move,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ExportRequest {
+    pub dest_dir: String,
+    #[serde(default)]
+    pub template: Option<String>,
+    #[serde(default)]
+    pub mode: ExportMode,
+    // Cache keys to export; every cached track when omitted
+    #[serde(default)]
+    pub keys: Option<Vec<String>>,
+    // Tags from the caller (e.g. the library database), layered over the embedded ones
+    #[serde(default)]
+    pub metadata: HashMap<String, TrackMetadata>,
+    // Re-export tracks the mapping already considers up to date
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Where a cached track was exported to, and from which version of it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExportRecord {
+    pub relative_path: String,
+    // Hash of the cache file identity and the tags written
+    pub signature: String,
+    pub exported_at: u64,
+    pub tagged: bool,
+}
+
+// Persisted mapping: library folder -> cache key -> record
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct ExportMap {
+    libraries: HashMap<String, HashMap<String, ExportRecord>>,
+}
+
+impl ExportMap {
+    fn load() -> Self {
+        let path = match get_map_path() {
+            Ok(p) => p,
+            Err(_) => return Self::default(),
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                println!(
+                    "[export] Failed to parse export mapping, starting fresh: {}",
+                    e
+                );
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let path = get_map_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+        }
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize export mapping: {}", e))?;
+        std::fs::write(&path, content).map_err(|e| format!("Failed to write export mapping: {}", e))
+    }
+}
+
+fn get_map_path() -> Result<PathBuf, String> {
+    let data_dir = dirs::data_dir().ok_or("Failed to get system data directory")?;
+
+    let mut path = data_dir;
+    path.push("com.freely.player");
+    path.push("library_exports.json");
+    Ok(path)
+}
+
+static EXPORT_MAP: Lazy<Mutex<ExportMap>> = Lazy::new(|| Mutex::new(ExportMap::load()));
+// Cancel flags of running export jobs
+static JOBS: Lazy<Mutex<HashMap<String, Arc<AtomicBool>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static NEXT_JOB: AtomicU64 = AtomicU64::new(1);
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Caller-supplied fields win; anything they leave out comes from the embedded tags
+fn merge_metadata(
+    embedded: Option<&TrackMetadata>,
+    overrides: Option<&TrackMetadata>,
+) -> TrackMetadata {
+    let mut base = serde_json::to_value(embedded.cloned().unwrap_or_default()).unwrap_or_default();
+    if let (Some(base), Some(over)) = (
+        base.as_object_mut(),
+        overrides.and_then(|o| serde_json::to_value(o).ok()),
+    ) {
+        if let Some(over) = over.as_object() {
+            for (k, v) in over {
+                if !v.is_null() {
+                    base.insert(k.clone(), v.clone());
+                }
+            }
+        }
+    }
+    serde_json::from_value(base).unwrap_or_default()
+}
+
+/// Extension matching the container the cached file is actually stored in
+fn container_extension(path: &Path) -> &'static str {
+    use std::io::Read;
+    let mut head = [0u8; 64];
+    let n = std::fs::File::open(path)
+        .and_then(|mut f| f.read(&mut head))
+        .unwrap_or(0);
+    let head = &head[..n];
+    if head.starts_with(b"fLaC") {
+        "flac"
+    } else if head.starts_with(b"OggS") {
+        if head.windows(8).any(|w| w == b"OpusHead") {
+            "opus"
+        } else {
+            "ogg"
+        }
+    } else if head.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+        "webm"
+    } else if head.len() >= 12 && &head[4..8] == b"ftyp" {
+        "m4a"
+    } else if head.len() >= 12 && head.starts_with(b"RIFF") && &head[8..12] == b"WAVE" {
+        "wav"
+    } else if head.starts_with(b"ID3")
+        || (head.len() >= 2 && head[0] == 0xFF && head[1] & 0xE0 == 0xE0)
+    {
+        "mp3"
+    } else {
+        "bin"
+    }
+}
+
+/// Output extension once ffmpeg has remuxed the file: named after the codec, so WebM Opus
+/// becomes `.opus`. Only valid when the remux actually happened.
+fn remuxed_extension(entry: &CacheEntry, container: &'static str) -> &'static str {
+    let codec = entry.codec.as_deref().map(|c| c.to_lowercase());
+    match codec.as_deref() {
+        Some(c) if c.contains("flac") => "flac",
+        Some(c) if c.contains("opus") => "opus",
+        Some(c) if c.contains("vorbis") => "ogg",
+        Some(c) if c.contains("mp3") || c.contains("mpeg") => "mp3",
+        Some(c) if c.contains("aac") || c.contains("mp4a") || c.contains("alac") => "m4a",
+        Some(c) if c.contains("wav") || c.contains("pcm") => "wav",
+        // Unknown codec in Matroska: keep it audio-only Matroska
+        _ if container == "webm" => "mka",
+        _ => container,
+    }
+}
+
+// Make one path component safe on every platform
+fn sanitize_component(raw: &str) -> String {
+    let mut out: String = raw
+        .chars()
+        .filter(|c| !c.is_control())
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c => c,
+        })
+        .collect();
+    if out.chars().count() > MAX_COMPONENT_LEN {
+        // Shorten the name but keep a file extension intact
+        let ext = match out.rfind('.') {
+            Some(dot) if out.len() - dot <= 6 => out[dot..].to_string(),
+            _ => String::new(),
+        };
+        let keep = MAX_COMPONENT_LEN - ext.chars().count();
+        out = out.chars().take(keep).collect::<String>() + &ext;
+    }
+    // Windows rejects trailing dots and spaces
+    let out = out.trim().trim_end_matches('.').trim_end().to_string();
+    let stem = out.split('.').next().unwrap_or("").to_uppercase();
+    let reserved = matches!(stem.as_str(), "CON" | "PRN" | "AUX" | "NUL")
+        || ((stem.starts_with("COM") || stem.starts_with("LPT"))
+            && stem.len() == 4
+            && stem.as_bytes()[3].is_ascii_digit());
+    if out.is_empty() || out == ".." {
+        "_".to_string()
+    } else if reserved {
+        format!("_{}", out)
+    } else {
+        out
+    }
+}
+
+fn template_value(name: &str, meta: &TrackMetadata, track_id: &str, ext: &str) -> Option<String> {
+    let value = match name {
+        "artist" => meta
+            .artist
+            .clone()
+            .or_else(|| meta.album_artist.clone())
+            .unwrap_or_else(|| "Unknown Artist".to_string()),
+        "album_artist" => meta
+            .album_artist
+            .clone()
+            .or_else(|| meta.artist.clone())
+            .unwrap_or_else(|| "Unknown Artist".to_string()),
+        "album" => meta
+            .album
+            .clone()
+            .unwrap_or_else(|| "Unknown Album".to_string()),
+        "title" => meta.title.clone().unwrap_or_else(|| track_id.to_string()),
+        "genre" => meta.genre.clone().unwrap_or_default(),
+        "year" => meta
+            .date
+            .as_deref()
+            .map(|d| d.chars().take(4).collect())
+            .unwrap_or_default(),
+        "track" => meta.track_number.map(|n| n.to_string()).unwrap_or_default(),
+        "disc" => meta.disc_number.map(|n| n.to_string()).unwrap_or_default(),
+        "track_id" => track_id.to_string(),
+        "ext" => ext.to_string(),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Reject unknown placeholders up front instead of exporting half-named files
+pub fn validate_template(template: &str) -> Result<(), String> {
+    let empty = TrackMetadata::default();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        let close = rest[open..]
+            .find('}')
+            .ok_or_else(|| format!("Unclosed placeholder in template '{}'", template))?;
+        let spec = &rest[open + 1..open + close];
+        let name = spec.split(':').next().unwrap_or("");
+        if template_value(name, &empty, "", "").is_none() {
+            return Err(format!("Unknown placeholder {{{}}} in template", name));
+        }
+        rest = &rest[open + close + 1..];
+    }
+    if template.trim().is_empty() {
+        return Err("Naming template is empty".to_string());
+    }
+    Ok(())
+}
+
+/// Expand a template such as `{artist}/{album}/{track:02} - {title}.{ext}` into a relative path.
+/// `{name:0N}` zero-pads numbers to N digits.
+pub fn render_template(template: &str, meta: &TrackMetadata, track_id: &str, ext: &str) -> PathBuf {
+    let mut path = PathBuf::new();
+    for part in template.split(['/', '\\']).filter(|p| !p.is_empty()) {
+        let mut rendered = String::new();
+        let mut rest = part;
+        while let Some(open) = rest.find('{') {
+            rendered.push_str(&rest[..open]);
+            let close = match rest[open..].find('}') {
+                Some(c) => open + c,
+                None => break,
+            };
+            let spec = &rest[open + 1..close];
+            let (name, format) = match spec.split_once(':') {
+                Some((n, f)) => (n, Some(f)),
+                None => (spec, None),
+            };
+            let mut value = template_value(name, meta, track_id, ext).unwrap_or_default();
+            if let Some(width) = format
+                .and_then(|f| f.strip_prefix('0'))
+                .and_then(|w| w.parse::<usize>().ok())
+            {
+                if !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) {
+                    value = format!("{:0>width$}", value, width = width);
+                }
+            }
+            rendered.push_str(&value);
+            rest = &rest[close + 1..];
+        }
+        rendered.push_str(rest);
+        // A missing track number leaves a dangling " - " separator at the front
+        let rendered = rendered.trim_start_matches([' ', '-', '_']);
+        path.push(sanitize_component(rendered));
+    }
+    path
+}
+
+fn signature(entry: &CacheEntry, meta: &TrackMetadata, template: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(entry.file_path.as_bytes());
+    hasher.update(entry.file_size.to_le_bytes());
+    hasher.update(entry.cached_at.to_le_bytes());
+    hasher.update(serde_json::to_vec(meta).unwrap_or_default());
+    hasher.update(template.as_bytes());
+    format!("{:x}", hasher.finalize())[..32].to_string()
+}
+
+// Append " (2)", " (3)", ... to the file stem until neither another track nor an
+// unrelated file on disk holds the path
+fn disambiguate(root: &Path, rel: PathBuf, taken: &HashSet<PathBuf>) -> PathBuf {
+    let is_free = |p: &PathBuf| !taken.contains(p) && !root.join(p).exists();
+    if is_free(&rel) {
+        return rel;
+    }
+    let stem = rel
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let ext = rel.extension().map(|e| e.to_string_lossy().to_string());
+    for n in 2.. {
+        let name = match &ext {
+            Some(ext) => format!("{} ({}).{}", stem, n, ext),
+            None => format!("{} ({})", stem, n),
+        };
+        let candidate = rel.with_file_name(name);
+        if is_free(&candidate) {
+            return candidate;
+        }
+    }
+    unreachable!()
+}
+
+fn ffmpeg_tag_args(meta: &TrackMetadata) -> Vec<String> {
+    let pair = |n: Option<u32>, total: Option<u32>| match (n, total) {
+        (Some(n), Some(t)) => Some(format!("{}/{}", n, t)),
+        (Some(n), None) => Some(n.to_string()),
+        _ => None,
+    };
+    let fields = [
+        ("title", meta.title.clone()),
+        ("artist", meta.artist.clone()),
+        ("album", meta.album.clone()),
+        ("album_artist", meta.album_artist.clone()),
+        ("genre", meta.genre.clone()),
+        ("date", meta.date.clone()),
+        ("track", pair(meta.track_number, meta.track_total)),
+        ("disc", pair(meta.disc_number, meta.disc_total)),
+        ("MUSICBRAINZ_TRACKID", meta.musicbrainz_recording_id.clone()),
+        ("MUSICBRAINZ_ALBUMID", meta.musicbrainz_release_id.clone()),
+        ("MUSICBRAINZ_ARTISTID", meta.musicbrainz_artist_id.clone()),
+    ];
+    let mut args = Vec::new();
+    for (key, value) in fields {
+        if let Some(value) = value {
+            args.push("-metadata".to_string());
+            args.push(format!("{}={}", key, value));
+        }
+    }
+    args
+}
+
+/// Write `src` to `tmp` with tags and cover art. Returns false when ffmpeg was unavailable
+/// or failed and a plain copy was made instead.
+async fn write_tagged(
+    src: &Path,
+    tmp: &Path,
+    ext: &str,
+    meta: &TrackMetadata,
+    art: Option<&Path>,
+) -> Result<bool, String> {
+    if !crate::transcode::ffmpeg_available() {
+        tokio::fs::copy(src, tmp)
+            .await
+            .map_err(|e| format!("Failed to copy {:?}: {}", src, e))?;
+        return Ok(false);
+    }
+    // Ogg and WAV have no attached-picture support in ffmpeg's muxers
+    let art = art.filter(|_| matches!(ext, "mp3" | "flac" | "m4a"));
+    let mut cmd = Command::new(crate::transcode::resolve_ffmpeg_exe());
+    cmd.args(["-hide_banner", "-nostdin", "-v", "error", "-y", "-i"])
+        .arg(src);
+    if let Some(art) = art {
+        cmd.arg("-i").arg(art);
+    }
+    cmd.args(["-map", "0:a:0"]);
+    if art.is_some() {
+        cmd.args([
+            "-map",
+            "1:v:0",
+            "-c:v",
+            "copy",
+            "-disposition:v:0",
+            "attached_pic",
+        ]);
+    }
+    cmd.args(["-c:a", "copy", "-map_metadata", "0"]);
+    cmd.args(ffmpeg_tag_args(meta));
+    if ext == "mp3" {
+        cmd.args(["-id3v2_version", "3"]);
+    }
+    cmd.arg(tmp);
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+    match cmd.output().await {
+        Ok(output) if output.status.success() => Ok(true),
+        result => {
+            let reason = match result {
+                Ok(output) => String::from_utf8_lossy(&output.stderr).trim().to_string(),
+                Err(e) => e.to_string(),
+            };
+            println!(
+                "[export] Tagging failed for {:?}, copying untagged: {}",
+                src, reason
+            );
+            tokio::fs::copy(src, tmp)
+                .await
+                .map_err(|e| format!("Failed to copy {:?}: {}", src, e))?;
+            Ok(false)
+        }
+    }
+}
+
+// Remove now-empty folders between `file` and the library root
+fn prune_empty_dirs(root: &Path, file: &Path) {
+    let mut dir = file.parent();
+    while let Some(d) = dir {
+        if d == root || !d.starts_with(root) || std::fs::remove_dir(d).is_err() {
+            break;
+        }
+        dir = d.parent();
+    }
+}
+
+enum TrackOutcome {
+    Exported,
+    Skipped,
+}
+
+async fn export_track(
+    request: &ExportRequest,
+    root: &Path,
+    template: &str,
+    key: &str,
+    taken: &mut HashSet<PathBuf>,
+) -> Result<TrackOutcome, String> {
+    let (entry, src) = crate::cache::get_entry_snapshot(key).ok_or("Entry no longer cached")?;
+    let meta = merge_metadata(entry.metadata.as_ref(), request.metadata.get(key));
+    let container = container_extension(&src);
+    let ext = if crate::transcode::ffmpeg_available() {
+        remuxed_extension(&entry, container)
+    } else {
+        container
+    };
+    let sig = signature(&entry, &meta, template);
+    let previous = EXPORT_MAP
+        .lock()
+        .unwrap()
+        .libraries
+        .get(&request.dest_dir)
+        .and_then(|m| m.get(key))
+        .cloned();
+
+    if let Some(prev) = previous.as_ref() {
+        if !request.force && prev.signature == sig && root.join(&prev.relative_path).exists() {
+            taken.insert(PathBuf::from(&prev.relative_path));
+            return Ok(TrackOutcome::Skipped);
+        }
+    }
+
+    let target_path = |ext: &str| {
+        let rel = render_template(template, &meta, &entry.track_id, ext);
+        match previous.as_ref() {
+            // Re-exporting to the same place overwrites our own earlier file
+            Some(prev) if Path::new(&prev.relative_path) == rel => rel,
+            _ => disambiguate(root, rel, taken),
+        }
+    };
+    let mut rel = target_path(ext);
+    let mut dest = root.join(&rel);
+    if let Some(parent) = dest.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("Failed to create {:?}: {}", parent, e))?;
+    }
+    let file_name = dest
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    // Keep the real extension last so ffmpeg picks the right muxer
+    let tmp = dest.with_file_name(format!(".{}.tmp.{}", file_name, ext));
+    let art = match (meta.cover_art.as_deref(), crate::cache::get_cache_dir()) {
+        (Some(name), Some(dir)) => crate::tags::cover_art_path(&dir, name),
+        _ => None,
+    };
+    let tagged = match write_tagged(&src, &tmp, ext, &meta, art.as_deref()).await {
+        Ok(tagged) => tagged,
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&tmp).await;
+            return Err(e);
+        }
+    };
+    if !tagged && ext != container {
+        // ffmpeg fell back to a plain copy, so the file is still in its original container
+        rel = target_path(container);
+        dest = root.join(&rel);
+    }
+    tokio::fs::rename(&tmp, &dest)
+        .await
+        .map_err(|e| format!("Failed to finalize {:?}: {}", dest, e))?;
+    taken.insert(rel.clone());
+
+    // Tags or the template changed the name: drop the file from the earlier export
+    if let Some(prev) = previous.as_ref() {
+        if Path::new(&prev.relative_path) != rel {
+            let old = root.join(&prev.relative_path);
+            if tokio::fs::remove_file(&old).await.is_ok() {
+                prune_empty_dirs(root, &old);
+            }
+            taken.remove(Path::new(&prev.relative_path));
+        }
+    }
+
+    {
+        let mut map = EXPORT_MAP.lock().unwrap();
+        map.libraries
+            .entry(request.dest_dir.clone())
+            .or_default()
+            .insert(
+                key.to_string(),
+                ExportRecord {
+                    relative_path: rel.to_string_lossy().to_string(),
+                    signature: sig,
+                    exported_at: now_secs(),
+                    tagged,
+                },
+            );
+        if let Err(e) = map.save() {
+            println!("[export] {}", e);
+        }
+    }
+
+    if request.mode == ExportMode::Move && !entry.pinned {
+        if let Err(e) = crate::cache::remove_entry(key) {
+            println!(
+                "[export] Exported {} but could not remove it from the cache: {}",
+                key, e
+            );
+        }
+    }
+    Ok(TrackOutcome::Exported)
+}
+
+async fn run_export(
+    app: tauri::AppHandle,
+    job_id: String,
+    request: ExportRequest,
+    cancel: Arc<AtomicBool>,
+) {
+    let root = PathBuf::from(&request.dest_dir);
+    let template = request
+        .template
+        .clone()
+        .unwrap_or_else(|| DEFAULT_TEMPLATE.to_string());
+    let keys = request
+        .keys
+        .clone()
+        .unwrap_or_else(crate::cache::list_cache_keys);
+    // Paths already owned by other tracks in this library
+    let mut taken: HashSet<PathBuf> = EXPORT_MAP
+        .lock()
+        .unwrap()
+        .libraries
+        .get(&request.dest_dir)
+        .map(|m| {
+            m.iter()
+                .filter(|(k, _)| !keys.contains(k))
+                .map(|(_, r)| PathBuf::from(&r.relative_path))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    println!(
+        "[export] Job {}: {} track(s) to {} ({:?})",
+        job_id,
+        keys.len(),
+        root.display(),
+        request.mode
+    );
+    let (mut exported, mut skipped) = (0usize, 0usize);
+    let mut failures: Vec<serde_json::Value> = Vec::new();
+    for (i, key) in keys.iter().enumerate() {
+        if cancel.load(Ordering::SeqCst) {
+            break;
+        }
+        match export_track(&request, &root, &template, key, &mut taken).await {
+            Ok(TrackOutcome::Exported) => exported += 1,
+            Ok(TrackOutcome::Skipped) => skipped += 1,
+            Err(e) => {
+                println!("[export] {} failed: {}", key, e);
+                failures.push(serde_json::json!({ "key": key, "error": e }));
+            }
+        }
+        let _ = app.emit(
+            "library:export:progress",
+            serde_json::json!({
+                "jobId": job_id,
+                "total": keys.len(),
+                "done": i + 1,
+                "exported": exported,
+                "skipped": skipped,
+                "failed": failures.len(),
+                "current": key,
+            }),
+        );
+    }
+
+    let cancelled = cancel.load(Ordering::SeqCst);
+    JOBS.lock().unwrap().remove(&job_id);
+    println!(
+        "[export] Job {} {}: {} exported, {} up to date, {} failed",
+        job_id,
+        if cancelled { "cancelled" } else { "finished" },
+        exported,
+        skipped,
+        failures.len()
+    );
+    let _ = app.emit(
+        "library:export:complete",
+        serde_json::json!({
+            "jobId": job_id,
+            "total": keys.len(),
+            "exported": exported,
+            "skipped": skipped,
+            "failed": failures.len(),
+            "failures": failures,
+            "cancelled": cancelled,
+            // Without ffmpeg files are copied as-is, untagged
+            "ffmpegAvailable": crate::transcode::ffmpeg_available(),
+        }),
+    );
+}
+
+/// Start an export job in the background and return its id
+#[tauri::command]
+pub async fn library_export(
+    app: tauri::AppHandle,
+    request: ExportRequest,
+) -> Result<String, String> {
+    if request.dest_dir.trim().is_empty() {
+        return Err("No library folder given".to_string());
+    }
+    validate_template(request.template.as_deref().unwrap_or(DEFAULT_TEMPLATE))?;
+    std::fs::create_dir_all(&request.dest_dir)
+        .map_err(|e| format!("Failed to create library folder: {}", e))?;
+
+    let job_id = format!("export-{}", NEXT_JOB.fetch_add(1, Ordering::SeqCst));
+    let cancel = Arc::new(AtomicBool::new(false));
+    JOBS.lock().unwrap().insert(job_id.clone(), cancel.clone());
+    tauri::async_runtime::spawn(run_export(app, job_id.clone(), request, cancel));
+    Ok(job_id)
+}
+
+/// Stop an export job after the track it is currently writing
+#[tauri::command]
+pub async fn library_export_cancel(job_id: String) -> Result<bool, String> {
+    match JOBS.lock().unwrap().get(&job_id) {
+        Some(flag) => {
+            flag.store(true, Ordering::SeqCst);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Cache key -> exported file mapping for a library folder
+#[tauri::command]
+pub async fn library_export_mapping(dest_dir: String) -> Result<serde_json::Value, String> {
+    let map = EXPORT_MAP.lock().unwrap();
+    serde_json::to_value(map.libraries.get(&dest_dir).cloned().unwrap_or_default())
+        .map_err(|e| format!("Failed to serialize export mapping: {}", e))
+}
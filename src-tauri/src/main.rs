@@ -6,15 +6,27 @@
 #[macro_use]
 mod logging; // must come first so logging macros are available to subsequent modules
 mod audio_settings;
+mod bandwidth;
 mod bass;
 mod cache;
+mod cache_policy;
 mod commands;
+mod download_history;
 mod downloads;
+mod hooks;
+mod http;
+mod library_export;
+mod partial;
 mod paths;
 mod playback;
+mod proxy;
+mod seeding;
+mod source_health;
 mod utils;
 pub mod plugins;
 pub mod scrape;
+mod tags;
+mod transcode;
 mod youtube;
 mod window;
 
@@ -24,6 +36,10 @@ use commands::torrent as cmd_torrent;
 use logging::BackendLogger;
 use paths::{get_path_config, write_to_log_file, PathConfig};
 mod torrents;
+mod torrent_settings;
+mod torrent_import;
+#[cfg(any(test, feature = "fake-torrents"))]
+mod torrent_fake;
 use window::{handle_window_resize, WindowState};
 
 use once_cell::sync::Lazy;
@@ -186,6 +202,12 @@ fn main() {
             // Initialize audio cache
             cache::init_cache(&path_config.audio_cache_dir)
                 .map_err(|e| format!("Failed to initialize audio cache: {}", e))?;
+            // Read embedded tags for entries cached before metadata extraction existed
+            tags::sweep();
+            // Resume transcode-on-cache for entries cached before the policy was enabled
+            if cache_policy::get_cache_policy().transcode_enabled {
+                transcode::sweep();
+            }
 
             // Initialize window state
             let main_window = app.handle().get_webview_window("main").unwrap();
@@ -197,6 +219,13 @@ fn main() {
 
             // Store global app handle for event emission
             *APP_HANDLE.lock().unwrap() = Some(app_handle.clone());
+            // Apply bandwidth limits and follow the download schedule
+            bandwidth::start_schedule_loop();
+            // Pick up downloads queued before the last shutdown
+            downloads::resume_queue(app_handle.clone());
+            // Restore the torrent session (unfinished torrents resume on their own)
+            torrents::restore_session();
+            seeding::start_seeding_loop();
             // Note: We no longer auto-start any embedded HTTP server.
             // The React app will call app_ready() when it's fully loaded.
 
@@ -240,12 +269,24 @@ fn main() {
             cmd_torrent::torrent_list_scrapers,
             cmd_torrent::torrent_search,
             cmd_torrent::torrent_get_files,
+            cmd_torrent::torrent_add_file,
             cmd_torrent::torrent_start_download,
             cmd_torrent::torrent_progress,
             cmd_torrent::torrent_get_file_path,
             cmd_torrent::torrent_pause,
             cmd_torrent::torrent_resume,
             cmd_torrent::torrent_remove,
+            cmd_torrent::torrent_prioritize,
+            cmd_torrent::torrent_list,
+            cmd_torrent::torrent_details,
+            cmd_torrent::torrent_peers,
+            cmd_torrent::torrent_prefetch_metadata,
+            torrent_import::torrent_import_album,
+            torrent_settings::torrent_get_settings,
+            torrent_settings::torrent_set_settings,
+            seeding::seeding_get_settings,
+            seeding::seeding_set_settings,
+            seeding::seeding_set_metered,
             // Search commands
             search::source_search,
             // YouTube commands
@@ -266,10 +307,34 @@ fn main() {
             commands::playback::playback_set_mute,
             commands::playback::playback_toggle_mute,
             commands::playback::get_download_progress,
+            source_health::sources_get_health,
+            source_health::sources_reset_health,
             // Downloads control commands
             commands::downloads::downloads_pause,
             commands::downloads::downloads_resume,
             commands::downloads::downloads_remove,
+            commands::downloads::downloads_list,
+            commands::downloads::downloads_reprioritize,
+            commands::downloads::downloads_set_concurrency,
+            commands::downloads::downloads_enqueue_batch,
+            commands::downloads::downloads_list_batches,
+            commands::downloads::downloads_pause_batch,
+            commands::downloads::downloads_resume_batch,
+            commands::downloads::downloads_cancel_batch,
+            hooks::hooks_get_settings,
+            hooks::hooks_set_settings,
+            hooks::downloads_hook_result,
+            download_history::downloads_history,
+            download_history::downloads_history_clear,
+            download_history::downloads_history_get_settings,
+            download_history::downloads_history_set_settings,
+            // Network commands
+            http::http_get_settings,
+            http::http_set_settings,
+            http::http_clear_cookies,
+            proxy::proxy_get_settings,
+            proxy::proxy_set_settings,
+            proxy::proxy_test,
             // Audio settings commands
             commands::playback::get_audio_devices,
             commands::playback::get_audio_settings,
@@ -280,8 +345,23 @@ fn main() {
             cache::cache_download_and_store,
             cache::cache_download_status,
             cache::cache_get_stats,
+            cache::cache_list,
+            cache::cache_get_metadata,
             cache::cache_clear,
             cache::cache_list_inflight,
+            cache::cache_set_pinned,
+            cache::cache_get_policy,
+            cache::cache_set_policy,
+            cache::cache_export,
+            cache::cache_import,
+            // Library export commands
+            library_export::library_export,
+            library_export::library_export_cancel,
+            library_export::library_export_mapping,
+            // Bandwidth commands
+            bandwidth::bandwidth_get_settings,
+            bandwidth::bandwidth_set_settings,
+            bandwidth::bandwidth_get_state,
             // External API commands
             external::charts_get_weekly_tops,
             external::genius_search,
@@ -304,6 +384,7 @@ fn main() {
                 tauri::WindowEvent::CloseRequested { .. } => {
                     // Cleanup BASS resources before closing main window
                     if window.label() == "main" {
+                        crate::download_history::flush();
                         tauri::async_runtime::spawn(async move {
                             let _ = crate::playback::playback_cleanup_internal().await;
                         });
@@ -0,0 +1,668 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// Sidecar next to each `<base>.part` recording which byte ranges are already on disk
+const SIDECAR_SUFFIX: &str = ".ranges";
+const SIDECAR_VERSION: u32 = 1;
+// Persist the range map after this many newly written bytes (and always on flush/drop)
+const SAVE_EVERY_BYTES: u64 = 1024 * 1024;
+// Block size used when mirroring from another file (torrent engine output)
+const MIRROR_BLOCK: u64 = 256 * 1024;
+// How often a blocked PartReader re-checks the range map, and how long it waits for a gap
+const READ_POLL: Duration = Duration::from_millis(50);
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Persisted description of a partial cache file
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PartialMeta {
+    #[serde(default)]
+    pub version: u32,
+    pub track_id: String,
+    pub source_type: String,
+    pub source_hash: String,
+    #[serde(default)]
+    pub file_index: Option<usize>,
+    #[serde(default)]
+    pub total: Option<u64>,
+    // Sorted, non-overlapping, half-open [start, end) byte ranges present in the .part file
+    #[serde(default)]
+    pub ranges: Vec<(u64, u64)>,
+    #[serde(default)]
+    pub updated_at: u64,
+}
+
+impl PartialMeta {
+    fn insert(&mut self, start: u64, end: u64) {
+        if end <= start {
+            return;
+        }
+        let (mut start, mut end) = (start, end);
+        let mut merged = Vec::with_capacity(self.ranges.len() + 1);
+        for &(s, e) in &self.ranges {
+            if e < start || s > end {
+                merged.push((s, e));
+            } else {
+                start = start.min(s);
+                end = end.max(e);
+            }
+        }
+        let at = merged.partition_point(|&(s, _)| s < start);
+        merged.insert(at, (start, end));
+        self.ranges = merged;
+    }
+
+    fn clip(&mut self, len: u64) {
+        self.ranges.retain(|&(s, _)| s < len);
+        if let Some(last) = self.ranges.last_mut() {
+            last.1 = last.1.min(len);
+        }
+    }
+
+    /// Total number of bytes present
+    pub fn covered(&self) -> u64 {
+        self.ranges.iter().map(|&(s, e)| e - s).sum()
+    }
+
+    /// Length of the contiguous run starting at byte 0
+    pub fn prefix_len(&self) -> u64 {
+        match self.ranges.first() {
+            Some(&(0, e)) => e,
+            _ => 0,
+        }
+    }
+
+    /// End of the present run containing `pos`, if `pos` is on disk
+    pub fn run_end_at(&self, pos: u64) -> Option<u64> {
+        self.ranges
+            .iter()
+            .find(|&&(s, e)| s <= pos && pos < e)
+            .map(|&(_, e)| e)
+    }
+
+    /// First missing range as (start, exclusive end); `None` end means "until EOF"
+    pub fn next_gap(&self) -> Option<(u64, Option<u64>)> {
+        let start = self.prefix_len();
+        if let Some(total) = self.total {
+            if start >= total {
+                return None;
+            }
+        }
+        let end = self.ranges.iter().map(|&(s, _)| s).find(|&s| s > start);
+        Some((start, end.or(self.total)))
+    }
+
+    pub fn is_complete(&self) -> bool {
+        matches!(self.total, Some(t) if t > 0 && self.prefix_len() >= t)
+    }
+
+    /// Missing ranges inside `[start, end)`
+    pub fn uncovered(&self, start: u64, end: u64) -> Vec<(u64, u64)> {
+        let mut out = Vec::new();
+        let mut cur = start;
+        for &(s, e) in &self.ranges {
+            if e <= cur {
+                continue;
+            }
+            if s >= end {
+                break;
+            }
+            if s > cur {
+                out.push((cur, s.min(end)));
+            }
+            cur = cur.max(e);
+            if cur >= end {
+                break;
+            }
+        }
+        if cur < end {
+            out.push((cur, end));
+        }
+        out
+    }
+}
+
+/// An open `.part` file plus its range map; shared by every writer of the same cache key
+pub struct PartialFile {
+    path: PathBuf,
+    file: File,
+    meta: PartialMeta,
+    unsaved: u64,
+    finished: bool,
+    // Bumped per range map snapshot, so a slow save can't overwrite a newer one
+    saved_seq: u64,
+    sidecar: Arc<Mutex<SidecarState>>,
+}
+
+// Guards the sidecar itself; held only around its rename, never across data writes or fsync
+#[derive(Default)]
+struct SidecarState {
+    written_seq: u64,
+    closed: bool,
+}
+
+/// Range map snapshot taken under the partial's lock and written out after it is released
+pub struct PendingSave {
+    path: PathBuf,
+    file: File,
+    content: String,
+    seq: u64,
+    sidecar: Arc<Mutex<SidecarState>>,
+}
+
+impl PendingSave {
+    /// Sync the data the snapshot describes, then replace the sidecar unless a newer one won
+    pub fn write(self) -> Result<(), String> {
+        self.file
+            .sync_data()
+            .map_err(|e| format!("Failed to flush partial file: {}", e))?;
+        let mut state = self.sidecar.lock().unwrap();
+        if state.closed || state.written_seq >= self.seq {
+            return Ok(());
+        }
+        let sidecar = sidecar_path(&self.path);
+        let tmp = sidecar.with_extension("ranges.tmp");
+        std::fs::write(&tmp, &self.content)
+            .and_then(|_| std::fs::rename(&tmp, &sidecar))
+            .map_err(|e| format!("Failed to write range map: {}", e))?;
+        state.written_seq = self.seq;
+        Ok(())
+    }
+}
+
+pub type SharedPartial = Arc<Mutex<PartialFile>>;
+
+// Live partial files keyed by .part path, so the playback callback and the cache downloader
+// share one range map instead of overwriting each other's sidecar
+static OPEN: Lazy<Mutex<HashMap<PathBuf, Weak<Mutex<PartialFile>>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Sidecar path for a `.part` file
+pub fn sidecar_path(part_path: &Path) -> PathBuf {
+    let mut s = part_path.as_os_str().to_os_string();
+    s.push(SIDECAR_SUFFIX);
+    PathBuf::from(s)
+}
+
+fn read_sidecar(part_path: &Path) -> Option<PartialMeta> {
+    let content = std::fs::read_to_string(sidecar_path(part_path)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+impl PartialFile {
+    pub fn meta(&self) -> &PartialMeta {
+        &self.meta
+    }
+
+    pub fn set_total(&mut self, total: u64) {
+        if total > 0 && self.meta.total != Some(total) {
+            self.meta.total = Some(total);
+            self.meta.clip(total);
+            self.unsaved = SAVE_EVERY_BYTES;
+        }
+    }
+
+    /// Write the parts of `data` (located at `offset`) that are not already on disk.
+    /// Returns the number of newly stored bytes.
+    pub fn write_at(&mut self, offset: u64, data: &[u8]) -> std::io::Result<u64> {
+        if self.finished || data.is_empty() {
+            return Ok(0);
+        }
+        let mut end = offset + data.len() as u64;
+        if let Some(total) = self.meta.total {
+            end = end.min(total);
+        }
+        let mut written = 0u64;
+        for (s, e) in self.meta.uncovered(offset, end) {
+            let slice = &data[(s - offset) as usize..(e - offset) as usize];
+            self.file.seek(SeekFrom::Start(s))?;
+            self.file.write_all(slice)?;
+            self.meta.insert(s, e);
+            written += e - s;
+        }
+        self.unsaved += written;
+        Ok(written)
+    }
+
+    /// Read up to `n` bytes from the start of the file (for content validation on resume)
+    pub fn read_head(&mut self, n: usize) -> Vec<u8> {
+        let want = (self.meta.prefix_len() as usize).min(n);
+        let mut buf = vec![0u8; want];
+        let ok =
+            self.file.seek(SeekFrom::Start(0)).is_ok() && self.file.read_exact(&mut buf).is_ok();
+        if ok {
+            buf
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Snapshot the range map once enough new bytes are on disk (or `force`), for the
+    /// caller to write out after dropping the lock
+    pub fn take_save(&mut self, force: bool) -> Result<Option<PendingSave>, String> {
+        if self.finished || !(force || self.unsaved >= SAVE_EVERY_BYTES) {
+            return Ok(None);
+        }
+        self.meta.version = SIDECAR_VERSION;
+        self.meta.updated_at = now_secs();
+        let content = serde_json::to_string(&self.meta)
+            .map_err(|e| format!("Failed to serialize range map: {}", e))?;
+        let file = self
+            .file
+            .try_clone()
+            .map_err(|e| format!("Failed to reopen partial file: {}", e))?;
+        self.saved_seq += 1;
+        self.unsaved = 0;
+        Ok(Some(PendingSave {
+            path: self.path.clone(),
+            file,
+            content,
+            seq: self.saved_seq,
+            sidecar: self.sidecar.clone(),
+        }))
+    }
+
+    /// Flush data and save the range map while holding the file
+    pub fn persist(&mut self) -> Result<(), String> {
+        match self.take_save(true)? {
+            Some(save) => save.write(),
+            None => Ok(()),
+        }
+    }
+
+    /// Stop tracking: the .part is about to be renamed into the cache
+    pub fn finish(&mut self) {
+        let _ = self.file.flush();
+        self.finished = true;
+        self.sidecar.lock().unwrap().closed = true;
+        let _ = std::fs::remove_file(sidecar_path(&self.path));
+    }
+
+    /// Drop the partial download entirely
+    pub fn discard(&mut self) {
+        self.finished = true;
+        self.sidecar.lock().unwrap().closed = true;
+        let _ = std::fs::remove_file(&self.path);
+        let _ = std::fs::remove_file(sidecar_path(&self.path));
+    }
+}
+
+impl Drop for PartialFile {
+    fn drop(&mut self) {
+        if !self.finished {
+            if let Err(e) = self.persist() {
+                println!("[cache] {}: {}", e, self.path.display());
+            }
+        }
+    }
+}
+
+/// Reader over a `.part` that blocks on ranges the writer hasn't filled yet, so BASS can
+/// decode a cache file while it is still downloading instead of playing its holes as silence
+pub struct PartReader {
+    partial: SharedPartial,
+    file: File,
+    pos: u64,
+}
+
+impl PartReader {
+    pub fn new(partial: SharedPartial) -> Result<Self, String> {
+        let path = partial.lock().unwrap().path.clone();
+        let file = File::open(&path)
+            .map_err(|e| format!("Failed to open partial file for reading: {}", e))?;
+        Ok(Self {
+            partial,
+            file,
+            pos: 0,
+        })
+    }
+
+    /// Bytes readable at the current position without blocking; `Some(0)` at end of file
+    fn available(&self) -> Option<u64> {
+        let p = self.partial.lock().unwrap();
+        if let Some(total) = p.meta.total {
+            if self.pos >= total {
+                return Some(0);
+            }
+        }
+        if p.finished {
+            // Renamed into the cache: everything is on disk, our handle still points at it
+            return Some(u64::MAX);
+        }
+        p.meta.run_end_at(self.pos).map(|end| end - self.pos)
+    }
+}
+
+impl crate::bass::BlockingReader for PartReader {
+    fn length(&self) -> u64 {
+        self.partial.lock().unwrap().meta.total.unwrap_or(0)
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let started = Instant::now();
+        let ready = loop {
+            if let Some(n) = self.available() {
+                break n;
+            }
+            if started.elapsed() >= READ_TIMEOUT {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    format!("no data at offset {} after {:?}", self.pos, READ_TIMEOUT),
+                ));
+            }
+            std::thread::sleep(READ_POLL);
+        };
+        let want = (buf.len() as u64).min(ready) as usize;
+        if want == 0 {
+            return Ok(0);
+        }
+        self.file.seek(SeekFrom::Start(self.pos))?;
+        let n = self.file.read(&mut buf[..want])?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+
+    fn seek(&mut self, offset: u64) -> bool {
+        if matches!(self.partial.lock().unwrap().meta.total, Some(t) if offset > t) {
+            return false;
+        }
+        self.pos = offset;
+        true
+    }
+}
+
+/// Open (or join) the partial file at `part_path`, restoring its range map from the sidecar.
+/// A legacy `.part` without a sidecar is treated as a contiguous prefix of its current length.
+pub fn open(
+    part_path: &Path,
+    track_id: &str,
+    source_type: &str,
+    source_hash: &str,
+    file_index: Option<usize>,
+) -> Result<SharedPartial, String> {
+    let mut open_files = OPEN.lock().unwrap();
+    if let Some(existing) = open_files.get(part_path).and_then(|w| w.upgrade()) {
+        if !existing.lock().unwrap().finished {
+            return Ok(existing);
+        }
+    }
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .open(part_path)
+        .map_err(|e| format!("Failed to open partial file: {}", e))?;
+    let len = file
+        .metadata()
+        .map_err(|e| format!("Failed to stat partial file: {}", e))?
+        .len();
+
+    let mut meta = match read_sidecar(part_path) {
+        Some(m) => m,
+        None => {
+            let mut m = PartialMeta::default();
+            if len > 0 {
+                println!(
+                    "[cache] No range map for {}, assuming {} contiguous bytes",
+                    part_path.display(),
+                    len
+                );
+                m.ranges.push((0, len));
+            }
+            m
+        }
+    };
+    // Never trust ranges beyond what actually reached the disk
+    meta.clip(len);
+    meta.track_id = track_id.to_string();
+    meta.source_type = source_type.to_string();
+    meta.source_hash = source_hash.to_string();
+    meta.file_index = file_index;
+
+    let shared = Arc::new(Mutex::new(PartialFile {
+        path: part_path.to_path_buf(),
+        file,
+        meta,
+        unsaved: 0,
+        finished: false,
+        saved_seq: 0,
+        sidecar: Arc::new(Mutex::new(SidecarState::default())),
+    }));
+    open_files.retain(|_, w| w.strong_count() > 0);
+    open_files.insert(part_path.to_path_buf(), Arc::downgrade(&shared));
+    Ok(shared)
+}
+
+/// Write `data` at `offset`, syncing and saving the range map after the lock is released.
+/// Blocking: async callers go through `spawn_blocking`.
+pub fn write_shared(partial: &SharedPartial, offset: u64, data: &[u8]) -> std::io::Result<u64> {
+    let (written, save) = {
+        let mut p = partial.lock().unwrap();
+        let written = p.write_at(offset, data)?;
+        (written, p.take_save(false))
+    };
+    if let Err(e) = save.and_then(|s| s.map_or(Ok(()), PendingSave::write)) {
+        println!("[cache] {}", e);
+    }
+    Ok(written)
+}
+
+/// Flush data and save the range map without holding the lock across the fsync
+pub fn persist(partial: &SharedPartial) -> Result<(), String> {
+    let save = partial.lock().unwrap().take_save(true)?;
+    save.map_or(Ok(()), PendingSave::write)
+}
+
+/// Copy the parts of `ranges` we don't have yet from `src`. Callers pass only ranges the
+/// source has actually verified; the content itself says nothing about presence.
+pub fn mirror(partial: &SharedPartial, src: &Path, ranges: &[(u64, u64)]) -> std::io::Result<u64> {
+    let mut input = File::open(src)?;
+    let mut buf = vec![0u8; MIRROR_BLOCK as usize];
+    let mut copied = 0u64;
+    for &(start, end) in ranges {
+        let gaps = partial.lock().unwrap().meta.uncovered(start, end);
+        for (s, e) in gaps {
+            let mut pos = s;
+            while pos < e {
+                let want = (e - pos).min(MIRROR_BLOCK) as usize;
+                input.seek(SeekFrom::Start(pos))?;
+                let n = input.read(&mut buf[..want])?;
+                if n == 0 {
+                    break;
+                }
+                copied += write_shared(partial, pos, &buf[..n])?;
+                pos += n as u64;
+            }
+        }
+    }
+    Ok(copied)
+}
+
+/// Current range map for a `.part`, preferring the live writer over the sidecar
+pub fn load(part_path: &Path) -> Option<PartialMeta> {
+    let live = OPEN
+        .lock()
+        .unwrap()
+        .get(part_path)
+        .and_then(|w| w.upgrade());
+    if let Some(p) = live {
+        return Some(p.lock().unwrap().meta.clone());
+    }
+    read_sidecar(part_path)
+}
+
+/// Mark a `.part` as finalized, whether or not a writer still has it open
+pub fn finish(part_path: &Path) {
+    let live = OPEN
+        .lock()
+        .unwrap()
+        .get(part_path)
+        .and_then(|w| w.upgrade());
+    match live {
+        Some(p) => p.lock().unwrap().finish(),
+        None => {
+            let _ = std::fs::remove_file(sidecar_path(part_path));
+        }
+    }
+}
+
+/// Delete a `.part` and its range map, whether or not a writer still has it open
+pub fn discard(part_path: &Path) {
+    let live = OPEN
+        .lock()
+        .unwrap()
+        .get(part_path)
+        .and_then(|w| w.upgrade());
+    match live {
+        Some(p) => p.lock().unwrap().discard(),
+        None => {
+            let _ = std::fs::remove_file(part_path);
+            let _ = std::fs::remove_file(sidecar_path(part_path));
+        }
+    }
+}
+
+/// Partial downloads recorded in the cache dir, e.g. to list resumable items after a restart
+pub fn list(cache_dir: &Path) -> Vec<(PathBuf, PartialMeta)> {
+    let mut out = Vec::new();
+    let rd = match std::fs::read_dir(cache_dir) {
+        Ok(rd) => rd,
+        Err(_) => return out,
+    };
+    for entry in rd.flatten() {
+        let path = entry.path();
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        if let Some(part_name) = name.strip_suffix(SIDECAR_SUFFIX) {
+            let part_path = path.with_file_name(part_name);
+            if !part_path.exists() {
+                let _ = std::fs::remove_file(&path);
+                continue;
+            }
+            if let Some(meta) = load(&part_path) {
+                out.push((part_path, meta));
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(ranges: &[(u64, u64)], total: Option<u64>) -> PartialMeta {
+        PartialMeta {
+            ranges: ranges.to_vec(),
+            total,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn insert_merges_overlapping_and_adjacent_ranges() {
+        let mut m = PartialMeta::default();
+        m.insert(10, 20);
+        m.insert(40, 50);
+        m.insert(0, 5);
+        assert_eq!(m.ranges, vec![(0, 5), (10, 20), (40, 50)]);
+
+        // Overlapping on both sides
+        m.insert(15, 45);
+        assert_eq!(m.ranges, vec![(0, 5), (10, 50)]);
+
+        // Touching ends join into one run
+        m.insert(5, 10);
+        assert_eq!(m.ranges, vec![(0, 50)]);
+        m.insert(50, 60);
+        assert_eq!(m.ranges, vec![(0, 60)]);
+
+        // Already covered and empty inserts change nothing
+        m.insert(20, 30);
+        m.insert(70, 70);
+        assert_eq!(m.ranges, vec![(0, 60)]);
+        assert_eq!(m.covered(), 60);
+    }
+
+    #[test]
+    fn uncovered_lists_missing_ranges_in_window() {
+        let m = meta(&[(10, 20), (30, 40)], Some(50));
+        assert_eq!(m.uncovered(0, 50), vec![(0, 10), (20, 30), (40, 50)]);
+        assert_eq!(m.uncovered(15, 35), vec![(20, 30)]);
+        assert_eq!(m.uncovered(10, 20), vec![]);
+        assert_eq!(m.uncovered(5, 15), vec![(5, 10)]);
+        assert_eq!(m.uncovered(35, 45), vec![(40, 45)]);
+
+        let empty = PartialMeta::default();
+        assert_eq!(empty.uncovered(0, 10), vec![(0, 10)]);
+        assert_eq!(empty.covered(), 0);
+    }
+
+    #[test]
+    fn next_gap_finds_first_hole() {
+        // Empty map: everything from byte 0, bounded by the total when known
+        assert_eq!(PartialMeta::default().next_gap(), Some((0, None)));
+        assert_eq!(meta(&[], Some(100)).next_gap(), Some((0, Some(100))));
+
+        // Gap at the start, before the first run
+        assert_eq!(meta(&[(10, 20)], Some(100)).next_gap(), Some((0, Some(10))));
+
+        // Gap between runs
+        assert_eq!(meta(&[(0, 10), (30, 40)], None).next_gap(), Some((10, Some(30))));
+
+        // Gap at the end, open-ended until the size is known
+        assert_eq!(meta(&[(0, 60)], Some(100)).next_gap(), Some((60, Some(100))));
+        assert_eq!(meta(&[(0, 60)], None).next_gap(), Some((60, None)));
+
+        let done = meta(&[(0, 100)], Some(100));
+        assert_eq!(done.next_gap(), None);
+        assert!(done.is_complete());
+    }
+
+    #[test]
+    fn range_map_survives_restart() {
+        let dir = std::env::temp_dir().join(format!("freely-partial-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let part = dir.join("track.part");
+
+        let partial = open(&part, "track", "http", "hash", None).unwrap();
+        write_shared(&partial, 0, &[1; 100]).unwrap();
+        write_shared(&partial, 300, &[2; 50]).unwrap();
+        partial.lock().unwrap().set_total(400);
+        persist(&partial).unwrap();
+        drop(partial);
+
+        // A new process only has the sidecar to go on
+        let saved = read_sidecar(&part).unwrap();
+        assert_eq!(saved.ranges, vec![(0, 100), (300, 350)]);
+        assert_eq!(saved.total, Some(400));
+
+        let partial = open(&part, "track", "http", "hash", None).unwrap();
+        {
+            let p = partial.lock().unwrap();
+            assert_eq!(p.meta().ranges, vec![(0, 100), (300, 350)]);
+            assert_eq!(p.meta().next_gap(), Some((100, Some(300))));
+        }
+        // Only the hole is written again
+        assert_eq!(write_shared(&partial, 50, &[3; 300]).unwrap(), 200);
+        assert_eq!(partial.lock().unwrap().meta().ranges, vec![(0, 350)]);
+
+        partial.lock().unwrap().discard();
+        assert!(!part.exists());
+        assert!(!sidecar_path(&part).exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
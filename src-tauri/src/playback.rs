@@ -18,7 +18,7 @@ use crate::bass::{
 };
 use crate::bass::{
     BassChannelPlay, BassChannelSeconds2Bytes, BassChannelSetAttribute, BassChannelSetPosition,
-    BassChannelStop, BassDeviceInfo, BassStreamCreateFile, BassStreamFree, DownloadProc,
+    BassChannelStop, BassDeviceInfo, BassStreamCreateFile, BassStreamFree, BlockingReader, DownloadProc,
     BASS_ACTIVE_PAUSED, BASS_ACTIVE_PLAYING, BASS_ACTIVE_STALLED, BASS_ACTIVE_STOPPED,
     BASS_ATTRIB_FREQ, BASS_ATTRIB_VOL, BASS_CONFIG_NET_AGENT, BASS_CONFIG_NET_BUFFER,
     BASS_CONFIG_NET_TIMEOUT, BASS_DEVICE_DEFAULT, BASS_DEVICE_DEFAULT_FLAG, BASS_DEVICE_ENABLED,
@@ -38,10 +38,9 @@ use anyhow::Result;
 use libloading::{Library, Symbol};
 use once_cell::sync::Lazy;
 use serde::Deserialize;
-use std::fs::{File, OpenOptions};
-use std::io::Write;
 use std::os::raw::{c_char, c_int, c_uint, c_ulong};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::SystemTime;
 use std::{
@@ -136,6 +135,7 @@ pub fn ensure_bass_initialized(state: &mut PlaybackState, force_reinit: bool) ->
         
         // Apply configuration before initialization
         settings.apply_to_bass(lib);
+        crate::proxy::apply_to_bass(lib);
         
         // Initialize BASS
         let ok = bass_init(lib, settings.device_id, settings.sample_rate, 0);
@@ -221,6 +221,8 @@ async fn restore_playback_state(snapshot: PlaybackStateSnapshot) -> Result<(), S
                 prefer_cache: Some(true),
                 source_meta: None,
                 client_request_id: Some("audio_settings_restore".to_string()),
+                fallbacks: Vec::new(),
+                timeout_ms: None,
             };
             
             // We need the app handle for playback_start_with_source_internal
@@ -374,10 +376,12 @@ pub struct DownloadFileState {
     pub source_type: String,
     pub source_hash: String,
     pub file_index: Option<usize>,
-    pub cache_file: Arc<Mutex<File>>,
+    // Part file and its persisted range map (shared with a concurrent cache download, if any)
+    pub partial: crate::partial::SharedPartial,
     pub cache_path: PathBuf,
-    // When resuming without server Range support, skip this many bytes from the start
-    pub skip_remaining: u64,
+    // File offset of the next byte the callback will receive; STREAM_POS_DETACHED after a
+    // network seek, since BASS then continues from an offset we can't observe
+    pub stream_pos: Arc<AtomicU64>,
     // Observed bytes downloaded for this stream (captured on stop)
     pub downloaded_bytes: u64,
     // Observed total bytes if known (captured on stop)
@@ -386,6 +390,8 @@ pub struct DownloadFileState {
     pub download_complete: bool,
 }
 
+const STREAM_POS_DETACHED: u64 = u64::MAX;
+
 // filename helper was moved to crate::cache::create_cache_filename
 
 // BASS download callback function
@@ -404,10 +410,14 @@ unsafe extern "C" fn download_proc(buffer: *const c_void, length: c_uint, user:
         // Mark download as complete
         state.download_complete = true;
 
-        // Flush the file
-        if let Ok(mut file) = state.cache_file.lock() {
-            let _ = file.flush();
+        // Flush the file and range map; a download that ran from byte 0 also tells us the size
+        if let Ok(mut partial) = state.partial.lock() {
+            let pos = state.stream_pos.load(Ordering::SeqCst);
+            if pos != STREAM_POS_DETACHED && partial.meta().total.is_none() {
+                partial.set_total(pos);
+            }
         }
+        let _ = crate::partial::persist(&state.partial);
 
         // If the track has already ended, finalize the cache now
         // We need to check if the playback has ended
@@ -422,29 +432,20 @@ unsafe extern "C" fn download_proc(buffer: *const c_void, length: c_uint, user:
     // Convert buffer to byte slice and write to cache file
     let data_slice = std::slice::from_raw_parts(buffer as *const u8, length as usize);
 
-    // If we need to skip some initial bytes (server didn't honor resume), drop them
-    let mut write_slice = data_slice;
-    if state.skip_remaining > 0 {
-        let to_skip = std::cmp::min(state.skip_remaining as usize, write_slice.len());
-        state.skip_remaining -= to_skip as u64;
-        write_slice = &write_slice[to_skip..];
-        if write_slice.is_empty() {
-            return; // nothing to write this callback
-        }
+    let pos = state.stream_pos.load(Ordering::SeqCst);
+    if pos == STREAM_POS_DETACHED {
+        return; // offset unknown after a seek; the gap is filled by a later resume
     }
+    state.stream_pos.store(pos + data_slice.len() as u64, Ordering::SeqCst);
 
-    if let Ok(mut file) = state.cache_file.lock() {
-        let _ = file.write_all(write_slice);
-        // Flush every 100KB to balance performance and data safety
-        if length > 100_000 {
-            let _ = file.flush();
-        }
+    // Only bytes missing from the range map are written, so resumed streams never duplicate data
+    if let Err(e) = crate::partial::write_shared(&state.partial, pos, data_slice) {
+        log_warn!("[bass] Failed to write cache data at {}: {}", pos, e);
+    }
+    // Update in-memory counter for bytes on disk (best-effort)
+    if let Ok(partial) = state.partial.lock() {
+        state.downloaded_bytes = partial.meta().covered();
     }
-
-    // Update in-memory counter for bytes written (best-effort)
-    state.downloaded_bytes = state
-        .downloaded_bytes
-        .saturating_add(write_slice.len() as u64);
 }
 
 pub struct PlaybackState {
@@ -464,6 +465,8 @@ pub struct PlaybackState {
     current_track_id: Option<String>,
     current_source_type: Option<String>,
     current_source_hash: Option<String>,
+    // Torrent file being played, so seeks can move the engine's piece priority
+    current_file_index: Option<usize>,
     // Download file state for BASS callback
     download_file_state: Option<Box<DownloadFileState>>,
     // Codec/format information
@@ -491,6 +494,7 @@ impl PlaybackState {
             current_track_id: None,
             current_source_type: None,
             current_source_hash: None,
+            current_file_index: None,
             download_file_state: None,
             // Codec/format information
             codec: None,
@@ -506,6 +510,20 @@ static STATE: Lazy<Mutex<PlaybackState>> = Lazy::new(|| Mutex::new(PlaybackState
 // accidentally invokes it multiple times in quick succession. Maps a key -> epoch millis.
 static RECENT_STARTS: Lazy<Mutex<HashMap<String, u128>>> = Lazy::new(|| Mutex::new(HashMap::new()));
 
+// Start the torrent behind a torrent:// URL and open its engine reader, ahead of
+// create_bass_stream and off the async runtime; None for any other URL
+async fn open_torrent_reader(url: &str) -> Result<Option<Box<dyn BlockingReader>>, String> {
+    let rest = match url.strip_prefix("torrent://") {
+        Some(rest) => rest,
+        None => return Ok(None),
+    };
+    let (info_hash, index) = rest.split_once('/').unwrap_or((rest, "0"));
+    let index: u32 = index.parse().map_err(|_| format!("Invalid torrent file index: {}", index))?;
+    let key = format!("magnet:?xt=urn:btih:{}", info_hash);
+    log_debug!("[bass] Creating stream from torrent {} file {}", info_hash, index);
+    crate::torrents::open_stream_reader(key, index).await.map(Some)
+}
+
 // Unified function to create BASS streams with optional download callback for caching
 fn create_bass_stream(
     lib: &Library,
@@ -513,6 +531,7 @@ fn create_bass_stream(
     enable_caching: bool,
     cache_info: Option<(&str, &str, &str)>, // track_id, source_type, source_hash
     file_index: Option<usize>,
+    torrent_reader: Option<Box<dyn BlockingReader>>, // from open_torrent_reader for torrent:// URLs
 ) -> Result<(u32, Option<Box<DownloadFileState>>), String> {
     let handle = if url.starts_with("file://") {
         // For local files, use BASS_StreamCreateFile (no caching needed)
@@ -528,6 +547,20 @@ fn create_bass_stream(
             None,
             std::ptr::null_mut(),
         )
+    } else if url.starts_with("torrent://") {
+        // Torrent file read in place from the engine: BASS decodes as soon as the first
+        // pieces verify instead of waiting for the file to appear on disk
+        let reader = torrent_reader.ok_or("Torrent reader was not opened")?;
+        stream_create(
+            lib,
+            StreamSource::User {
+                reader,
+                buffered: true,
+            },
+            BASS_STREAM_AUTOFREE,
+            None,
+            std::ptr::null_mut(),
+        )
     } else {
         // For remote URLs, use BASS_StreamCreateURL
         let c_url = CString::new(url).map_err(|_| "Invalid URL: contains null bytes")?;
@@ -544,56 +577,43 @@ fn create_bass_stream(
                 create_cache_filename_with_index(track_id, source_type, source_hash, file_index);
             let cache_path = cache_dir.join(format!("{}.part", base));
 
-            // Create or open the cache file. If it exists, open for append to resume;
-            // otherwise create a new file.
-            let (cache_file, existing_len) = if cache_path.exists() {
-                let meta = std::fs::metadata(&cache_path)
-                    .map_err(|e| format!("Failed to stat cache file: {}", e))?;
-                let len = meta.len();
-                let f = OpenOptions::new()
-                    .create(true)
-                    .append(true)
-                    .open(&cache_path)
-                    .map_err(|e| format!("Failed to open cache file for append: {}", e))?;
+            // Create or resume the cache file; its range map says which bytes are already there
+            let partial = crate::partial::open(
+                &cache_path,
+                track_id,
+                source_type,
+                source_hash,
+                file_index,
+            )?;
+            let existing_len = partial.lock().unwrap().meta().covered();
+            if existing_len > 0 {
                 log_debug!(
                     "[bass] Resuming cache file: {} (existing {} bytes)",
                     cache_path.display(),
-                    len
+                    existing_len
                 );
-                (f, len)
             } else {
-                let f = OpenOptions::new()
-                    .create(true)
-                    .write(true)
-                    .truncate(true)
-                    .open(&cache_path)
-                    .map_err(|e| format!("Failed to create cache file: {}", e))?;
-                (f, 0)
-            };
-
-            log_debug!("[bass] Creating cache file: {}", cache_path.display());
+                log_debug!("[bass] Creating cache file: {}", cache_path.display());
+            }
 
             // Create download state
-            let mut download_state = Box::new(DownloadFileState {
+            let download_state = Box::new(DownloadFileState {
                 track_id: track_id.to_string(),
                 source_type: source_type.to_string(),
                 source_hash: source_hash.to_string(),
                 file_index,
-                cache_file: Arc::new(Mutex::new(cache_file)),
+                partial,
                 cache_path: cache_path,
-                skip_remaining: 0,
-                downloaded_bytes: existing_len as u64,
+                stream_pos: Arc::new(AtomicU64::new(0)),
+                downloaded_bytes: existing_len,
                 total_bytes: None,
                 download_complete: false,
             });
 
             let stream_flags = BASS_STREAM_STATUS | BASS_STREAM_BLOCK;
 
-            // Robust resume: always start from 0 and skip existing bytes in the callback.
-            // This avoids duplicate data when servers ignore Range requests.
-            if existing_len > 0 {
-                download_state.skip_remaining = existing_len as u64;
-            }
+            // Robust resume: always start from 0 (the decoder needs the header) and let the
+            // range map drop bytes already on disk. This also works when servers ignore Range.
             let handle = stream_create(
                 lib,
                 StreamSource::Url {
@@ -650,19 +670,22 @@ async fn finalize_cache_file(
 ) {
     // Check if file exists and has content
     if let Ok(metadata) = std::fs::metadata(&cache_path) {
-        let file_size = metadata.len();
+        // The range map is authoritative when present: the file may have holes or be preallocated
+        let ranges = crate::partial::load(&cache_path);
+        let file_size = ranges.as_ref().map(|m| m.covered()).unwrap_or(metadata.len());
 
         if file_size > 1024 {
             // Only cache files larger than 1KB
             // If total size is known and file is incomplete, do not finalize yet
-            if let Some(total) = total_bytes {
-                if file_size < total {
-                    log_debug!("[bass] Download incomplete ({} of {} bytes), keeping .part: {}", file_size, total, cache_path.display());
+            if let Some(total) = total_bytes.or(ranges.as_ref().and_then(|m| m.total)) {
+                let have = ranges.as_ref().map(|m| m.prefix_len()).unwrap_or(file_size);
+                if have < total {
+                    log_debug!("[bass] Download incomplete ({} of {} bytes), keeping .part: {}", have, total, cache_path.display());
                     return;
                 }
             } else {
-                // Unknown total size; check if BASS indicated download completion
-                if download_complete {
+                // Unknown total size; check if BASS indicated download completion over a gap-free file
+                if download_complete && ranges.as_ref().map_or(true, |m| m.ranges.len() == 1) {
                     log_info!("[bass] Download marked complete by BASS despite unknown total size, finalizing: {}", cache_path.display());
                 } else {
                     // Unknown total size and not marked complete - defer finalization
@@ -678,6 +701,9 @@ async fn finalize_cache_file(
             if let Some(cache_dir) = get_cache_dir() {
                 let final_cache_path = cache_dir.join(&cache_filename);
 
+                // Stop range tracking (and any writer still holding the part file) before the move
+                crate::partial::finish(&cache_path);
+
                 // Move the temporary .part file to the final (extension-less) location
                 if let Err(e) = std::fs::rename(&cache_path, &final_cache_path) {
                     log_warn!("[bass] Failed to move cache file to final location: {}", e);
@@ -716,6 +742,7 @@ async fn finalize_cache_file(
         } else {
             log_debug!("[bass] Cache file too small ({} bytes), not caching: {}", file_size, cache_path.display());
             let _ = std::fs::remove_file(&cache_path);
+            let _ = std::fs::remove_file(crate::partial::sidecar_path(&cache_path));
         }
     } else {
         log_debug!("[bass] Cache file does not exist or cannot be accessed: {}", cache_path.display());
@@ -739,6 +766,7 @@ pub async fn playback_start_internal(url: String) -> Result<serde_json::Value, S
     } else {
         url
     };
+    let torrent_reader = open_torrent_reader(&actual_url).await?;
 
     let mut st = STATE.lock().unwrap();
 
@@ -761,7 +789,7 @@ pub async fn playback_start_internal(url: String) -> Result<serde_json::Value, S
 
     // Create new stream using unified function (no caching for simple playback_start)
     log_info!("[bass] Creating stream for: {}", actual_url);
-    let (handle, _download_state) = match create_bass_stream(lib, &actual_url, false, None, None) {
+    let (handle, _download_state) = match create_bass_stream(lib, &actual_url, false, None, None, torrent_reader) {
         Ok(v) => v,
         Err(e) => return Err(e),
     };
@@ -837,6 +865,7 @@ pub async fn playback_start_internal(url: String) -> Result<serde_json::Value, S
         st.current_track_id = None;
         st.current_source_type = None;
         st.current_source_hash = None;
+        st.current_file_index = None;
     }
 
     // Emit status update
@@ -1042,7 +1071,7 @@ async fn gapless_handoff_to(cached_path: String) {
     );
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize)]
 pub struct PlaybackSourceSpec {
     pub track_id: String,
     pub source_type: String,
@@ -1051,60 +1080,238 @@ pub struct PlaybackSourceSpec {
     pub source_meta: Option<serde_json::Value>,
     // Optional client-provided ID for correlation
     pub client_request_id: Option<String>,
+    // Alternatives tried in order when the primary source fails or times out
+    #[serde(default)]
+    pub fallbacks: Vec<SourceAlternative>,
+    // Per-source time limit for the primary; only applied when there are fallbacks
+    pub timeout_ms: Option<u64>,
 }
 
-pub async fn playback_start_with_source_internal(
-    app: tauri::AppHandle,
-    spec: PlaybackSourceSpec,
-) -> Result<serde_json::Value, String> {
-    log_debug!(
-        "[bass] playback_start_with_source track={} type={} prefer_cache={:?}",
-        spec.track_id, spec.source_type, spec.prefer_cache
-    );
+#[derive(Clone, Deserialize)]
+pub struct SourceAlternative {
+    pub source_type: String,
+    pub source_value: String,
+    pub source_meta: Option<serde_json::Value>,
+    pub timeout_ms: Option<u64>,
+}
 
-    // Extract source hash for caching BEFORE doing any URL resolution
-    let source_hash = match spec.source_type.as_str() {
+// Time a source gets to start playing before the next alternative is tried
+fn default_source_timeout_ms(source_type: &str) -> u64 {
+    match source_type {
+        "torrent" => 45_000,
+        "youtube" => 20_000,
+        _ => 15_000,
+    }
+}
+
+// Cache/health identity of a source: video ID for YouTube, info hash for torrents
+fn source_hash_for(source_type: &str, source_value: &str) -> String {
+    match source_type {
         "youtube" => {
             // For YouTube, use the video ID as hash
-            if spec.source_value.len() == 11
-                && spec
-                    .source_value
+            if source_value.len() == 11
+                && source_value
                     .chars()
                     .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
             {
-                spec.source_value.clone()
-            } else if let Some(start) = spec.source_value.find("v=") {
-                if let Some(end) = spec.source_value[start + 2..].find('&') {
-                    spec.source_value[start + 2..start + 2 + end].to_string()
+                source_value.to_string()
+            } else if let Some(start) = source_value.find("v=") {
+                if let Some(end) = source_value[start + 2..].find('&') {
+                    source_value[start + 2..start + 2 + end].to_string()
                 } else {
-                    spec.source_value[start + 2..].to_string()
+                    source_value[start + 2..].to_string()
                 }
             } else {
-                spec.source_value.clone() // Assume it's already a video ID
+                source_value.to_string() // Assume it's already a video ID
             }
         }
         "torrent" => {
             // For torrents, extract info hash
-            if spec.source_value.starts_with("magnet:") {
-                if let Some(start) = spec.source_value.find("xt=urn:btih:") {
+            if source_value.starts_with("magnet:") {
+                if let Some(start) = source_value.find("xt=urn:btih:") {
                     let hash_start = start + 12;
-                    if let Some(end) = spec.source_value[hash_start..].find('&') {
-                        spec.source_value[hash_start..hash_start + end].to_lowercase()
+                    if let Some(end) = source_value[hash_start..].find('&') {
+                        source_value[hash_start..hash_start + end].to_lowercase()
                     } else {
-                        spec.source_value[hash_start..].to_lowercase()
+                        source_value[hash_start..].to_lowercase()
                     }
                 } else {
                     "unknown".to_string()
                 }
             } else {
-                spec.source_value.to_lowercase()
+                source_value.to_lowercase()
             }
         }
         _ => {
             // For other types, use the value directly as hash
-            spec.source_value.clone()
+            source_value.to_string()
         }
-    };
+    }
+}
+
+/// Start playback from the spec's primary source, falling back through its alternatives.
+/// Sources that failed repeatedly in recent sessions are tried last.
+pub async fn playback_start_with_source_internal(
+    app: tauri::AppHandle,
+    spec: PlaybackSourceSpec,
+) -> Result<serde_json::Value, String> {
+    if spec.fallbacks.is_empty() {
+        let key = crate::source_health::source_key(
+            &spec.source_type,
+            &source_hash_for(&spec.source_type, &spec.source_value),
+        );
+        let started = Instant::now();
+        let result = playback_start_single_source(app, spec).await;
+        record_source_outcome(&key, &result, started);
+        return result;
+    }
+
+    let mut chain = vec![SourceAlternative {
+        source_type: spec.source_type.clone(),
+        source_value: spec.source_value.clone(),
+        source_meta: spec.source_meta.clone(),
+        timeout_ms: spec.timeout_ms,
+    }];
+    chain.extend(spec.fallbacks.iter().cloned());
+    let hashes: Vec<String> = chain
+        .iter()
+        .map(|alt| source_hash_for(&alt.source_type, &alt.source_value))
+        .collect();
+    let keys: Vec<String> = chain
+        .iter()
+        .zip(&hashes)
+        .map(|(alt, hash)| crate::source_health::source_key(&alt.source_type, hash))
+        .collect();
+
+    let mut errors: Vec<serde_json::Value> = Vec::new();
+    for index in crate::source_health::attempt_order(&keys) {
+        let alt = &chain[index];
+        let hash = &hashes[index];
+        let attempt = PlaybackSourceSpec {
+            source_type: alt.source_type.clone(),
+            source_value: alt.source_value.clone(),
+            source_meta: alt.source_meta.clone(),
+            fallbacks: Vec::new(),
+            timeout_ms: None,
+            ..spec.clone()
+        };
+        let limit_ms = alt
+            .timeout_ms
+            .unwrap_or_else(|| default_source_timeout_ms(&alt.source_type));
+        log_info!(
+            "[bass] Trying source {}/{} for track {}: {} {} (timeout {}ms)",
+            index + 1,
+            chain.len(),
+            spec.track_id,
+            alt.source_type,
+            hash,
+            limit_ms
+        );
+
+        let started = Instant::now();
+        let result = match tokio::time::timeout(
+            Duration::from_millis(limit_ms),
+            playback_start_single_source(app.clone(), attempt),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(_) => {
+                // Stop whatever download the abandoned attempt had started
+                let file_index = alt
+                    .source_meta
+                    .as_ref()
+                    .and_then(|m| m.get("fileIndex"))
+                    .and_then(|v| v.as_u64())
+                    .map(|v| v as usize);
+                crate::downloads::request_cancel(&crate::cache::create_cache_filename_with_index(
+                    &spec.track_id,
+                    &alt.source_type,
+                    hash,
+                    file_index,
+                ));
+                Err(format!("Timed out after {}ms", limit_ms))
+            }
+        };
+        record_source_outcome(&keys[index], &result, started);
+
+        match result {
+            Ok(mut value) => {
+                let selected = serde_json::json!({
+                    "index": index,
+                    "sourceType": alt.source_type,
+                    "sourceHash": hash,
+                    "failed": errors,
+                });
+                let _ = app.emit(
+                    "playback:source:selected",
+                    serde_json::json!({
+                        "trackId": spec.track_id,
+                        "clientRequestId": spec.client_request_id,
+                        "source": selected,
+                    }),
+                );
+                if let Some(obj) = value.as_object_mut() {
+                    obj.insert("source".to_string(), selected);
+                }
+                return Ok(value);
+            }
+            Err(e) => {
+                log_warn!(
+                    "[bass] Source {} {} failed for track {}: {}",
+                    alt.source_type,
+                    hash,
+                    spec.track_id,
+                    e
+                );
+                let _ = app.emit(
+                    "playback:source:failed",
+                    serde_json::json!({
+                        "trackId": spec.track_id,
+                        "clientRequestId": spec.client_request_id,
+                        "index": index,
+                        "sourceType": alt.source_type,
+                        "sourceHash": hash,
+                        "message": e,
+                    }),
+                );
+                errors.push(serde_json::json!({
+                    "index": index,
+                    "sourceType": alt.source_type,
+                    "sourceHash": hash,
+                    "message": e,
+                }));
+            }
+        }
+    }
+
+    Err(format!(
+        "All {} sources failed for track {}",
+        chain.len(),
+        spec.track_id
+    ))
+}
+
+// Dedup acks say nothing about the source, so only real outcomes are recorded
+fn record_source_outcome(key: &str, result: &Result<serde_json::Value, String>, started: Instant) {
+    match result {
+        Ok(value) if value.get("dedup").is_some() => {}
+        Ok(_) => crate::source_health::record_success(key, started.elapsed().as_millis() as u64),
+        Err(e) => crate::source_health::record_failure(key, e),
+    }
+}
+
+async fn playback_start_single_source(
+    app: tauri::AppHandle,
+    spec: PlaybackSourceSpec,
+) -> Result<serde_json::Value, String> {
+    log_debug!(
+        "[bass] playback_start_with_source track={} type={} prefer_cache={:?}",
+        spec.track_id, spec.source_type, spec.prefer_cache
+    );
+
+    // Extract source hash for caching BEFORE doing any URL resolution
+    let source_hash = source_hash_for(&spec.source_type, &spec.source_value);
 
     log_debug!(
         "[bass] Generated source hash: {} type={}",
@@ -1249,50 +1456,33 @@ pub async fn playback_start_with_source_internal(
         resolved_source.url, resolved_source.format
     );
 
-    // If this is a torrent source, enforce verified-bytes gating before starting stream
+    // Torrent streams read through a reader that waits for missing pieces, so there is no
+    // buffering up front. Add the torrent from the full magnet first so its trackers are used;
+    // the stream itself only carries the info hash.
     if spec.source_type == "torrent" {
-        // We require at least a minimal buffer OR full file before playback.
-        // Threshold: 256 KiB or total, whichever is smaller.
-        let min_buffer: u64 = 256 * 1024;
-        let mut attempts = 0u32;
-        let max_wait_ms = 15_000; // hard cap 15s to avoid infinite wait
-        let poll_interval_ms = 500;
-        let mut waited_ms = 0u64;
-    log_info!("[bass] Applying torrent verified-bytes gating (min buffer {} bytes)", min_buffer);
-        if let Some(fi) = file_index {
-            // Attempt to poll backend torrent progress command via tauri invoke-like internal helper is not present here.
-            // We will directly call into engine through crate::torrents if available.
-            {
-                use crate::torrents::{get_engine, TorrentEngine};
-                let engine = get_engine();
-                loop {
-                    match engine.progress(&spec.source_value, fi as u32) {
-                        Ok(p) => {
-                            let verified = p.verified_bytes;
-                            let needed = std::cmp::min(min_buffer, p.total);
-                            log_debug!("[bass] Torrent gating progress: verified={} total={} need={} downSpeed={}B/s", verified, p.total, needed, p.down_speed);
-                            if verified >= needed || verified == p.total { break; }
-                        }
-                        Err(e) => {
-                            log_warn!("[bass] Torrent gating progress error: {}", e);
-                            // On error, break early rather than block playback forever
-                            break;
-                        }
-                    }
-                    if waited_ms >= max_wait_ms as u64 { log_warn!("[bass] Gating timeout reached, continuing anyway"); break; }
-                    attempts += 1;
-                    waited_ms += poll_interval_ms as u64;
-                    tokio::time::sleep(std::time::Duration::from_millis(poll_interval_ms)).await;
+        if let (Some(fi), Some(config)) = (file_index, crate::utils::get_path_config_clone()) {
+            let key = spec.source_value.clone();
+            let started = tokio::task::spawn_blocking(move || {
+                use crate::torrents::TorrentEngine;
+                crate::torrents::get_engine().start_download(&key, fi as u32, &config.torrents_dir)
+            })
+            .await
+            .unwrap_or_else(|e| Err(format!("join error: {e}")));
+            match started {
+                Ok(()) => crate::torrents::focus_playback(&spec.source_value, fi as u32),
+                Err(e) => {
+                    log_warn!("[bass] Failed to start torrent download before playback: {}", e)
                 }
             }
         } else {
-            log_warn!("[bass] No file_index provided for torrent source; cannot gate by verified bytes");
+            log_warn!("[bass] No file_index provided for torrent source; streaming file 0");
         }
     }
 
     // Use the new BASS download callback approach for both streaming and caching
     if spec.prefer_cache.unwrap_or(true) {
     log_info!("[bass] Starting playback with BASS download callback for caching");
+        let torrent_reader = open_torrent_reader(&resolved_source.url).await;
 
         // Use centralized initialization
         {
@@ -1330,7 +1520,7 @@ pub async fn playback_start_with_source_internal(
                 allow_caching,
                 resolved_source.url
             );
-            match create_bass_stream(lib, &resolved_source.url, allow_caching, cache_info, file_index) {
+            match torrent_reader.and_then(|reader| create_bass_stream(lib, &resolved_source.url, allow_caching, cache_info, file_index, reader)) {
                 Ok(v) => v,
                 Err(e) => {
                     // If stream creation fails and we were caching, cancel the associated download
@@ -1386,6 +1576,7 @@ pub async fn playback_start_with_source_internal(
         ) {
             if let Some(ref mut ds) = download_state_opt {
                 ds.total_bytes = Some(total);
+                ds.partial.lock().unwrap().set_total(total);
             }
         }
 
@@ -1465,6 +1656,7 @@ pub async fn playback_start_with_source_internal(
             state.current_track_id = Some(spec.track_id.clone());
             state.current_source_type = Some(spec.source_type.clone());
             state.current_source_hash = Some(source_hash.clone());
+            state.current_file_index = file_index;
             // Only set download state if caching/downloading is active
             state.download_file_state = download_state_opt;
         }
@@ -1699,6 +1891,31 @@ pub async fn playback_seek_internal(position: f64) -> Result<serde_json::Value,
         clamped
     };
 
+    // Seeking into bytes the cache callback already stored: play the part file from disk
+    // instead of letting BASS reconnect to the network
+    if let (Some(ds), Some(d)) = (st.download_file_state.as_ref(), st.duration) {
+        if partial_covers_position(ds, pos, d) {
+            return seek_from_partial(&mut st, pos);
+        }
+    }
+
+    // Torrent playback: have the engine fetch pieces from the new position first
+    if let (Some("torrent"), Some(hash), Some(idx), Some(d)) = (
+        st.current_source_type.as_deref(),
+        st.current_source_hash.clone(),
+        st.current_file_index,
+        st.duration,
+    ) {
+        if d > 0.0 {
+            let fraction = pos / d;
+            std::thread::spawn(move || {
+                if let Err(e) = crate::torrents::prioritize_position(&hash, idx as u32, fraction) {
+                    log_debug!("[bass] Torrent re-prioritization skipped: {}", e);
+                }
+            });
+        }
+    }
+
     // Try to get stream length and available data to check buffering status
     let _len_bytes = channel_get_length(lib, h, BASS_POS_BYTE);
 
@@ -1740,6 +1957,12 @@ pub async fn playback_seek_internal(position: f64) -> Result<serde_json::Value,
         }
     }
 
+    // BASS may reconnect at an offset the download callback can't observe; stop recording
+    // rather than store bytes at the wrong position (a later resume fills the gap)
+    if let Some(ds) = st.download_file_state.as_ref() {
+        ds.stream_pos.store(STREAM_POS_DETACHED, Ordering::SeqCst);
+    }
+
     // For streaming content, try seek but don't fail the entire operation if it doesn't work
     if channel_set_position(lib, h, bytes, BASS_POS_BYTE) == 0 {
         let error = bass_err(lib);
@@ -1798,6 +2021,136 @@ pub async fn playback_seek_internal(position: f64) -> Result<serde_json::Value,
     }))
 }
 
+// Whether the bytes needed to resume at `pos` are in the part file's contiguous prefix.
+// The file offset is estimated proportionally, with a margin for VBR drift and decoder read-ahead.
+fn partial_covers_position(ds: &DownloadFileState, pos: f64, duration: f64) -> bool {
+    const SEEK_DISK_MARGIN: u64 = 512 * 1024;
+    let partial = match ds.partial.lock() {
+        Ok(p) => p,
+        Err(_) => return false,
+    };
+    let meta = partial.meta();
+    match meta.total {
+        Some(total) if duration > 0.0 => {
+            let target = ((pos / duration) * total as f64) as u64;
+            meta.prefix_len() >= (target + SEEK_DISK_MARGIN).min(total)
+        }
+        _ => false,
+    }
+}
+
+// Replace the caching URL stream with a file stream over the part file, positioned at `pos`.
+// The part file is extended to full length so BASS sees the whole track, and the cache
+// downloader fetches the remaining gaps with Range requests into the same range map.
+fn seek_from_partial(st: &mut PlaybackState, pos: f64) -> Result<serde_json::Value, String> {
+    let (lib_ptr, old_handle) = match (st.bass_lib.as_ref(), st.stream) {
+        (Some(lib), Some(h)) => (lib as *const Library, h),
+        _ => {
+            return Ok(serde_json::json!({
+                "success": false,
+                "reason": "no_stream",
+                "message": "No active stream for seeking"
+            }))
+        }
+    };
+    let lib = unsafe { &*lib_ptr };
+    let (cache_path, reader, resume) = match st.download_file_state.as_ref() {
+        Some(ds) => {
+            let _ = crate::partial::persist(&ds.partial);
+            (
+                ds.cache_path.clone(),
+                // Waits for ranges the gap download hasn't written yet instead of reading holes
+                crate::partial::PartReader::new(ds.partial.clone())?,
+                (
+                    ds.track_id.clone(),
+                    ds.source_type.clone(),
+                    ds.source_hash.clone(),
+                    ds.file_index,
+                ),
+            )
+        }
+        None => return Err("No cache file for disk seek".to_string()),
+    };
+
+    let new_handle = stream_create(
+        lib,
+        StreamSource::User {
+            reader: Box::new(reader),
+            buffered: true,
+        },
+        BASS_STREAM_AUTOFREE,
+        None,
+        std::ptr::null_mut(),
+    );
+    if new_handle == 0 {
+        let error = bass_err(lib);
+        log_warn!("[bass] Failed to open part file for disk seek: {}", error);
+        return Ok(serde_json::json!({
+            "success": false,
+            "reason": "seek_error",
+            "message": error
+        }));
+    }
+
+    let settings = get_audio_settings();
+    let volume = if settings.muted { 0.0 } else { settings.volume };
+    let _ = channel_set_attribute(lib, new_handle, BASS_ATTRIB_VOL, volume);
+    let bytes = channel_seconds2bytes(lib, new_handle, pos);
+    let _ = channel_set_position(lib, new_handle, bytes, BASS_POS_BYTE);
+    if channel_play(lib, new_handle, 0) == 0 {
+        let error = bass_err(lib);
+        log_warn!("[bass] Failed to start part file stream: {}", error);
+        stream_free(lib, new_handle);
+        return Ok(serde_json::json!({
+            "success": false,
+            "reason": "seek_error",
+            "message": error
+        }));
+    }
+    channel_stop(lib, old_handle);
+    stream_free(lib, old_handle);
+
+    // The URL stream is gone, so nothing more arrives through the download callback
+    if let Some(ds) = st.download_file_state.as_ref() {
+        ds.stream_pos.store(STREAM_POS_DETACHED, Ordering::SeqCst);
+    }
+    let source_url = st.url.clone();
+    st.stream = Some(new_handle);
+    st.seek_offset = pos;
+    st.started_at = Some(Instant::now());
+    st.accumulated_paused = Duration::ZERO;
+    st.paused_at = None;
+    st.playing = true;
+
+    log_info!("[bass] Seek to {}s served from part file: {}", pos, cache_path.display());
+
+    let app = crate::APP_HANDLE.lock().unwrap().clone();
+    if let (Some(app), Some(url)) = (app, source_url) {
+        let (track_id, source_type, source_hash, file_index) = resume;
+        tokio::spawn(async move {
+            if let Err(e) = crate::cache::cache_download_and_store(
+                app,
+                track_id,
+                source_type,
+                source_hash,
+                url,
+                file_index,
+                Some(crate::downloads::PRIORITY_HIGH),
+            )
+            .await
+            {
+                log_warn!("[bass] Failed to start gap download after disk seek: {}", e);
+            }
+        });
+    }
+
+    Ok(serde_json::json!({
+        "success": true,
+        "position": pos,
+        "fromDisk": true
+    }))
+}
+
 #[derive(serde::Serialize)]
 pub struct PlaybackStatus {
     pub url: Option<String>,
@@ -2504,6 +2857,24 @@ pub async fn set_audio_settings_internal(
     }))
 }
 
+/// Initialize BASS the way playback would (configured device, sample rate, codec plugins) for
+/// background decoders such as tag extraction, so they never claim the device with other settings
+pub fn ensure_bass_ready() -> Result<(), String> {
+    let mut state = STATE.lock().unwrap();
+    ensure_bass_initialized(&mut state, false)
+}
+
+/// Push the current proxy and user agent to BASS; takes effect for the next network stream
+pub fn apply_network_settings() {
+    let state = STATE.lock().unwrap();
+    if state.bass_initialized {
+        if let Some(lib) = state.bass_lib.as_ref() {
+            crate::audio_settings::apply_user_agent(lib);
+            crate::proxy::apply_to_bass(lib);
+        }
+    }
+}
+
 pub async fn reinitialize_audio_internal(
     device_id: i32,
     sample_rate: u32,
@@ -2670,11 +3041,11 @@ pub async fn get_download_progress_internal() -> Result<serde_json::Value, Strin
     let state = STATE.lock().unwrap();
 
     if let (Some(handle), Some(lib)) = (state.stream, state.bass_lib.as_ref()) {
-        // Prefer .part file size if we're actively caching via callback
+        // Prefer bytes recorded in the part file's range map if we're actively caching via callback
         let mut downloaded_bytes: Option<u64> = None;
         if let Some(ref dfs) = state.download_file_state {
-            if let Ok(meta) = std::fs::metadata(&dfs.cache_path) {
-                downloaded_bytes = Some(meta.len());
+            if let Ok(partial) = dfs.partial.lock() {
+                downloaded_bytes = Some(partial.meta().covered());
             }
         }
 
@@ -342,6 +342,19 @@ pub fn list_script_sources(config: &PathConfig) -> Vec<serde_json::Value> {
     out
 }
 
+/// Torrent indexer spec of the enabled plugin matching `provider` (by name or provider id)
+pub fn find_indexer_spec(config: &PathConfig, provider: &str) -> Option<ManifestV1TorrentSpec> {
+    let enabled_map = load_enabled_map(config);
+    let fl = provider.to_lowercase();
+    load_plugins(config).into_iter()
+        .filter(|p| enabled_map.get(&p.name).copied().unwrap_or(true))
+        .filter(|p| p.name.to_lowercase() == fl || p.provider.as_deref().unwrap_or("").to_lowercase() == fl)
+        .find_map(|p| match p.manifest.kind {
+            ManifestV1Kind::TorrentIndexer { spec } => Some(spec),
+            _ => None,
+        })
+}
+
 /// Perform a search across all matching plugins (optionally filtered by provider/name)
 pub async fn search_plugins(config: &PathConfig, query: &str, provider: Option<&str>) -> Result<Vec<serde_json::Value>, String> {
     let mut out: Vec<serde_json::Value> = Vec::new();
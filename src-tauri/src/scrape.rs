@@ -16,11 +16,7 @@ struct FieldSpec {
 
 fn build_headers(custom: &Option<std::collections::HashMap<String, String>>, cookies: &Option<String>) -> Result<HeaderMap, String> {
     let mut headers = HeaderMap::new();
-    // Sensible defaults
-    headers.insert(
-        reqwest::header::USER_AGENT,
-        HeaderValue::from_static("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/118.0 Safari/537.36"),
-    );
+    // Sensible defaults; the User-Agent comes from the shared HTTP settings unless overridden
     headers.insert(
         reqwest::header::ACCEPT,
         HeaderValue::from_static("text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8"),
@@ -181,9 +177,41 @@ fn extract_field(doc: &Html, row: &scraper::ElementRef, field: &FieldSpec) -> Op
     None
 }
 
+// Largest .torrent accepted from an indexer; real ones are rarely over a few MiB
+const MAX_TORRENT_FILE: usize = 16 * 1024 * 1024;
+
+/// Download a .torrent from an indexer with its configured headers and the user's cookies
+pub async fn fetch_torrent_file(spec: Option<&crate::plugins::ManifestV1TorrentSpec>, url: &str, cookies: Option<String>) -> Result<Vec<u8>, String> {
+    let custom = spec.and_then(|s| s.network.headers.clone());
+    let mut headers = build_headers(&custom, &cookies)?;
+    headers.insert(
+        reqwest::header::ACCEPT,
+        HeaderValue::from_static("application/x-bittorrent,application/octet-stream;q=0.9,*/*;q=0.5"),
+    );
+    let client = crate::http::builder(crate::proxy::ProxySubsystem::Scrapers)
+        .default_headers(headers)
+        .build()
+        .map_err(|e| format!("http client: {e}"))?;
+    let (resp, _slot) = crate::http::send_held(client.get(url))
+        .await
+        .map_err(|e| format!("request failed: {e}"))?;
+    if !resp.status().is_success() {
+        return Err(format!("indexer returned HTTP {}", resp.status()));
+    }
+    let body = resp.bytes().await.map_err(|e| format!("read body: {e}"))?;
+    if body.len() > MAX_TORRENT_FILE {
+        return Err(format!("torrent file too large ({} bytes)", body.len()));
+    }
+    // Bencoded torrents are dictionaries; anything else is usually a login or captcha page
+    if body.first() != Some(&b'd') {
+        return Err("indexer did not return a .torrent file (login or cookies may be required)".to_string());
+    }
+    Ok(body.to_vec())
+}
+
 pub async fn search_torrent_indexer(spec: &crate::plugins::ManifestV1TorrentSpec, query: &str) -> Result<Vec<serde_json::Value>, String> {
     let headers = build_headers(&spec.network.headers, &None)?;
-    let client = reqwest::Client::builder()
+    let client = crate::http::builder(crate::proxy::ProxySubsystem::Scrapers)
         .default_headers(headers)
         .build()
         .map_err(|e| format!("http client: {e}"))?;
@@ -197,9 +225,7 @@ pub async fn search_torrent_indexer(spec: &crate::plugins::ManifestV1TorrentSpec
     for i in 0..limit {
         let url = replace_query_and_page(&spec.network.url_template, query, page);
 
-        let resp = client
-            .request(spec.network.method.parse().unwrap_or(reqwest::Method::GET), &url)
-            .send()
+        let (resp, _slot) = crate::http::send_held(client.request(spec.network.method.parse().unwrap_or(reqwest::Method::GET), &url))
             .await
             .map_err(|e| format!("request failed: {e}"))?;
         if !resp.status().is_success() {
@@ -0,0 +1,471 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::torrents::{get_engine, TorrentEngine};
+
+// How often seeding limits and the network condition are re-evaluated
+const POLICY_TICK: Duration = Duration::from_secs(60);
+// Wait before touching engine data so the cache copy and any readers have settled
+const HANDOFF_GRACE: Duration = Duration::from_secs(2);
+// Folder under the torrents dir holding hard links to cached torrents that are being seeded
+const SEEDS_DIR: &str = ".seeds";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SeedingMode {
+    /// Drop engine data as soon as the file is cached (previous behaviour)
+    Never,
+    /// Seed until the ratio or time limit is reached, whichever comes first
+    Limited,
+    /// Seed until the torrent is removed
+    Always,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SeedingSettings {
+    pub mode: SeedingMode,
+    // Uploaded / size of the cached files; 0 disables the ratio limit
+    pub ratio_limit: f64,
+    // Minutes of seeding; 0 disables the time limit
+    pub time_limit_mins: u64,
+    // Pause seeds while the frontend reports a metered connection
+    pub unmetered_only: bool,
+    // Once every file of a torrent is cached, seed from hard links to the cached copies instead
+    // of keeping the engine's download
+    pub seed_from_cache: bool,
+}
+
+impl Default for SeedingSettings {
+    fn default() -> Self {
+        Self {
+            mode: SeedingMode::Never,
+            ratio_limit: 1.0,
+            time_limit_mins: 24 * 60,
+            unmetered_only: true,
+            seed_from_cache: true,
+        }
+    }
+}
+
+impl SeedingSettings {
+    pub fn load() -> Self {
+        let path = match get_seeding_settings_path("seeding_settings.json") {
+            Ok(p) => p,
+            Err(_) => return Self::default(),
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                println!(
+                    "[seeding] Failed to parse seeding settings: {}, using defaults",
+                    e
+                );
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = get_seeding_settings_path("seeding_settings.json")?;
+        write_json(&path, self)?;
+        println!("[seeding] Saved seeding settings to {:?}", path);
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.ratio_limit.is_finite() || self.ratio_limit < 0.0 {
+            return Err("Ratio limit must be zero or positive".to_string());
+        }
+        if self.mode == SeedingMode::Limited && self.ratio_limit == 0.0 && self.time_limit_mins == 0
+        {
+            return Err("Limited seeding needs a ratio or time limit".to_string());
+        }
+        Ok(())
+    }
+
+    fn limit_reached(&self, ratio: f64, seeded_secs: u64) -> bool {
+        match self.mode {
+            SeedingMode::Never => true,
+            SeedingMode::Always => false,
+            SeedingMode::Limited => {
+                (self.ratio_limit > 0.0 && ratio >= self.ratio_limit)
+                    || (self.time_limit_mins > 0 && seeded_secs >= self.time_limit_mins * 60)
+            }
+        }
+    }
+}
+
+/// A torrent the engine keeps seeding after (some of) its files were cached
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeedEntry {
+    pub key: String,
+    pub info_hash: String,
+    // Cached copies of the torrent's files by file index
+    pub files: BTreeMap<u32, PathBuf>,
+    // Total size of the cached files; the ratio is the torrent's upload over this
+    pub size: u64,
+    pub started_at: u64,
+    // Seeding from hard links to the cache rather than the engine's own download
+    pub from_cache: bool,
+    // Upload carried over from earlier sessions; rqbit's counter restarts with the app
+    pub uploaded_base: u64,
+    pub last_uploaded: u64,
+    #[serde(skip)]
+    pub paused: bool,
+}
+
+impl SeedEntry {
+    fn uploaded(&self) -> u64 {
+        self.uploaded_base + self.last_uploaded
+    }
+
+    fn ratio(&self) -> f64 {
+        if self.size == 0 {
+            0.0
+        } else {
+            self.uploaded() as f64 / self.size as f64
+        }
+    }
+
+    fn record_uploaded(&mut self, current: u64) {
+        if current < self.last_uploaded {
+            self.uploaded_base += self.last_uploaded;
+        }
+        self.last_uploaded = current;
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn get_seeding_settings_path(name: &str) -> Result<PathBuf, String> {
+    // Tests run with the defaults (no seeding) whatever the user has saved
+    if cfg!(test) {
+        return Err("not persisted in tests".to_string());
+    }
+    let data_dir = dirs::data_dir().ok_or("Failed to get system data directory")?;
+
+    let mut path = data_dir;
+    path.push("com.freely.player");
+    path.push(name);
+    Ok(path)
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize seeding data: {}", e))?;
+    std::fs::write(path, content).map_err(|e| format!("Failed to write seeding data: {}", e))
+}
+
+static SEEDING_SETTINGS: Lazy<Mutex<SeedingSettings>> =
+    Lazy::new(|| Mutex::new(SeedingSettings::load()));
+
+// Seeds keyed by info hash, persisted so limits survive restarts
+static SEEDS: Lazy<Mutex<HashMap<String, SeedEntry>>> = Lazy::new(|| Mutex::new(load_seeds()));
+
+// Reported by the frontend (Network Information API); assume unmetered until told otherwise
+static METERED: AtomicBool = AtomicBool::new(false);
+
+fn load_seeds() -> HashMap<String, SeedEntry> {
+    get_seeding_settings_path("seeding_state.json")
+        .ok()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_seeds(seeds: &HashMap<String, SeedEntry>) {
+    let result =
+        get_seeding_settings_path("seeding_state.json").and_then(|p| write_json(&p, seeds));
+    if let Err(e) = result {
+        println!("[seeding] {}", e);
+    }
+}
+
+pub fn get_seeding_settings() -> SeedingSettings {
+    SEEDING_SETTINGS.lock().unwrap().clone()
+}
+
+pub fn update_seeding_settings<F>(updater: F) -> Result<SeedingSettings, String>
+where
+    F: FnOnce(&mut SeedingSettings),
+{
+    let mut settings = SEEDING_SETTINGS.lock().unwrap();
+    let mut updated = settings.clone();
+    updater(&mut updated);
+    updated.validate()?;
+    updated.save()?;
+    *settings = updated.clone();
+    Ok(updated)
+}
+
+/// Seeding state for the torrent with `info_hash`, if it is being seeded
+pub fn seed_for(info_hash: &str) -> Option<SeedEntry> {
+    SEEDS
+        .lock()
+        .unwrap()
+        .get(&info_hash.to_lowercase())
+        .cloned()
+}
+
+/// Called once a torrent file has been copied into the cache: either hand the torrent over to
+/// seeding or remove the engine's data right away, depending on the policy
+pub fn on_torrent_cached(
+    key: String,
+    file_index: u32,
+    engine_path: PathBuf,
+    final_path: PathBuf,
+    expected_size: Option<u64>,
+) {
+    let settings = get_seeding_settings();
+    if settings.mode == SeedingMode::Never {
+        crate::cache::schedule_torrent_cleanup(key, engine_path, final_path, expected_size);
+        return;
+    }
+    // The engine blocks on its own runtime; keep it off the async cache task
+    std::thread::spawn(move || {
+        std::thread::sleep(HANDOFF_GRACE);
+        let engine = get_engine();
+        let info_hash = match crate::torrents::info_hash_from_key(&key).or_else(|| {
+            engine
+                .torrent_details(&key)
+                .ok()
+                .map(|d| d.summary.info_hash)
+        }) {
+            Some(ih) => ih.to_lowercase(),
+            None => {
+                println!("[seeding] Unknown info hash for {}, not seeding it", key);
+                return;
+            }
+        };
+        let size = expected_size
+            .or_else(|| std::fs::metadata(&final_path).ok().map(|m| m.len()))
+            .unwrap_or(0);
+        let entry = {
+            let mut seeds = SEEDS.lock().unwrap();
+            let entry = seeds.entry(info_hash.clone()).or_insert_with(|| SeedEntry {
+                key: key.clone(),
+                info_hash: info_hash.clone(),
+                files: BTreeMap::new(),
+                size: 0,
+                started_at: now_secs(),
+                from_cache: false,
+                uploaded_base: 0,
+                last_uploaded: 0,
+                paused: false,
+            });
+            if entry.files.insert(file_index, final_path).is_none() {
+                entry.size += size;
+            }
+            let entry = entry.clone();
+            save_seeds(&seeds);
+            entry
+        };
+        println!(
+            "[seeding] Seeding {} ({} cached file(s), {} bytes)",
+            info_hash,
+            entry.files.len(),
+            entry.size
+        );
+        if !settings.seed_from_cache || entry.from_cache {
+            return;
+        }
+        match relink_to_cache(&entry) {
+            Ok(()) => {
+                println!("[seeding] Seeding {} from the cache", info_hash);
+                if let Some(slot) = SEEDS.lock().unwrap().get_mut(&info_hash) {
+                    slot.from_cache = true;
+                }
+            }
+            Err(e) => println!(
+                "[seeding] Seeding {} from engine data instead of the cache: {}",
+                info_hash, e
+            ),
+        }
+    });
+}
+
+// Re-add the torrent pointed at hard links of the cached files, then drop the engine's copy.
+// Only done once every file of the torrent is cached with its original bytes (not transcoded)
+// on the same volume: all pieces then verify on re-add, so the engine never writes through a
+// link into the cache.
+fn relink_to_cache(entry: &SeedEntry) -> Result<(), String> {
+    let config = crate::utils::get_path_config_clone().ok_or("PathConfig not initialized")?;
+    let engine = get_engine();
+    let details = engine.torrent_details(&entry.key)?;
+    let missing = details
+        .files
+        .iter()
+        .filter(|f| !entry.files.contains_key(&f.index))
+        .count();
+    if missing > 0 {
+        return Err(format!("{} file(s) of the torrent are not cached", missing));
+    }
+    let seed_dir = config.torrents_dir.join(SEEDS_DIR).join(&entry.info_hash);
+    let mut links = Vec::new();
+    for file in &details.files {
+        let cached = &entry.files[&file.index];
+        let cached_len = std::fs::metadata(cached)
+            .map_err(|e| format!("cached file missing: {}", e))?
+            .len();
+        if cached_len != file.length {
+            return Err(format!(
+                "cached copy of file {} differs from the torrent",
+                file.index
+            ));
+        }
+        // Keep the torrent's relative paths so the re-added torrent finds its files
+        let engine_path = engine.file_path(&entry.key, file.index, &config.torrents_dir)?;
+        let rel = engine_path
+            .strip_prefix(&config.torrents_dir)
+            .map_err(|_| "engine data is outside the torrents folder".to_string())?;
+        links.push((cached.clone(), seed_dir.join(rel)));
+    }
+    let _ = std::fs::remove_dir_all(&seed_dir);
+    for (cached, link) in &links {
+        let linked = link
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::hard_link(cached, link));
+        if let Err(e) = linked {
+            let _ = std::fs::remove_dir_all(&seed_dir);
+            return Err(format!("hard link: {}", e));
+        }
+    }
+
+    engine.remove(&entry.key, true)?;
+    let indices: Vec<u32> = entry.files.keys().copied().collect();
+    if let Err(e) = engine.start_download_files(&entry.key, &indices, &seed_dir) {
+        let _ = std::fs::remove_dir_all(&seed_dir);
+        return Err(format!("re-add from cache: {}", e));
+    }
+    Ok(())
+}
+
+fn stop_seed(entry: &SeedEntry, reason: &str) {
+    match get_engine().remove(&entry.key, true) {
+        Ok(()) => println!(
+            "[seeding] Stopped seeding {} ({}, ratio {:.2})",
+            entry.info_hash,
+            reason,
+            entry.ratio()
+        ),
+        Err(e) => println!("[seeding] Failed to stop seeding {}: {}", entry.key, e),
+    }
+    crate::torrents::unregister_download(&entry.key);
+    if entry.from_cache {
+        if let Some(config) = crate::utils::get_path_config_clone() {
+            let _ =
+                std::fs::remove_dir_all(config.torrents_dir.join(SEEDS_DIR).join(&entry.info_hash));
+        }
+    }
+}
+
+// One pass over all seeds: update upload totals, enforce limits and the network condition
+fn evaluate() {
+    let settings = get_seeding_settings();
+    let metered = METERED.load(Ordering::SeqCst);
+    let snapshot: Vec<SeedEntry> = SEEDS.lock().unwrap().values().cloned().collect();
+    let engine = get_engine();
+    let mut finished = Vec::new();
+    let mut updated = Vec::new();
+    for mut entry in snapshot {
+        // Upload is reported for the whole torrent, whichever file is asked about
+        let first = entry.files.keys().next().copied().unwrap_or(0);
+        if let Ok(p) = engine.progress(&entry.key, first) {
+            entry.record_uploaded(p.uploaded);
+        }
+        let seeded = now_secs().saturating_sub(entry.started_at);
+        // Removing the torrent would also stop files of it that are still downloading
+        let pending = crate::downloads::torrent_files_pending(&entry.info_hash);
+        if pending == 0 && settings.limit_reached(entry.ratio(), seeded) {
+            stop_seed(&entry, "limit reached");
+            finished.push(entry.info_hash.clone());
+            continue;
+        }
+        let hold = settings.unmetered_only && metered;
+        if hold != entry.paused {
+            let res = if hold {
+                engine.pause(&entry.key)
+            } else {
+                engine.resume(&entry.key)
+            };
+            match res {
+                Ok(()) => entry.paused = hold,
+                Err(e) => println!("[seeding] Could not update {}: {}", entry.key, e),
+            }
+        }
+        updated.push(entry);
+    }
+    let mut seeds = SEEDS.lock().unwrap();
+    for key in finished {
+        seeds.remove(&key);
+    }
+    for entry in updated {
+        // Skip entries removed while the engine was being queried
+        if let Some(slot) = seeds.get_mut(&entry.info_hash) {
+            *slot = entry;
+        }
+    }
+    save_seeds(&seeds);
+}
+
+/// Enforce the seeding policy periodically
+pub fn start_seeding_loop() {
+    tauri::async_runtime::spawn(async {
+        loop {
+            tokio::time::sleep(POLICY_TICK).await;
+            if SEEDS.lock().unwrap().is_empty() {
+                continue;
+            }
+            let _ = tokio::task::spawn_blocking(evaluate).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn seeding_get_settings() -> Result<serde_json::Value, String> {
+    serde_json::to_value(get_seeding_settings())
+        .map_err(|e| format!("Failed to serialize seeding settings: {}", e))
+}
+
+#[tauri::command]
+pub async fn seeding_set_settings(
+    settings: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let incoming: SeedingSettings =
+        serde_json::from_value(settings).map_err(|e| format!("Invalid seeding settings: {}", e))?;
+    let updated = update_seeding_settings(|s| *s = incoming)?;
+    // Apply new limits (or "never") to existing seeds right away
+    let _ = tokio::task::spawn_blocking(evaluate).await;
+    serde_json::to_value(updated)
+        .map_err(|e| format!("Failed to serialize seeding settings: {}", e))
+}
+
+/// Let the frontend report whether the current connection is metered
+#[tauri::command]
+pub async fn seeding_set_metered(metered: bool) -> Result<(), String> {
+    if METERED.swap(metered, Ordering::SeqCst) != metered {
+        println!(
+            "[seeding] Network is now {}",
+            if metered { "metered" } else { "unmetered" }
+        );
+        let _ = tokio::task::spawn_blocking(evaluate).await;
+    }
+    Ok(())
+}
@@ -0,0 +1,188 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// A source that failed this many times in a row is tried after the healthy ones
+const SUSPECT_AFTER_FAILURES: u32 = 2;
+// ...until this long has passed since its last failure, when it gets a fresh chance
+const SUSPECT_COOLDOWN_SECS: u64 = 6 * 60 * 60;
+// Records nobody touched for this long are dropped on save
+const FORGET_AFTER_SECS: u64 = 90 * 24 * 60 * 60;
+
+/// Outcome history of one playback source, keyed by "<source_type>:<source_hash>"
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SourceHealth {
+    pub successes: u32,
+    pub failures: u32,
+    pub consecutive_failures: u32,
+    pub last_success_at: Option<u64>,
+    pub last_failure_at: Option<u64>,
+    pub last_error: Option<String>,
+    // Rolling average time from request to playback start
+    pub avg_start_ms: Option<u64>,
+}
+
+impl SourceHealth {
+    /// Success ratio with a neutral prior, so unknown sources start at 0.5
+    pub fn score(&self) -> f64 {
+        (self.successes as f64 + 1.0) / ((self.successes + self.failures) as f64 + 2.0)
+    }
+
+    /// Recently failing repeatedly; worth trying only after the alternatives
+    pub fn is_suspect(&self) -> bool {
+        self.consecutive_failures >= SUSPECT_AFTER_FAILURES
+            && self
+                .last_failure_at
+                .map(|t| now_secs().saturating_sub(t) < SUSPECT_COOLDOWN_SECS)
+                .unwrap_or(false)
+    }
+
+    fn last_seen(&self) -> u64 {
+        self.last_success_at
+            .unwrap_or(0)
+            .max(self.last_failure_at.unwrap_or(0))
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct HealthTable {
+    sources: HashMap<String, SourceHealth>,
+}
+
+impl HealthTable {
+    fn load() -> Self {
+        let path = match get_health_path() {
+            Ok(p) => p,
+            Err(_) => return Self::default(),
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                println!(
+                    "[sources] Failed to parse source health, starting fresh: {}",
+                    e
+                );
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    fn save(&mut self) -> Result<(), String> {
+        let cutoff = now_secs().saturating_sub(FORGET_AFTER_SECS);
+        self.sources.retain(|_, h| h.last_seen() >= cutoff);
+
+        let path = get_health_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+        }
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize source health: {}", e))?;
+        std::fs::write(&path, content).map_err(|e| format!("Failed to write source health: {}", e))
+    }
+}
+
+fn get_health_path() -> Result<PathBuf, String> {
+    let data_dir = dirs::data_dir().ok_or("Failed to get system data directory")?;
+
+    let mut path = data_dir;
+    path.push("com.freely.player");
+    path.push("source_health.json");
+    Ok(path)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+static HEALTH: Lazy<Mutex<HealthTable>> = Lazy::new(|| Mutex::new(HealthTable::load()));
+
+pub fn source_key(source_type: &str, source_hash: &str) -> String {
+    format!("{}:{}", source_type, source_hash)
+}
+
+fn update_health<F>(key: &str, updater: F)
+where
+    F: FnOnce(&mut SourceHealth),
+{
+    let mut table = HEALTH.lock().unwrap();
+    updater(table.sources.entry(key.to_string()).or_default());
+    if let Err(e) = table.save() {
+        println!("[sources] {}", e);
+    }
+}
+
+pub fn record_success(key: &str, start_ms: u64) {
+    update_health(key, |h| {
+        h.successes += 1;
+        h.consecutive_failures = 0;
+        h.last_success_at = Some(now_secs());
+        h.avg_start_ms = Some(match h.avg_start_ms {
+            Some(avg) => (avg * 3 + start_ms) / 4,
+            None => start_ms,
+        });
+    });
+}
+
+pub fn record_failure(key: &str, error: &str) {
+    update_health(key, |h| {
+        h.failures += 1;
+        h.consecutive_failures += 1;
+        h.last_failure_at = Some(now_secs());
+        h.last_error = Some(error.to_string());
+    });
+}
+
+/// Order for trying `keys`: the given order, except suspect sources move to the back.
+/// Returns indices into `keys`.
+pub fn attempt_order(keys: &[String]) -> Vec<usize> {
+    let table = HEALTH.lock().unwrap();
+    let mut order: Vec<usize> = (0..keys.len()).collect();
+    order.sort_by_key(|&i| {
+        table
+            .sources
+            .get(&keys[i])
+            .map(|h| h.is_suspect())
+            .unwrap_or(false)
+    });
+    order
+}
+
+#[tauri::command]
+pub async fn sources_get_health() -> Result<serde_json::Value, String> {
+    let table = HEALTH.lock().unwrap();
+    let sources: serde_json::Map<String, serde_json::Value> = table
+        .sources
+        .iter()
+        .map(|(key, h)| {
+            let mut value = serde_json::to_value(h).unwrap_or_default();
+            if let Some(obj) = value.as_object_mut() {
+                obj.insert("score".to_string(), serde_json::json!(h.score()));
+                obj.insert("suspect".to_string(), serde_json::json!(h.is_suspect()));
+            }
+            (key.clone(), value)
+        })
+        .collect();
+    Ok(serde_json::Value::Object(sources))
+}
+
+#[tauri::command]
+pub async fn sources_reset_health(key: Option<String>) -> Result<serde_json::Value, String> {
+    let mut table = HEALTH.lock().unwrap();
+    match key {
+        Some(key) => {
+            table.sources.remove(&key);
+        }
+        None => table.sources.clear(),
+    }
+    table.save()?;
+    Ok(serde_json::json!({ "success": true }))
+}
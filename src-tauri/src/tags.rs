@@ -0,0 +1,614 @@
+use crate::bass::{
+    channel_get_tags, ensure_bass_loaded, probe_duration_bass, stream_create, stream_free,
+    StreamSource, TagFlacPicture, BASS_STREAM_DECODE, BASS_TAG_APE, BASS_TAG_FLAC_PICTURE,
+    BASS_TAG_ID3V2, BASS_TAG_MP4, BASS_TAG_OGG,
+};
+use base64::Engine;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use tokio::sync::Semaphore;
+
+/// Sub-directory of the audio cache holding extracted cover art
+pub const ART_DIR_NAME: &str = "art";
+// Longest edge of stored cover art thumbnails
+const THUMBNAIL_MAX_EDGE: u32 = 300;
+// APIC picture type for the front cover
+const PICTURE_FRONT_COVER: u8 = 3;
+
+// Extraction opens files through BASS and may run ffmpeg; keep it off the playback's toes
+static EXTRACT_SLOTS: Lazy<Semaphore> = Lazy::new(|| Semaphore::new(1));
+// Cache keys currently queued or running
+static PENDING: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Embedded tags of a cached file, stored on its index entry
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrackMetadata {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub genre: Option<String>,
+    pub date: Option<String>,
+    pub track_number: Option<u32>,
+    pub track_total: Option<u32>,
+    pub disc_number: Option<u32>,
+    pub disc_total: Option<u32>,
+    pub duration: Option<f64>,
+    pub musicbrainz_recording_id: Option<String>,
+    pub musicbrainz_release_id: Option<String>,
+    pub musicbrainz_artist_id: Option<String>,
+    /// Thumbnail file name inside the cache's art directory
+    pub cover_art: Option<String>,
+}
+
+impl TrackMetadata {
+    fn set_text(slot: &mut Option<String>, value: &str) {
+        let value = value.trim();
+        if slot.is_none() && !value.is_empty() {
+            *slot = Some(value.to_string());
+        }
+    }
+
+    // "3" or "3/12"
+    fn set_number_pair(number: &mut Option<u32>, total: &mut Option<u32>, value: &str) {
+        let mut parts = value.trim().splitn(2, '/');
+        if number.is_none() {
+            *number = parts
+                .next()
+                .and_then(|n| n.trim().parse().ok())
+                .filter(|n| *n > 0);
+        }
+        if total.is_none() {
+            *total = parts
+                .next()
+                .and_then(|n| n.trim().parse().ok())
+                .filter(|n| *n > 0);
+        }
+    }
+
+    /// Apply a Vorbis/APE/MP4 style `key=value` field. Keys are matched loosely
+    /// ("ALBUMARTIST", "Album Artist", "album_artist" are the same field).
+    fn apply_field(&mut self, key: &str, value: &str) {
+        let key: String = key
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        match key.as_str() {
+            "title" | "nam" => Self::set_text(&mut self.title, value),
+            "artist" | "art" => Self::set_text(&mut self.artist, value),
+            "album" | "alb" => Self::set_text(&mut self.album, value),
+            "albumartist" | "aart" => Self::set_text(&mut self.album_artist, value),
+            "genre" | "gen" => Self::set_text(&mut self.genre, value),
+            "date" | "year" | "day" => Self::set_text(&mut self.date, value),
+            "tracknumber" | "track" | "trkn" => {
+                Self::set_number_pair(&mut self.track_number, &mut self.track_total, value)
+            }
+            "tracktotal" | "totaltracks" => {
+                Self::set_number_pair(&mut self.track_total, &mut None, value)
+            }
+            "discnumber" | "disc" | "disk" => {
+                Self::set_number_pair(&mut self.disc_number, &mut self.disc_total, value)
+            }
+            "disctotal" | "totaldiscs" => {
+                Self::set_number_pair(&mut self.disc_total, &mut None, value)
+            }
+            "musicbrainztrackid" | "musicbrainzrecordingid" => {
+                Self::set_text(&mut self.musicbrainz_recording_id, value)
+            }
+            "musicbrainzalbumid" | "musicbrainzreleaseid" => {
+                Self::set_text(&mut self.musicbrainz_release_id, value)
+            }
+            "musicbrainzartistid" => Self::set_text(&mut self.musicbrainz_artist_id, value),
+            _ => {}
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.artist.is_none()
+            && self.album.is_none()
+            && self.cover_art.is_none()
+    }
+}
+
+// An embedded picture; `cover` marks the APIC "front cover" type
+struct Picture {
+    cover: bool,
+    data: Vec<u8>,
+}
+
+fn prefer_picture(current: &mut Option<Picture>, candidate: Picture) {
+    if candidate.data.is_empty() {
+        return;
+    }
+    let replace = match current {
+        None => true,
+        Some(existing) => candidate.cover && !existing.cover,
+    };
+    if replace {
+        *current = Some(candidate);
+    }
+}
+
+// ---- ID3v2 ----
+
+fn syncsafe(b: &[u8]) -> usize {
+    b.iter()
+        .fold(0usize, |acc, x| (acc << 7) | (*x as usize & 0x7f))
+}
+
+fn big_endian(b: &[u8]) -> usize {
+    b.iter().fold(0usize, |acc, x| (acc << 8) | *x as usize)
+}
+
+// Undo ID3 unsynchronisation (0xFF 0x00 -> 0xFF)
+fn unsynchronise(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        out.push(data[i]);
+        if data[i] == 0xff && data.get(i + 1) == Some(&0) {
+            i += 1;
+        }
+        i += 1;
+    }
+    out
+}
+
+// Split `data` at the first terminator for the given text encoding
+fn split_terminated(data: &[u8], encoding: u8) -> (&[u8], &[u8]) {
+    if encoding == 1 || encoding == 2 {
+        let mut i = 0;
+        while i + 1 < data.len() {
+            if data[i] == 0 && data[i + 1] == 0 {
+                return (&data[..i], &data[i + 2..]);
+            }
+            i += 2;
+        }
+        (data, &[])
+    } else {
+        match data.iter().position(|b| *b == 0) {
+            Some(i) => (&data[..i], &data[i + 1..]),
+            None => (data, &[]),
+        }
+    }
+}
+
+fn decode_id3_text(data: &[u8], encoding: u8) -> String {
+    let text = match encoding {
+        1 | 2 => {
+            let (mut big, body) = match data {
+                [0xfe, 0xff, rest @ ..] => (true, rest),
+                [0xff, 0xfe, rest @ ..] => (false, rest),
+                _ => (false, data),
+            };
+            if encoding == 2 {
+                big = true;
+            }
+            let units: Vec<u16> = body
+                .chunks_exact(2)
+                .map(|c| {
+                    if big {
+                        u16::from_be_bytes([c[0], c[1]])
+                    } else {
+                        u16::from_le_bytes([c[0], c[1]])
+                    }
+                })
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        3 => String::from_utf8_lossy(data).into_owned(),
+        _ => data.iter().map(|b| *b as char).collect(),
+    };
+    // Multiple values are NUL separated in v2.4
+    text.split('\0')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn apply_id3_frame(meta: &mut TrackMetadata, picture: &mut Option<Picture>, id: &str, data: &[u8]) {
+    if data.is_empty() {
+        return;
+    }
+    match id {
+        "TXXX" => {
+            let encoding = data[0];
+            let (desc, value) = split_terminated(&data[1..], encoding);
+            let desc = decode_id3_text(desc, encoding);
+            meta.apply_field(&desc, &decode_id3_text(value, encoding));
+        }
+        "UFID" => {
+            let (owner, id) = split_terminated(data, 0);
+            if owner == b"http://musicbrainz.org" {
+                TrackMetadata::set_text(
+                    &mut meta.musicbrainz_recording_id,
+                    &String::from_utf8_lossy(id),
+                );
+            }
+        }
+        "APIC" => {
+            let encoding = data[0];
+            let (_mime, rest) = split_terminated(&data[1..], 0);
+            if let Some((kind, rest)) = rest.split_first() {
+                let (_desc, image) = split_terminated(rest, encoding);
+                prefer_picture(
+                    picture,
+                    Picture {
+                        cover: *kind == PICTURE_FRONT_COVER,
+                        data: image.to_vec(),
+                    },
+                );
+            }
+        }
+        "PIC" => {
+            // v2.2: encoding, 3-byte image format, type, description, data
+            if data.len() > 5 {
+                let encoding = data[0];
+                let kind = data[4];
+                let (_desc, image) = split_terminated(&data[5..], encoding);
+                prefer_picture(
+                    picture,
+                    Picture {
+                        cover: kind == PICTURE_FRONT_COVER,
+                        data: image.to_vec(),
+                    },
+                );
+            }
+        }
+        _ if id.starts_with('T') => {
+            let value = decode_id3_text(&data[1..], data[0]);
+            let field = match id {
+                "TIT2" | "TT2" => "title",
+                "TPE1" | "TP1" => "artist",
+                "TALB" | "TAL" => "album",
+                "TPE2" | "TP2" => "albumartist",
+                "TCON" | "TCO" => "genre",
+                "TDRC" | "TYER" | "TYE" => "date",
+                "TRCK" | "TRK" => "tracknumber",
+                "TPOS" | "TPA" => "discnumber",
+                _ => return,
+            };
+            meta.apply_field(field, &value);
+        }
+        _ => {}
+    }
+}
+
+/// Parse a raw ID3v2 block as returned by BASS_TAG_ID3V2 (header included)
+fn parse_id3v2(tag: &[u8], meta: &mut TrackMetadata, picture: &mut Option<Picture>) {
+    if tag.len() < 10 || &tag[..3] != b"ID3" {
+        return;
+    }
+    let version = tag[3];
+    let flags = tag[5];
+    let end = (10 + syncsafe(&tag[6..10])).min(tag.len());
+    let body = if flags & 0x80 != 0 && version < 4 {
+        unsynchronise(&tag[10..end])
+    } else {
+        tag[10..end].to_vec()
+    };
+
+    let mut pos = 0usize;
+    if flags & 0x40 != 0 && version >= 3 && body.len() >= 4 {
+        pos = if version == 4 {
+            syncsafe(&body[..4])
+        } else {
+            4 + big_endian(&body[..4])
+        };
+    }
+
+    let (id_len, header_len) = if version == 2 { (3, 6) } else { (4, 10) };
+    while pos + header_len <= body.len() {
+        let header = &body[pos..pos + header_len];
+        if header[0] == 0 {
+            break; // padding
+        }
+        let id = String::from_utf8_lossy(&header[..id_len]).into_owned();
+        let size = match version {
+            2 => big_endian(&header[3..6]),
+            3 => big_endian(&header[4..8]),
+            _ => syncsafe(&header[4..8]),
+        };
+        let start = pos + header_len;
+        let stop = start.saturating_add(size).min(body.len());
+        pos = start.saturating_add(size);
+
+        let mut data = body[start..stop].to_vec();
+        if version == 4 {
+            let format_flags = header[9];
+            if format_flags & 0x02 != 0 {
+                data = unsynchronise(&data);
+            }
+            // Data length indicator precedes the frame body
+            if format_flags & 0x01 != 0 && data.len() >= 4 {
+                data.drain(..4);
+            }
+            // Compressed or encrypted frames are not worth the trouble
+            if format_flags & 0x0c != 0 {
+                continue;
+            }
+        } else if version == 3 && header[9] & 0xc0 != 0 {
+            continue;
+        }
+        apply_id3_frame(meta, picture, &id, &data);
+    }
+}
+
+// ---- Vorbis comments / APE / MP4 ----
+
+// FLAC METADATA_BLOCK_PICTURE payload (also used base64-encoded in Vorbis comments)
+fn parse_flac_picture_block(block: &[u8]) -> Option<Picture> {
+    let read_u32 = |pos: usize| -> Option<usize> { block.get(pos..pos + 4).map(big_endian) };
+    let kind = read_u32(0)?;
+    let mut pos = 4;
+    pos += 4 + read_u32(pos)?; // mime
+    pos += 4 + read_u32(pos)?; // description
+    pos += 16; // width, height, depth, colors
+    let len = read_u32(pos)?;
+    let data = block.get(pos + 4..pos + 4 + len)?;
+    Some(Picture {
+        cover: kind == PICTURE_FRONT_COVER as usize,
+        data: data.to_vec(),
+    })
+}
+
+/// Read a BASS "series of null-terminated strings" tag block
+unsafe fn read_string_list(ptr: *const c_char) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut cur = ptr;
+    while !cur.is_null() && *cur != 0 {
+        let s = CStr::from_ptr(cur);
+        out.push(s.to_string_lossy().into_owned());
+        cur = cur.add(s.to_bytes().len() + 1);
+    }
+    out
+}
+
+fn apply_comment_list(items: &[String], meta: &mut TrackMetadata, picture: &mut Option<Picture>) {
+    for item in items {
+        let (key, value) = match item.split_once('=') {
+            Some(kv) => kv,
+            None => continue,
+        };
+        if key.eq_ignore_ascii_case("METADATA_BLOCK_PICTURE") {
+            if let Ok(block) = base64::engine::general_purpose::STANDARD.decode(value.trim()) {
+                if let Some(p) = parse_flac_picture_block(&block) {
+                    prefer_picture(picture, p);
+                }
+            }
+        } else {
+            meta.apply_field(key, value);
+        }
+    }
+}
+
+// ---- Extraction ----
+
+/// Read embedded tags (and the preferred embedded picture) from an audio file via BASS
+fn read_tags(
+    lib: &libloading::Library,
+    path: &Path,
+) -> Result<(TrackMetadata, Option<Vec<u8>>), String> {
+    let c_path =
+        CString::new(path.to_string_lossy().as_bytes()).map_err(|_| "invalid path".to_string())?;
+    let handle = stream_create(
+        lib,
+        StreamSource::File(&c_path),
+        BASS_STREAM_DECODE,
+        None,
+        std::ptr::null_mut(),
+    );
+    if handle == 0 {
+        return Err(format!(
+            "BASS could not open file: {}",
+            crate::bass::bass_err(lib)
+        ));
+    }
+
+    let mut meta = TrackMetadata::default();
+    let mut picture: Option<Picture> = None;
+    unsafe {
+        let id3 = channel_get_tags(lib, handle, BASS_TAG_ID3V2) as *const u8;
+        if !id3.is_null() {
+            let header = std::slice::from_raw_parts(id3, 10);
+            if &header[..3] == b"ID3" {
+                let len = 10 + syncsafe(&header[6..10]);
+                parse_id3v2(
+                    std::slice::from_raw_parts(id3, len),
+                    &mut meta,
+                    &mut picture,
+                );
+            }
+        }
+        for tag_type in [BASS_TAG_OGG, BASS_TAG_APE, BASS_TAG_MP4] {
+            let ptr = channel_get_tags(lib, handle, tag_type);
+            if !ptr.is_null() {
+                apply_comment_list(&read_string_list(ptr), &mut meta, &mut picture);
+            }
+        }
+        for index in 0..8 {
+            let ptr = channel_get_tags(lib, handle, BASS_TAG_FLAC_PICTURE + index)
+                as *const TagFlacPicture;
+            if ptr.is_null() {
+                break;
+            }
+            let pic = &*ptr;
+            if !pic.data.is_null() && pic.length > 0 {
+                let data =
+                    std::slice::from_raw_parts(pic.data as *const u8, pic.length as usize).to_vec();
+                prefer_picture(
+                    &mut picture,
+                    Picture {
+                        cover: pic.apic == PICTURE_FRONT_COVER as u32,
+                        data,
+                    },
+                );
+            }
+        }
+    }
+    meta.duration = probe_duration_bass(lib, handle);
+    let _ = stream_free(lib, handle);
+    Ok((meta, picture.map(|p| p.data)))
+}
+
+// ffmpeg invocation writing its result to stdout
+fn run_ffmpeg(configure: impl FnOnce(&mut Command)) -> Option<Vec<u8>> {
+    if !crate::transcode::ffmpeg_available() {
+        return None;
+    }
+    let mut cmd = Command::new(crate::transcode::resolve_ffmpeg_exe());
+    cmd.args(["-hide_banner", "-nostdin", "-v", "error"]);
+    configure(&mut cmd);
+    cmd.stdin(Stdio::null()).stderr(Stdio::null());
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+    let output = cmd.output().ok()?;
+    if output.status.success() && !output.stdout.is_empty() {
+        Some(output.stdout)
+    } else {
+        None
+    }
+}
+
+/// Attached picture that BASS doesn't expose (MP4 `covr`, APE binary items), pulled out by ffmpeg
+fn extract_attached_picture(path: &Path) -> Option<Vec<u8>> {
+    run_ffmpeg(|cmd| {
+        cmd.arg("-i").arg(path).args([
+            "-an",
+            "-map",
+            "0:v:0",
+            "-c",
+            "copy",
+            "-f",
+            "image2pipe",
+            "-",
+        ]);
+    })
+}
+
+/// Store `image` as a thumbnail in `art_dir`, named after the hash of the original bytes so
+/// every track of an album shares one file. Returns the file name.
+pub fn store_cover_art(art_dir: &Path, image: &[u8]) -> Result<String, String> {
+    let hash = format!("{:x}", Sha256::digest(image));
+    let file_name = format!("{}.jpg", &hash[..32]);
+    let dest = art_dir.join(&file_name);
+    if dest.exists() {
+        return Ok(file_name);
+    }
+    std::fs::create_dir_all(art_dir)
+        .map_err(|e| format!("Failed to create art directory: {}", e))?;
+
+    let src = art_dir.join(format!("{}.src", &hash[..32]));
+    std::fs::write(&src, image).map_err(|e| format!("Failed to write cover art: {}", e))?;
+    let scale = format!(
+        "scale='min({0},iw)':'min({0},ih)':force_original_aspect_ratio=decrease",
+        THUMBNAIL_MAX_EDGE
+    );
+    let thumb = run_ffmpeg(|cmd| {
+        cmd.arg("-i").arg(&src).arg("-vf").arg(&scale).args([
+            "-frames:v",
+            "1",
+            "-q:v",
+            "3",
+            "-f",
+            "mjpeg",
+            "-",
+        ]);
+    });
+    let _ = std::fs::remove_file(&src);
+
+    // Without ffmpeg keep the original bytes; webviews sniff PNG content regardless of the extension
+    let bytes = thumb.as_deref().unwrap_or(image);
+    let tmp = art_dir.join(format!("{}.tmp", file_name));
+    std::fs::write(&tmp, bytes).map_err(|e| format!("Failed to write cover art: {}", e))?;
+    std::fs::rename(&tmp, &dest).map_err(|e| {
+        let _ = std::fs::remove_file(&tmp);
+        format!("Failed to store cover art: {}", e)
+    })?;
+    Ok(file_name)
+}
+
+fn extract_entry(cache_key: &str) -> Result<(), String> {
+    let (entry, path) =
+        crate::cache::get_entry_snapshot(cache_key).ok_or("entry no longer cached")?;
+    if entry.metadata.is_some() {
+        return Ok(());
+    }
+    let art_dir = path.parent().ok_or("bad cache path")?.join(ART_DIR_NAME);
+
+    // Decode streams need an initialized device and the codec plugins; both come from playback
+    crate::playback::ensure_bass_ready()?;
+    let lib = ensure_bass_loaded()?;
+    let (mut meta, picture) = read_tags(&lib, &path)?;
+    if let Some(image) = picture.or_else(|| extract_attached_picture(&path)) {
+        match store_cover_art(&art_dir, &image) {
+            Ok(name) => meta.cover_art = Some(name),
+            Err(e) => println!("[tags] {}: {}", cache_key, e),
+        }
+    }
+    if meta.is_empty() {
+        println!("[tags] {}: no embedded tags", cache_key);
+    } else {
+        println!(
+            "[tags] {}: {:?} - {:?} (art: {})",
+            cache_key,
+            meta.artist,
+            meta.title,
+            meta.cover_art.is_some()
+        );
+    }
+    // Entries without tags still get an (empty) record so they aren't probed again
+    crate::cache::apply_entry_metadata(cache_key, meta)
+}
+
+/// Queue tag and cover art extraction for a cached entry
+pub fn schedule(cache_key: String) {
+    {
+        let mut pending = PENDING.lock().unwrap();
+        if !pending.insert(cache_key.clone()) {
+            return;
+        }
+    }
+    tauri::async_runtime::spawn(async move {
+        let _permit = EXTRACT_SLOTS.acquire().await;
+        let key = cache_key.clone();
+        match tauri::async_runtime::spawn_blocking(move || extract_entry(&key)).await {
+            Ok(Err(e)) => println!("[tags] Skipped {}: {}", cache_key, e),
+            Err(e) => println!("[tags] Extraction task failed for {}: {}", cache_key, e),
+            Ok(Ok(())) => {}
+        }
+        PENDING.lock().unwrap().remove(&cache_key);
+    });
+}
+
+/// Queue every cached entry that has not been probed for tags yet
+pub fn sweep() {
+    for key in crate::cache::list_cache_keys_without_metadata() {
+        schedule(key);
+    }
+}
+
+/// Absolute path of a stored thumbnail, if it still exists
+pub fn cover_art_path(cache_dir: &Path, file_name: &str) -> Option<PathBuf> {
+    let p = cache_dir.join(ART_DIR_NAME).join(file_name);
+    if p.exists() {
+        Some(p)
+    } else {
+        None
+    }
+}
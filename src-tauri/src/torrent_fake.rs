@@ -0,0 +1,800 @@
+// Offline stand-in for the torrent engine. Each folder under the fixture directory is one
+// "torrent" named by its 40-char info hash; its files are handed out through the usual
+// `TorrentEngine` calls at a scripted pace, so the cache and playback torrent paths (retries,
+// stall timeouts, tail validation, cleanup) can be exercised without the public swarm.
+//
+// Built with the `fake-torrents` feature and in tests. Enable it by pointing
+// FREELY_FAKE_TORRENTS at the fixture directory. An optional `fake.json` next to a torrent's
+// files sets its `FakeProfile`.
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::bass::BlockingReader;
+use crate::torrents::{
+    info_hash_from_key, PeerCounts, PeerInfo, TorrentDetails, TorrentEngine, TorrentFileDetail,
+    TorrentFileInfo, TorrentProgress, TorrentSummary,
+};
+
+pub const FIXTURE_ENV: &str = "FREELY_FAKE_TORRENTS";
+const PROFILE_FILE: &str = "fake.json";
+// Transfers advance in fixed steps so a given profile always lands the same bytes per step
+const TICK: Duration = Duration::from_millis(50);
+const TICKS_PER_SEC: u64 = 20;
+// Same budget the real engine gives a playback read
+const READER_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How a fixture torrent behaves; every field is optional in `fake.json`
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct FakeProfile {
+    // Display name; defaults to the folder name
+    pub name: Option<String>,
+    // Wait before the first byte of each started file
+    pub latency_ms: u64,
+    // Per-file transfer rate; 0 writes the whole file on the first step
+    pub bytes_per_sec: u64,
+    // Pause the transfer once this many bytes of a file have landed...
+    pub stall_at: Option<u64>,
+    // ...for this long; 0 stalls until the torrent is removed
+    pub stall_ms: u64,
+    // Ranges written as zeros but still reported as verified (unfilled sparse pieces)
+    pub zero_ranges: Vec<ZeroRange>,
+    // Size output files up front, as rqbit does
+    pub preallocate: bool,
+    // Number of start requests that fail before one succeeds
+    pub fail_starts: u32,
+    pub peers: u32,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ZeroRange {
+    pub file: u32,
+    pub offset: u64,
+    pub len: u64,
+}
+
+pub fn fixture_dir() -> Option<PathBuf> {
+    std::env::var_os(FIXTURE_ENV)
+        .map(PathBuf::from)
+        .filter(|p| p.is_dir())
+}
+
+// One fixture folder as read from disk
+struct Fixture {
+    info_hash: String,
+    root: PathBuf,
+    // Relative path and length, sorted by path; the position is the file index
+    files: Vec<(String, u64)>,
+    profile: FakeProfile,
+}
+
+impl Fixture {
+    fn name(&self) -> String {
+        self.profile
+            .name
+            .clone()
+            .unwrap_or_else(|| self.info_hash.clone())
+    }
+}
+
+// Progress of one file being copied out of the fixture
+#[derive(Default)]
+struct Transfer {
+    written: AtomicU64,
+    speed: AtomicU64,
+    done: AtomicBool,
+}
+
+struct Active {
+    save_dir: PathBuf,
+    paused: Arc<AtomicBool>,
+    cancelled: Arc<AtomicBool>,
+    transfers: HashMap<u32, Arc<Transfer>>,
+}
+
+// Download cap shared by all transfers (bytes/sec, 0 = none)
+static DOWNLOAD_LIMIT: AtomicU64 = AtomicU64::new(0);
+// Start failures handed out so far, per info hash
+static FAILED_STARTS: Lazy<Mutex<HashMap<String, u32>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Start failures handed out so far for `info_hash`
+#[cfg(test)]
+pub(crate) fn failed_starts(info_hash: &str) -> u32 {
+    FAILED_STARTS
+        .lock()
+        .unwrap()
+        .get(info_hash)
+        .copied()
+        .unwrap_or(0)
+}
+
+pub struct FakeEngine {
+    dir: PathBuf,
+    active: Mutex<HashMap<String, Active>>,
+}
+
+impl FakeEngine {
+    pub fn new(dir: PathBuf) -> Self {
+        println!(
+            "[torrent] Using fake torrent engine with fixtures from {}",
+            dir.display()
+        );
+        Self {
+            dir,
+            active: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn fixture(&self, key: &str) -> Result<Fixture, String> {
+        let info_hash =
+            info_hash_from_key(key).ok_or_else(|| format!("not a magnet or info hash: {}", key))?;
+        let root = self.dir.join(&info_hash);
+        if !root.is_dir() {
+            return Err(format!("no fixture for torrent {}", info_hash));
+        }
+        let profile = match fs::read_to_string(root.join(PROFILE_FILE)) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| format!("invalid {} for {}: {}", PROFILE_FILE, info_hash, e))?,
+            Err(_) => FakeProfile::default(),
+        };
+        let mut files = Vec::new();
+        collect_files(&root, &root, &mut files)
+            .map_err(|e| format!("read fixture {}: {}", info_hash, e))?;
+        files.sort();
+        Ok(Fixture {
+            info_hash,
+            root,
+            files,
+            profile,
+        })
+    }
+
+    fn output_path(save_dir: &Path, fixture: &Fixture, file_index: u32) -> Result<PathBuf, String> {
+        let (rel, _) = fixture
+            .files
+            .get(file_index as usize)
+            .ok_or_else(|| format!("file index {} out of range", file_index))?;
+        Ok(save_dir.join(&fixture.info_hash).join(rel))
+    }
+
+    fn transfer(&self, info_hash: &str, file_index: u32) -> Option<Arc<Transfer>> {
+        self.active
+            .lock()
+            .unwrap()
+            .get(info_hash)
+            .and_then(|a| a.transfers.get(&file_index).cloned())
+    }
+
+    fn summary(&self, fixture: &Fixture) -> TorrentSummary {
+        let active = self.active.lock().unwrap();
+        let a = active.get(&fixture.info_hash);
+        let mut downloaded = 0;
+        let mut total = 0;
+        let mut down_speed = 0;
+        let mut finished = a.is_some();
+        for (i, (_, length)) in fixture.files.iter().enumerate() {
+            if let Some(t) = a.and_then(|a| a.transfers.get(&(i as u32))) {
+                downloaded += t.written.load(Ordering::SeqCst);
+                down_speed += t.speed.load(Ordering::SeqCst);
+                total += length;
+                finished &= t.done.load(Ordering::SeqCst);
+            }
+        }
+        let state = match a {
+            None => "initializing",
+            Some(a) if a.paused.load(Ordering::SeqCst) => "paused",
+            Some(_) => "live",
+        };
+        TorrentSummary {
+            info_hash: fixture.info_hash.clone(),
+            name: Some(fixture.name()),
+            state: state.to_string(),
+            downloaded,
+            total,
+            uploaded: 0,
+            down_speed,
+            up_speed: 0,
+            finished,
+        }
+    }
+}
+
+fn collect_files(root: &Path, dir: &Path, out: &mut Vec<(String, u64)>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(root, &path, out)?;
+        } else if dir != root || entry.file_name() != PROFILE_FILE {
+            let rel = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            out.push((rel, entry.metadata()?.len()));
+        }
+    }
+    Ok(())
+}
+
+// Copy one fixture file into the output folder following the profile
+fn run_transfer(
+    src: PathBuf,
+    dst: PathBuf,
+    file_index: u32,
+    profile: FakeProfile,
+    paused: Arc<AtomicBool>,
+    cancelled: Arc<AtomicBool>,
+    transfer: Arc<Transfer>,
+) -> std::io::Result<()> {
+    std::thread::sleep(Duration::from_millis(profile.latency_ms));
+    if let Some(parent) = dst.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut input = File::open(&src)?;
+    let length = input.metadata()?.len();
+    let mut output = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(&dst)?;
+    if profile.preallocate {
+        output.set_len(length)?;
+    }
+    let zero_ranges: Vec<&ZeroRange> = profile
+        .zero_ranges
+        .iter()
+        .filter(|r| r.file == file_index)
+        .collect();
+    let mut stalled = false;
+    let mut pos = 0u64;
+    let mut buf = Vec::new();
+    while pos < length {
+        if cancelled.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        if paused.load(Ordering::SeqCst) {
+            transfer.speed.store(0, Ordering::SeqCst);
+            std::thread::sleep(TICK);
+            continue;
+        }
+        if !stalled && profile.stall_at.is_some_and(|at| pos >= at) {
+            stalled = true;
+            transfer.speed.store(0, Ordering::SeqCst);
+            let until = (profile.stall_ms > 0)
+                .then(|| Instant::now() + Duration::from_millis(profile.stall_ms));
+            while until.map_or(true, |u| Instant::now() < u) {
+                if cancelled.load(Ordering::SeqCst) {
+                    return Ok(());
+                }
+                std::thread::sleep(TICK);
+            }
+        }
+        let rate = match (profile.bytes_per_sec, DOWNLOAD_LIMIT.load(Ordering::SeqCst)) {
+            (0, 0) => None,
+            (r, 0) | (0, r) => Some(r),
+            (r, limit) => Some(r.min(limit)),
+        };
+        let mut step = rate
+            .map_or(length - pos, |r| (r / TICKS_PER_SEC).max(1))
+            .min(length - pos);
+        // Land exactly on the stall point so it is hit at the same offset every run
+        if let Some(at) = profile.stall_at.filter(|&at| !stalled && at > pos) {
+            step = step.min(at - pos);
+        }
+        buf.resize(step as usize, 0);
+        input.seek(SeekFrom::Start(pos))?;
+        input.read_exact(&mut buf)?;
+        for r in &zero_ranges {
+            let start = r.offset.max(pos);
+            let end = (r.offset + r.len).min(pos + step);
+            if start < end {
+                buf[(start - pos) as usize..(end - pos) as usize].fill(0);
+            }
+        }
+        output.seek(SeekFrom::Start(pos))?;
+        output.write_all(&buf)?;
+        output.flush()?;
+        pos += step;
+        transfer.written.store(pos, Ordering::SeqCst);
+        transfer.speed.store(rate.unwrap_or(step), Ordering::SeqCst);
+        if pos < length {
+            std::thread::sleep(TICK);
+        }
+    }
+    transfer.speed.store(0, Ordering::SeqCst);
+    transfer.done.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+// Blocking reader over an output file that waits for the transfer to reach each read
+struct FakeReader {
+    file: File,
+    transfer: Arc<Transfer>,
+    length: u64,
+    pos: u64,
+}
+
+impl BlockingReader for FakeReader {
+    fn length(&self) -> u64 {
+        self.length
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.pos >= self.length || buf.is_empty() {
+            return Ok(0);
+        }
+        let deadline = Instant::now() + READER_TIMEOUT;
+        let available = loop {
+            let written = self.transfer.written.load(Ordering::SeqCst);
+            if written > self.pos {
+                break written - self.pos;
+            }
+            if Instant::now() >= deadline {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "timed out waiting for torrent pieces",
+                ));
+            }
+            std::thread::sleep(TICK);
+        };
+        let n = (buf.len() as u64).min(available) as usize;
+        self.file.seek(SeekFrom::Start(self.pos))?;
+        let n = self.file.read(&mut buf[..n])?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+
+    fn seek(&mut self, offset: u64) -> bool {
+        if offset > self.length {
+            return false;
+        }
+        self.pos = offset;
+        true
+    }
+}
+
+impl TorrentEngine for FakeEngine {
+    fn list_files(&self, magnet_or_infohash: &str) -> Result<Vec<TorrentFileInfo>, String> {
+        let fixture = self.fixture(magnet_or_infohash)?;
+        Ok(fixture
+            .files
+            .iter()
+            .enumerate()
+            .map(|(i, (name, length))| TorrentFileInfo {
+                index: i as u32,
+                name: name.clone(),
+                length: *length,
+            })
+            .collect())
+    }
+
+    fn start_download(
+        &self,
+        magnet_or_infohash: &str,
+        file_index: u32,
+        save_dir: &PathBuf,
+    ) -> Result<(), String> {
+        self.start_download_files(magnet_or_infohash, &[file_index], save_dir)
+    }
+
+    fn start_download_files(
+        &self,
+        magnet_or_infohash: &str,
+        file_indices: &[u32],
+        save_dir: &PathBuf,
+    ) -> Result<(), String> {
+        let fixture = self.fixture(magnet_or_infohash)?;
+        {
+            let mut failed = FAILED_STARTS.lock().unwrap();
+            let count = failed.entry(fixture.info_hash.clone()).or_insert(0);
+            if *count < fixture.profile.fail_starts {
+                *count += 1;
+                return Err(format!(
+                    "fake start failure {}/{}",
+                    count, fixture.profile.fail_starts
+                ));
+            }
+        }
+        let mut active = self.active.lock().unwrap();
+        let entry = active
+            .entry(fixture.info_hash.clone())
+            .or_insert_with(|| Active {
+                save_dir: save_dir.clone(),
+                paused: Arc::new(AtomicBool::new(false)),
+                cancelled: Arc::new(AtomicBool::new(false)),
+                transfers: HashMap::new(),
+            });
+        for &index in file_indices {
+            if entry.transfers.contains_key(&index) {
+                continue;
+            }
+            let (rel, _) = fixture
+                .files
+                .get(index as usize)
+                .cloned()
+                .ok_or_else(|| format!("file index {} out of range", index))?;
+            let transfer = Arc::new(Transfer::default());
+            entry.transfers.insert(index, transfer.clone());
+            let src = fixture.root.join(&rel);
+            let dst = Self::output_path(&entry.save_dir, &fixture, index)?;
+            let profile = fixture.profile.clone();
+            let paused = entry.paused.clone();
+            let cancelled = entry.cancelled.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = run_transfer(src, dst, index, profile, paused, cancelled, transfer)
+                {
+                    println!("[torrent] Fake transfer of {} failed: {}", rel, e);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    fn pause(&self, magnet_or_infohash: &str) -> Result<(), String> {
+        let fixture = self.fixture(magnet_or_infohash)?;
+        let active = self.active.lock().unwrap();
+        let a = active
+            .get(&fixture.info_hash)
+            .ok_or("torrent not started")?;
+        a.paused.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn resume(&self, magnet_or_infohash: &str) -> Result<(), String> {
+        let fixture = self.fixture(magnet_or_infohash)?;
+        let active = self.active.lock().unwrap();
+        let a = active
+            .get(&fixture.info_hash)
+            .ok_or("torrent not started")?;
+        a.paused.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn remove(&self, magnet_or_infohash: &str, remove_data: bool) -> Result<(), String> {
+        let fixture = self.fixture(magnet_or_infohash)?;
+        let removed = self.active.lock().unwrap().remove(&fixture.info_hash);
+        if let Some(a) = removed {
+            a.cancelled.store(true, Ordering::SeqCst);
+            if remove_data {
+                let _ = fs::remove_dir_all(a.save_dir.join(&fixture.info_hash));
+            }
+        }
+        Ok(())
+    }
+
+    fn progress(
+        &self,
+        magnet_or_infohash: &str,
+        file_index: u32,
+    ) -> Result<TorrentProgress, String> {
+        let fixture = self.fixture(magnet_or_infohash)?;
+        let total = fixture
+            .files
+            .get(file_index as usize)
+            .map(|(_, length)| *length)
+            .ok_or_else(|| format!("file index {} out of range", file_index))?;
+        let Some(transfer) = self.transfer(&fixture.info_hash, file_index) else {
+            return Ok(TorrentProgress {
+                total,
+                ..Default::default()
+            });
+        };
+        let written = transfer.written.load(Ordering::SeqCst);
+        let save_dir = self
+            .active
+            .lock()
+            .unwrap()
+            .get(&fixture.info_hash)
+            .map(|a| a.save_dir.clone());
+        let on_disk_bytes = save_dir
+            .and_then(|d| Self::output_path(&d, &fixture, file_index).ok())
+            .and_then(|p| fs::metadata(p).ok())
+            .map(|m| m.len())
+            .unwrap_or(0);
+        Ok(TorrentProgress {
+            bytes: written,
+            total,
+            peers: fixture.profile.peers,
+            down_speed: transfer.speed.load(Ordering::SeqCst),
+            verified_bytes: written,
+            on_disk_bytes,
+            uploaded: 0,
+            up_speed: 0,
+        })
+    }
+
+    fn file_path(
+        &self,
+        magnet_or_infohash: &str,
+        file_index: u32,
+        save_dir: &PathBuf,
+    ) -> Result<PathBuf, String> {
+        let fixture = self.fixture(magnet_or_infohash)?;
+        let dir = self
+            .active
+            .lock()
+            .unwrap()
+            .get(&fixture.info_hash)
+            .map(|a| a.save_dir.clone())
+            .unwrap_or_else(|| save_dir.clone());
+        Self::output_path(&dir, &fixture, file_index)
+    }
+
+    fn set_download_limit(&self, bytes_per_sec: Option<u64>) -> Result<(), String> {
+        DOWNLOAD_LIMIT.store(bytes_per_sec.unwrap_or(0), Ordering::SeqCst);
+        Ok(())
+    }
+
+    // Fixture files are always copied front to back
+    fn prioritize(&self, _m: &str, _i: u32, _o: u64) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn open_reader(
+        &self,
+        magnet_or_infohash: &str,
+        file_index: u32,
+    ) -> Result<Box<dyn BlockingReader>, String> {
+        let fixture = self.fixture(magnet_or_infohash)?;
+        let transfer = self
+            .transfer(&fixture.info_hash, file_index)
+            .ok_or("file download not started")?;
+        let length = fixture.files[file_index as usize].1;
+        let path = self.file_path(magnet_or_infohash, file_index, &PathBuf::new())?;
+        // The transfer thread creates the file after its latency
+        let deadline = Instant::now() + READER_TIMEOUT;
+        let file = loop {
+            match File::open(&path) {
+                Ok(f) => break f,
+                Err(_) if Instant::now() < deadline => std::thread::sleep(TICK),
+                Err(e) => return Err(format!("open {}: {}", path.display(), e)),
+            }
+        };
+        Ok(Box::new(FakeReader {
+            file,
+            transfer,
+            length,
+            pos: 0,
+        }))
+    }
+
+    // Files land front to back, so the verified part is always a prefix
+    fn verified_ranges(&self, magnet_or_infohash: &str, file_index: u32) -> Result<Vec<(u64, u64)>, String> {
+        let fixture = self.fixture(magnet_or_infohash)?;
+        let written = self
+            .transfer(&fixture.info_hash, file_index)
+            .map(|t| t.written.load(Ordering::SeqCst))
+            .unwrap_or(0);
+        Ok(if written > 0 { vec![(0, written)] } else { Vec::new() })
+    }
+
+    fn list_torrents(&self) -> Result<Vec<TorrentSummary>, String> {
+        let hashes: Vec<String> = self.active.lock().unwrap().keys().cloned().collect();
+        Ok(hashes
+            .iter()
+            .filter_map(|h| self.fixture(h).ok())
+            .map(|f| self.summary(&f))
+            .collect())
+    }
+
+    fn torrent_details(&self, magnet_or_infohash: &str) -> Result<TorrentDetails, String> {
+        let fixture = self.fixture(magnet_or_infohash)?;
+        let summary = self.summary(&fixture);
+        let active = self.active.lock().unwrap();
+        let a = active.get(&fixture.info_hash);
+        let files = fixture
+            .files
+            .iter()
+            .enumerate()
+            .map(|(i, (name, length))| {
+                let t = a.and_then(|a| a.transfers.get(&(i as u32)));
+                TorrentFileDetail {
+                    index: i as u32,
+                    name: name.clone(),
+                    length: *length,
+                    downloaded: t.map_or(0, |t| t.written.load(Ordering::SeqCst)),
+                    included: t.is_some(),
+                }
+            })
+            .collect();
+        let live = a.map_or(0, |_| fixture.profile.peers as u64);
+        let eta_secs = match fixture.profile.bytes_per_sec {
+            0 => None,
+            rate => Some(summary.total.saturating_sub(summary.downloaded) / rate),
+        };
+        Ok(TorrentDetails {
+            output_folder: a
+                .map(|a| {
+                    a.save_dir
+                        .join(&fixture.info_hash)
+                        .to_string_lossy()
+                        .to_string()
+                })
+                .unwrap_or_default(),
+            files,
+            peers: PeerCounts {
+                live,
+                seen: live,
+                ..Default::default()
+            },
+            pieces_have: None,
+            trackers: Vec::new(),
+            eta_secs: eta_secs.filter(|_| !summary.finished),
+            error: None,
+            summary,
+        })
+    }
+
+    fn peer_list(&self, magnet_or_infohash: &str) -> Result<Vec<PeerInfo>, String> {
+        let fixture = self.fixture(magnet_or_infohash)?;
+        let active = self.active.lock().unwrap();
+        let Some(a) = active.get(&fixture.info_hash) else {
+            return Ok(Vec::new());
+        };
+        let fetched: u64 = a
+            .transfers
+            .values()
+            .map(|t| t.written.load(Ordering::SeqCst))
+            .sum();
+        let peers = fixture.profile.peers.max(1) as u64;
+        Ok((0..fixture.profile.peers)
+            .map(|i| PeerInfo {
+                addr: format!("127.0.0.1:{}", 6881 + i),
+                state: "live".to_string(),
+                fetched_bytes: fetched / peers,
+                pieces: 0,
+                connections: 1,
+                errors: 0,
+            })
+            .collect())
+    }
+
+    fn add_torrent_bytes(&self, _bytes: Vec<u8>) -> Result<String, String> {
+        Err("the fake torrent engine only serves fixture folders".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEN: usize = 256 * 1024;
+    const WAIT: Duration = Duration::from_secs(10);
+
+    // Fresh fixture and output folders holding one file for `info_hash`
+    fn setup(info_hash: &str, data: &[u8], profile: &str) -> (FakeEngine, PathBuf) {
+        let base = std::env::temp_dir().join(format!(
+            "freely-fake-{}-{}",
+            &info_hash[..8],
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&base);
+        let root = base.join("fixtures").join(info_hash);
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("01 - Track.flac"), data).unwrap();
+        fs::write(root.join(PROFILE_FILE), profile).unwrap();
+        let save_dir = base.join("out");
+        (FakeEngine::new(base.join("fixtures")), save_dir)
+    }
+
+    // Non-zero bytes, like encoded audio
+    fn audio(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8 + 1).collect()
+    }
+
+    fn wait_for_bytes(engine: &FakeEngine, key: &str, bytes: u64) {
+        let deadline = Instant::now() + WAIT;
+        while engine.progress(key, 0).unwrap().bytes < bytes {
+            assert!(Instant::now() < deadline, "transfer never reached {} bytes", bytes);
+            std::thread::sleep(TICK);
+        }
+    }
+
+    #[test]
+    fn start_failures_clear_on_retry() {
+        let key = "1111111111111111111111111111111111111111";
+        let data = audio(LEN);
+        let (engine, save_dir) = setup(key, &data, r#"{"fail_starts": 2}"#);
+
+        assert!(engine.start_download(key, 0, &save_dir).is_err());
+        assert!(engine.start_download(key, 0, &save_dir).is_err());
+        // Failed starts leave nothing running for the cache loop to tail
+        assert_eq!(engine.progress(key, 0).unwrap().bytes, 0);
+        engine.start_download(key, 0, &save_dir).unwrap();
+
+        wait_for_bytes(&engine, key, LEN as u64);
+        assert_eq!(engine.verified_ranges(key, 0).unwrap(), vec![(0, LEN as u64)]);
+        let path = engine.file_path(key, 0, &save_dir).unwrap();
+        assert_eq!(fs::read(path).unwrap(), data);
+        engine.remove(key, true).unwrap();
+    }
+
+    #[test]
+    fn stalled_transfer_holds_readers_until_it_resumes() {
+        let key = "2222222222222222222222222222222222222222";
+        let data = audio(LEN);
+        let (engine, save_dir) = setup(key, &data, r#"{"stall_at": 65536, "stall_ms": 600}"#);
+        engine.start_download(key, 0, &save_dir).unwrap();
+        let started = Instant::now();
+
+        // What the cache's stall timeout sees: no new bytes and no speed
+        wait_for_bytes(&engine, key, 65536);
+        std::thread::sleep(Duration::from_millis(200));
+        let stalled = engine.progress(key, 0).unwrap();
+        assert_eq!(stalled.bytes, 65536);
+        assert_eq!(stalled.down_speed, 0);
+
+        // Playback reads block through the stall and then get every byte
+        let mut reader = engine.open_reader(key, 0).unwrap();
+        let mut read = Vec::new();
+        let mut buf = vec![0u8; 32 * 1024];
+        loop {
+            match reader.read(&mut buf).unwrap() {
+                0 => break,
+                n => read.extend_from_slice(&buf[..n]),
+            }
+        }
+        assert!(started.elapsed() >= Duration::from_millis(600));
+        assert_eq!(read, data);
+        engine.remove(key, true).unwrap();
+    }
+
+    #[tokio::test]
+    async fn tail_check_rejects_preallocated_and_zeroed_files() {
+        let key = "3333333333333333333333333333333333333333";
+        let (engine, save_dir) = setup(
+            key,
+            &audio(LEN),
+            r#"{"preallocate": true, "stall_at": 131072, "stall_ms": 0}"#,
+        );
+        engine.start_download(key, 0, &save_dir).unwrap();
+        wait_for_bytes(&engine, key, 131072);
+        let path = engine.file_path(key, 0, &save_dir).unwrap();
+
+        // Full length on disk, but the tail is still preallocated zeros
+        assert_eq!(fs::metadata(&path).unwrap().len(), LEN as u64);
+        assert!(!crate::cache::has_nontrivial_tail(&path, Some(LEN as u64)).await);
+
+        // Only the verified prefix is mirrored into the part file
+        let part = save_dir.join("track.part");
+        let partial = crate::partial::open(&part, "track", "torrent", key, Some(0)).unwrap();
+        let ranges = engine.verified_ranges(key, 0).unwrap();
+        crate::partial::mirror(&partial, &path, &ranges).unwrap();
+        assert_eq!(partial.lock().unwrap().meta().covered(), 131072);
+        partial.lock().unwrap().discard();
+        engine.remove(key, true).unwrap();
+
+        // Reported complete, yet the last pieces are zeros
+        let key = "4444444444444444444444444444444444444444";
+        let zeroed = format!(
+            r#"{{"zero_ranges": [{{"file": 0, "offset": {}, "len": 65536}}]}}"#,
+            LEN - 65536
+        );
+        let (engine, save_dir) = setup(key, &audio(LEN), &zeroed);
+        engine.start_download(key, 0, &save_dir).unwrap();
+        wait_for_bytes(&engine, key, LEN as u64);
+        let path = engine.file_path(key, 0, &save_dir).unwrap();
+        assert!(!crate::cache::has_nontrivial_tail(&path, Some(LEN as u64)).await);
+        engine.remove(key, true).unwrap();
+    }
+
+    #[tokio::test]
+    async fn tail_check_accepts_complete_files() {
+        let key = "5555555555555555555555555555555555555555";
+        let (engine, save_dir) = setup(key, &audio(LEN), r#"{"bytes_per_sec": 2097152}"#);
+        engine.start_download(key, 0, &save_dir).unwrap();
+        wait_for_bytes(&engine, key, LEN as u64);
+        let path = engine.file_path(key, 0, &save_dir).unwrap();
+        assert!(crate::cache::has_nontrivial_tail(&path, Some(LEN as u64)).await);
+        assert!(crate::cache::has_nonzero_middle_samples(&path, Some(LEN as u64)).await);
+        engine.remove(key, true).unwrap();
+    }
+}
@@ -0,0 +1,238 @@
+use serde::Serialize;
+use std::path::Path;
+use std::time::Duration;
+use tauri::State;
+
+use crate::downloads::{self, JobSpec};
+use crate::paths::PathConfig;
+use crate::torrents::{
+    ensure_progress_loop, get_engine, info_hash_from_key, register_download, TorrentFileInfo,
+};
+
+// Metadata for a magnet can take a while to arrive from the DHT
+const LIST_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Extensions BASS can decode with the plugins we ship
+const AUDIO_EXTENSIONS: &[&str] = &[
+    "mp3", "flac", "m4a", "mp4", "aac", "alac", "ogg", "oga", "opus", "wav", "aif", "aiff", "ape",
+    "wv", "wma", "mpc", "dsf",
+];
+
+/// One audio file of an imported album, in playback order
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumTrack {
+    pub index: u32,
+    pub path: String,
+    pub title: String,
+    pub disc: Option<u32>,
+    pub track: Option<u32>,
+    pub length: u64,
+    pub track_id: String,
+}
+
+fn is_audio(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| AUDIO_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+// Whitespace separated terms matched against the path, case-insensitive;
+// "-term" excludes files containing the term
+fn matches_filter(path: &str, filter: Option<&str>) -> bool {
+    let path = path.to_lowercase();
+    filter
+        .unwrap_or("")
+        .split_whitespace()
+        .all(|term| match term.strip_prefix('-') {
+            Some(excluded) if !excluded.is_empty() => !path.contains(&excluded.to_lowercase()),
+            _ => path.contains(&term.to_lowercase()),
+        })
+}
+
+// Leading decimal number of `s` and the rest of the string
+fn leading_number(s: &str) -> Option<(u32, &str)> {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    if end == 0 || end > 4 {
+        return None;
+    }
+    s[..end].parse().ok().map(|n| (n, &s[end..]))
+}
+
+// Disc number from a folder name such as "CD2", "Disc 1" or "disk_03"
+fn disc_from_folder(name: &str) -> Option<u32> {
+    let lower = name.to_lowercase();
+    for marker in ["disc", "disk", "cd"] {
+        let mut from = 0;
+        while let Some(pos) = lower[from..].find(marker) {
+            let start = from + pos;
+            let rest = lower[start + marker.len()..].trim_start_matches([' ', '_', '-', '.']);
+            // Require a word boundary so "abcd1" isn't read as a disc
+            let boundary = lower[..start]
+                .chars()
+                .last()
+                .map_or(true, |c| !c.is_alphanumeric());
+            if boundary {
+                if let Some((n, _)) = leading_number(rest) {
+                    return Some(n);
+                }
+            }
+            from = start + marker.len();
+        }
+    }
+    None
+}
+
+/// Disc and track number plus a display title, parsed from a path like
+/// "CD1/03 - Title.flac", "1-03 Title.mp3" or "Track 07.wav"
+fn parse_position(path: &str) -> (Option<u32>, Option<u32>, String) {
+    let p = Path::new(path);
+    let stem = p
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(path)
+        .trim();
+    let mut disc = p
+        .parent()
+        .into_iter()
+        .flat_map(|parent| parent.components())
+        .filter_map(|c| c.as_os_str().to_str())
+        .filter_map(disc_from_folder)
+        .last();
+
+    let lower = stem.to_lowercase();
+    let numbered = if lower.starts_with("track") {
+        stem["track".len()..].trim_start_matches([' ', '_', '-', '.'])
+    } else {
+        stem
+    };
+    let mut track = None;
+    let mut title = stem;
+    if let Some((first, rest)) = leading_number(numbered) {
+        // "1-03" / "1.03": disc then track, when the folders didn't name the disc
+        let second = rest
+            .strip_prefix(['-', '.'])
+            .and_then(leading_number)
+            .filter(|_| first < 10);
+        match second {
+            Some((n, after)) if disc.is_none() => {
+                disc = Some(first);
+                track = Some(n);
+                title = after;
+            }
+            _ => {
+                track = Some(first);
+                title = rest;
+            }
+        }
+    }
+    let title = title.trim_start_matches([' ', '_', '-', '.', ')']).trim();
+    let title = if title.is_empty() { stem } else { title };
+    (disc, track, title.to_string())
+}
+
+/// Audio files of a torrent in album order (disc, track, then path)
+pub fn album_tracks(
+    info_hash: &str,
+    files: &[TorrentFileInfo],
+    filter: Option<&str>,
+) -> Vec<AlbumTrack> {
+    let mut tracks: Vec<AlbumTrack> = files
+        .iter()
+        .filter(|f| is_audio(&f.name) && matches_filter(&f.name, filter))
+        .map(|f| {
+            let (disc, track, title) = parse_position(&f.name);
+            AlbumTrack {
+                index: f.index,
+                path: f.name.clone(),
+                title,
+                disc,
+                track,
+                length: f.length,
+                track_id: format!("{}-{}", info_hash, f.index),
+            }
+        })
+        .collect();
+    tracks.sort_by(|a, b| {
+        (a.disc.unwrap_or(1), a.track.unwrap_or(u32::MAX), &a.path).cmp(&(
+            b.disc.unwrap_or(1),
+            b.track.unwrap_or(u32::MAX),
+            &b.path,
+        ))
+    });
+    tracks
+}
+
+/// Import every audio file of an album torrent: one multi-file engine download plus a
+/// download batch that caches each track. Returns the ordered, queueable track list.
+#[tauri::command]
+pub async fn torrent_import_album(
+    app: tauri::AppHandle,
+    magnet: String,
+    filter: Option<String>,
+    config: State<'_, PathConfig>,
+) -> Result<serde_json::Value, String> {
+    let key = magnet.clone();
+    let files = tokio::time::timeout(
+        LIST_TIMEOUT,
+        tokio::task::spawn_blocking(move || get_engine().list_files(&key)),
+    )
+    .await
+    .map_err(|_| format!("Timeout after {} ms", LIST_TIMEOUT.as_millis()))?
+    .map_err(|e| format!("join error: {e}"))??;
+
+    let key = magnet.clone();
+    let save_dir = config.torrents_dir.clone();
+    let (info_hash, name, tracks) = tokio::task::spawn_blocking(move || {
+        let engine = get_engine();
+        let details = engine.torrent_details(&key).ok();
+        let info_hash = info_hash_from_key(&key)
+            .or_else(|| details.as_ref().map(|d| d.summary.info_hash.clone()))
+            .ok_or("Could not resolve the torrent's info hash")?;
+        let tracks = album_tracks(&info_hash, &files, filter.as_deref());
+        if tracks.is_empty() {
+            return Err("No audio files found in torrent".to_string());
+        }
+        let indices: Vec<u32> = tracks.iter().map(|t| t.index).collect();
+        engine.start_download_files(&key, &indices, &save_dir)?;
+        let name = details
+            .and_then(|d| d.summary.name)
+            .unwrap_or_else(|| info_hash.clone());
+        Ok::<_, String>((info_hash, name, tracks))
+    })
+    .await
+    .map_err(|e| format!("join error: {e}"))??;
+
+    for t in &tracks {
+        register_download(&app, &magnet, t.index);
+    }
+    ensure_progress_loop(&app);
+
+    let specs = tracks
+        .iter()
+        .map(|t| JobSpec {
+            track_id: t.track_id.clone(),
+            source_type: "torrent".to_string(),
+            source_hash: info_hash.clone(),
+            url: magnet.clone(),
+            file_index: Some(t.index as usize),
+        })
+        .collect();
+    let batch_id = downloads::enqueue_batch(&app, name.clone(), specs, downloads::PRIORITY_NORMAL)?;
+    println!(
+        "[torrent] Imported album '{}' ({} tracks) as {}",
+        name,
+        tracks.len(),
+        batch_id
+    );
+
+    Ok(serde_json::json!({
+        "status": "ok",
+        "infoHash": info_hash,
+        "name": name,
+        "batchId": batch_id,
+        "tracks": tracks,
+    }))
+}
@@ -0,0 +1,169 @@
+use once_cell::sync::Lazy;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+// Torrent session network settings with persistence support
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct TorrentSettings {
+    // Incoming peer connections on the first free port in the range
+    pub listen_enabled: bool,
+    pub listen_port_min: u16,
+    pub listen_port_max: u16,
+    // Ask the router to forward the listen port
+    pub upnp_enabled: bool,
+    pub dht_enabled: bool,
+}
+
+impl Default for TorrentSettings {
+    fn default() -> Self {
+        Self {
+            listen_enabled: true,
+            listen_port_min: 4240,
+            listen_port_max: 4260,
+            upnp_enabled: true,
+            dht_enabled: true,
+        }
+    }
+}
+
+impl TorrentSettings {
+    /// Load torrent settings from disk, using defaults if file doesn't exist
+    pub fn load() -> Self {
+        match Self::get_settings_path() {
+            Ok(path) => {
+                if path.exists() {
+                    match std::fs::read_to_string(&path) {
+                        Ok(content) => {
+                            match serde_json::from_str::<TorrentSettings>(&content) {
+                                Ok(mut settings) => {
+                                    println!(
+                                        "[torrent] Loaded torrent settings from: {}",
+                                        path.display()
+                                    );
+                                    settings.validate();
+                                    return settings;
+                                }
+                                Err(e) => {
+                                    println!("[torrent] Failed to parse settings file: {}, using defaults", e);
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            println!(
+                                "[torrent] Failed to read settings file: {}, using defaults",
+                                e
+                            );
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                println!(
+                    "[torrent] Failed to get settings path: {}, using defaults",
+                    e
+                );
+            }
+        }
+        Self::default()
+    }
+
+    /// Save torrent settings to disk
+    pub fn save(&self) -> Result<(), String> {
+        let path =
+            Self::get_settings_path().map_err(|e| format!("Failed to get settings path: {}", e))?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+        }
+
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+
+        std::fs::write(&path, content)
+            .map_err(|e| format!("Failed to write settings file: {}", e))?;
+
+        println!("[torrent] Saved torrent settings to: {}", path.display());
+        Ok(())
+    }
+
+    fn get_settings_path() -> Result<PathBuf, String> {
+        let data_dir = dirs::data_dir().ok_or("Failed to get system data directory")?;
+
+        let mut path = data_dir;
+        path.push("com.freely.player");
+        path.push("torrent_settings.json");
+        Ok(path)
+    }
+
+    /// Validate and clamp settings to values the engine accepts
+    pub fn validate(&mut self) {
+        // Stay out of the privileged range
+        self.listen_port_min = self.listen_port_min.max(1024);
+        self.listen_port_max = self.listen_port_max.max(self.listen_port_min);
+    }
+
+    /// Listen port range in the form rqbit expects (end exclusive)
+    pub fn listen_port_range(&self) -> Option<std::ops::Range<u16>> {
+        if self.listen_enabled {
+            Some(self.listen_port_min..self.listen_port_max.saturating_add(1))
+        } else {
+            None
+        }
+    }
+}
+
+// Global torrent settings instance
+static TORRENT_SETTINGS: Lazy<Mutex<TorrentSettings>> =
+    Lazy::new(|| Mutex::new(TorrentSettings::load()));
+
+/// Get a snapshot of current torrent settings
+pub fn get_torrent_settings() -> TorrentSettings {
+    TORRENT_SETTINGS.lock().unwrap().clone()
+}
+
+/// Update torrent settings and save to disk
+pub fn update_torrent_settings<F>(updater: F) -> Result<TorrentSettings, String>
+where
+    F: FnOnce(&mut TorrentSettings),
+{
+    let mut settings = TORRENT_SETTINGS.lock().unwrap();
+    updater(&mut settings);
+    settings.validate();
+    settings.save()?;
+    Ok(settings.clone())
+}
+
+#[tauri::command]
+pub async fn torrent_get_settings() -> Result<serde_json::Value, String> {
+    serde_json::to_value(get_torrent_settings())
+        .map_err(|e| format!("Failed to serialize torrent settings: {}", e))
+}
+
+/// Save new settings and rebuild the torrent session if anything changed. Settings the
+/// session can't be built with are rolled back, so they never reach the next launch.
+#[tauri::command]
+pub async fn torrent_set_settings(
+    settings: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let incoming: TorrentSettings =
+        serde_json::from_value(settings).map_err(|e| format!("Invalid torrent settings: {}", e))?;
+    let previous = get_torrent_settings();
+    let updated = update_torrent_settings(|s| *s = incoming)?;
+    let rebuilt = updated != previous;
+    if rebuilt {
+        let result = tokio::task::spawn_blocking(move || {
+            crate::torrents::rebuild_session(|| update_torrent_settings(|s| *s = previous).map(|_| ()))
+        })
+        .await
+        .unwrap_or_else(|e| Err(format!("join error: {e}")));
+        if let Err(e) = result {
+            return Err(format!("Torrent settings not applied: {}", e));
+        }
+    }
+    Ok(serde_json::json!({
+        "settings": updated,
+        "rebuilt": rebuilt,
+    }))
+}
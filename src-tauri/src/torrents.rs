@@ -6,6 +6,7 @@ use std::collections::{HashMap, HashSet};
 // logging macros available globally
 use std::sync::Mutex;
 use once_cell::sync::OnceCell;
+use crate::bass::BlockingReader;
 
 // ================= Shared Event / Tracking State (moved from commands.rs) =================
 // Track active (torrent -> set of file indices) for progress emission.
@@ -80,6 +81,8 @@ fn emit_progress_if_changed(handle: &tauri::AppHandle, key: &str, file_index: u3
                 "total": p.total,
                 "peers": p.peers,
                 "downSpeed": p.down_speed,
+                "uploaded": p.uploaded,
+                "upSpeed": p.up_speed,
                 "percent": pct,
                 "ts": ts,
             });
@@ -135,16 +138,103 @@ pub struct TorrentProgress {
 	pub verified_bytes: u64,
 	// New: raw on-disk file length (may include unverified/preallocated bytes)
 	pub on_disk_bytes: u64,
+	// Bytes uploaded to peers for the whole torrent this session, and current upload rate
+	pub uploaded: u64,
+	pub up_speed: u64,
+}
+
+// One torrent in the engine's session, with transfer totals for ratio bookkeeping
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TorrentSummary {
+	pub info_hash: String,
+	pub name: Option<String>,
+	pub state: String,
+	pub downloaded: u64,
+	pub total: u64,
+	pub uploaded: u64,
+	pub down_speed: u64,
+	pub up_speed: u64,
+	pub finished: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TorrentFileDetail {
+	pub index: u32,
+	pub name: String,
+	pub length: u64,
+	// Verified bytes of this file
+	pub downloaded: u64,
+	// Selected for download (only_files)
+	pub included: bool,
+}
+
+// Swarm connection counts as tracked by the engine
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerCounts {
+	pub live: u64,
+	pub connecting: u64,
+	pub queued: u64,
+	pub seen: u64,
+	pub dead: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TorrentDetails {
+	#[serde(flatten)]
+	pub summary: TorrentSummary,
+	pub output_folder: String,
+	pub files: Vec<TorrentFileDetail>,
+	pub peers: PeerCounts,
+	// Hash-verified pieces, when the torrent is live (per-file progress is in `files`)
+	pub pieces_have: Option<u64>,
+	// Announce URLs from the magnet link; librqbit doesn't report per-tracker status
+	pub trackers: Vec<String>,
+	pub eta_secs: Option<u64>,
+	pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerInfo {
+	pub addr: String,
+	pub state: String,
+	// librqbit only counts what each peer sent us; uploads are tracked per torrent
+	pub fetched_bytes: u64,
+	pub pieces: u64,
+	pub connections: u64,
+	pub errors: u64,
 }
 
 pub trait TorrentEngine: Send + Sync + 'static {
 	fn list_files(&self, magnet_or_infohash: &str) -> Result<Vec<TorrentFileInfo>, String>;
 	fn start_download(&self, magnet_or_infohash: &str, file_index: u32, save_dir: &PathBuf) -> Result<(), String>;
+	// One download for several files of the same torrent (album imports); widens the selection
+	// when the torrent is already running
+	fn start_download_files(&self, magnet_or_infohash: &str, file_indices: &[u32], save_dir: &PathBuf) -> Result<(), String>;
 	fn pause(&self, magnet_or_infohash: &str) -> Result<(), String>;
 	fn resume(&self, magnet_or_infohash: &str) -> Result<(), String>;
 	fn remove(&self, magnet_or_infohash: &str, remove_data: bool) -> Result<(), String>;
 	fn progress(&self, magnet_or_infohash: &str, file_index: u32) -> Result<TorrentProgress, String>;
 	fn file_path(&self, magnet_or_infohash: &str, file_index: u32, save_dir: &PathBuf) -> Result<PathBuf, String>;
+	// Session-wide download cap in bytes/sec (None = unlimited)
+	fn set_download_limit(&self, bytes_per_sec: Option<u64>) -> Result<(), String>;
+	// Fetch the file's pieces in order from `offset` ahead of everything else (playback seeks)
+	fn prioritize(&self, magnet_or_infohash: &str, file_index: u32, offset: u64) -> Result<(), String>;
+	// Blocking reader over an active download's file; reads wait for missing pieces
+	fn open_reader(&self, magnet_or_infohash: &str, file_index: u32) -> Result<Box<dyn BlockingReader>, String>;
+	// Byte ranges of the file known to be covered by hash-verified pieces, sorted and merged.
+	// May report less than is on disk, never more.
+	fn verified_ranges(&self, magnet_or_infohash: &str, file_index: u32) -> Result<Vec<(u64, u64)>, String>;
+	fn list_torrents(&self) -> Result<Vec<TorrentSummary>, String>;
+	fn torrent_details(&self, magnet_or_infohash: &str) -> Result<TorrentDetails, String>;
+	// Currently connected peers
+	fn peer_list(&self, magnet_or_infohash: &str) -> Result<Vec<PeerInfo>, String>;
+	// Register a .torrent file; returns its info hash, usable as the key for every other call
+	fn add_torrent_bytes(&self, bytes: Vec<u8>) -> Result<String, String>;
 }
 
 // Dummy engine used when feature is disabled
@@ -152,21 +242,30 @@ struct NoopEngine;
 impl TorrentEngine for NoopEngine {
 	fn list_files(&self, _m: &str) -> Result<Vec<TorrentFileInfo>, String> { Err("torrent engine not enabled".into()) }
 	fn start_download(&self, _m: &str, _i: u32, _d: &PathBuf) -> Result<(), String> { Err("torrent engine not enabled".into()) }
+	fn start_download_files(&self, _m: &str, _i: &[u32], _d: &PathBuf) -> Result<(), String> { Err("torrent engine not enabled".into()) }
 	fn pause(&self, _m: &str) -> Result<(), String> { Err("torrent engine not enabled".into()) }
 	fn resume(&self, _m: &str) -> Result<(), String> { Err("torrent engine not enabled".into()) }
 	fn remove(&self, _m: &str, _r: bool) -> Result<(), String> { Err("torrent engine not enabled".into()) }
 	fn progress(&self, _m: &str, _i: u32) -> Result<TorrentProgress, String> { Err("torrent engine not enabled".into()) }
 	fn file_path(&self, _m: &str, _i: u32, _d: &PathBuf) -> Result<PathBuf, String> { Err("torrent engine not enabled".into()) }
+	fn set_download_limit(&self, _b: Option<u64>) -> Result<(), String> { Err("torrent engine not enabled".into()) }
+	fn prioritize(&self, _m: &str, _i: u32, _o: u64) -> Result<(), String> { Err("torrent engine not enabled".into()) }
+	fn open_reader(&self, _m: &str, _i: u32) -> Result<Box<dyn BlockingReader>, String> { Err("torrent engine not enabled".into()) }
+	fn verified_ranges(&self, _m: &str, _i: u32) -> Result<Vec<(u64, u64)>, String> { Err("torrent engine not enabled".into()) }
+	fn list_torrents(&self) -> Result<Vec<TorrentSummary>, String> { Err("torrent engine not enabled".into()) }
+	fn torrent_details(&self, _m: &str) -> Result<TorrentDetails, String> { Err("torrent engine not enabled".into()) }
+	fn peer_list(&self, _m: &str) -> Result<Vec<PeerInfo>, String> { Err("torrent engine not enabled".into()) }
+	fn add_torrent_bytes(&self, _b: Vec<u8>) -> Result<String, String> { Err("torrent engine not enabled".into()) }
 }
 
 #[cfg(feature = "torrent-rqbit")]
 mod rqbit_impl {
 	use super::*;
 	use librqbit::api::Api;
-	use librqbit::{AddTorrent, AddTorrentOptions, Session};
+	use librqbit::{AddTorrent, AddTorrentOptions, Session, SessionOptions, SessionPersistenceConfig};
 	use once_cell::sync::OnceCell;
 	use std::sync::Mutex;
-	use std::collections::HashMap;
+	use std::collections::{HashMap, HashSet};
 	use std::time::Instant;
 	use std::future::Future;
 
@@ -184,12 +283,58 @@ mod rqbit_impl {
 	static DETAILS_CACHE: OnceCell<Mutex<HashMap<String, CachedTorrent>>> = OnceCell::new();
 	// User override of output folder (info_hash or original key -> chosen save_dir)
 	static SAVE_DIR_OVERRIDES: OnceCell<Mutex<HashMap<String, PathBuf>>> = OnceCell::new();
+	// Streaming focus: (info_hash, file_index) -> reader task pulling pieces in order
+	static STREAM_FOCUS: OnceCell<Mutex<HashMap<(String, u32), tokio::task::JoinHandle<()>>>> = OnceCell::new();
+	// Byte runs of each file (info_hash, file_index) that focus readers have streamed. A stream
+	// only yields hash-verified pieces and rqbit has no public per-piece state, so these runs
+	// are what we know to be verified short of the whole file.
+	static STREAMED_RUNS: OnceCell<Mutex<HashMap<(String, u32), Vec<(u64, u64)>>>> = OnceCell::new();
+	// File playback is streaming (info_hash, file_index); its reader is dropped when playback moves on
+	static PLAYBACK_FOCUS: Mutex<Option<(String, u32)>> = Mutex::new(None);
+	// Download cap last applied, carried over when the session is rebuilt
+	static DOWNLOAD_LIMIT: Mutex<Option<std::num::NonZeroU32>> = Mutex::new(None);
+	// Raw .torrent files by info hash (loaded lazily from the session folder)
+	static TORRENT_FILES: OnceCell<Mutex<HashMap<String, Vec<u8>>>> = OnceCell::new();
+	// Key/folder bookkeeping persisted next to rqbit's session state
+	static SESSION_INDEX: OnceCell<Mutex<SessionIndex>> = OnceCell::new();
+
+	// rqbit's own session state (torrent list, fastresume bitfields) lives in this subfolder
+	// of the torrents dir; our index sits beside it so both are reset together
+	const SESSION_STATE_DIR: &str = ".session";
+	const SESSION_INDEX_FILE: &str = "freely_index.json";
+	// .torrent files added from disk or indexers, kept so their metadata never needs the DHT
+	const TORRENT_FILES_DIR: &str = "torrent_files";
+	// Resolved file lists by info hash, under the app data dir so they outlive the session
+	const METADATA_DIR: &str = "torrent_metadata";
+	// Stored file lists kept across restarts; the oldest beyond this are pruned at startup
+	const MAX_STORED_METADATA: usize = 2000;
+
+	// Restored torrents come back under their info hash only; the index maps the magnets and
+	// hashes the frontend uses back to them, plus any output folder chosen per download
+	#[derive(Default, serde::Serialize, serde::Deserialize)]
+	#[serde(default)]
+	struct SessionIndex {
+		// user key (magnet or info hash) -> info hash
+		aliases: HashMap<String, String>,
+		// info hash -> output folder
+		save_dirs: HashMap<String, PathBuf>,
+	}
+
+	// rqbit requests the pieces just ahead of every open file stream first, so a reader that
+	// walks the file from the playback position turns the swarm download into a sequential one.
+	// Bytes read from the head before the tail is requested too
+	const HEAD_BEFORE_TAIL: u64 = 1024 * 1024;
+	// End of the file fetched early: MP4 `moov` atoms, APE/ID3v1 tags and seek tables live there
+	const TAIL_PREFETCH: u64 = 2 * 1024 * 1024;
+	const FOCUS_READ_CHUNK: usize = 64 * 1024;
+	// How long a playback read may wait for its pieces before BASS sees an error
+	const READER_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
 
 	// Refresh / staleness threshold for cached torrent metadata (seconds).
 	// Lowered to 2s so newly started downloads reflect custom output folder sooner.
 	const DETAILS_TTL_SECS: f64 = 2.0; // small so UI stays fresh but avoids metadata spam
 
-	#[derive(Clone, Debug)]
+	#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 	struct CachedFile {
 		components: Vec<String>,
 		name: String,
@@ -217,15 +362,250 @@ mod rqbit_impl {
 	}
 	fn rt_block_on<F: Future>(fut: F) -> F::Output { rt().block_on(fut) }
 
-	fn get_api() -> &'static Mutex<Api> {
-		API.get_or_init(|| {
-			let default_dir = std::env::temp_dir().join("freely-torrents");
-			let session = rt_block_on(Session::new(default_dir))
-				.expect("create librqbit session");
-			Mutex::new(Api::new(session, None))
+	// Session options for the given network settings, persisting state in `state_dir`
+	fn session_options(settings: &crate::torrent_settings::TorrentSettings, socks_proxy_url: Option<String>, state_dir: PathBuf) -> SessionOptions {
+		SessionOptions {
+			socks_proxy_url,
+			// Torrents added in earlier runs are restored (and resumed unless paused)
+			persistence: Some(SessionPersistenceConfig::Json { folder: Some(state_dir) }),
+			// Skip re-hashing already verified pieces on restore
+			fastresume: true,
+			disable_dht: !settings.dht_enabled,
+			listen_port_range: settings.listen_port_range(),
+			enable_upnp_port_forwarding: settings.upnp_enabled && settings.listen_enabled,
+			..Default::default()
+		}
+	}
+
+	fn build_session(root: PathBuf, opts: SessionOptions) -> Result<Api, String> {
+		let session = rt_block_on(Session::new_with_opts(root, opts))
+			.map_err(|e| format!("create librqbit session: {e}"))?;
+		if let Some(bps) = *DOWNLOAD_LIMIT.lock().unwrap() { session.ratelimits.set_download_bps(Some(bps)); }
+		Ok(Api::new(session, None))
+	}
+
+	// Build a session from the saved torrent and proxy settings. Torrents persisted by an earlier
+	// session (or run) are restored from the state folder.
+	fn create_session() -> Result<(Api, PathBuf), String> {
+		let default_dir = super::torrents_dir();
+		let state_dir = default_dir.join(SESSION_STATE_DIR);
+		let settings = crate::torrent_settings::get_torrent_settings();
+		let opts = session_options(&settings, crate::proxy::torrent_socks_url(), state_dir.clone());
+		Ok((build_session(default_dir, opts)?, state_dir))
+	}
+
+	// A session that fails to build (e.g. bad saved settings) is retried on the next call
+	fn get_api() -> Result<&'static Mutex<Api>, String> {
+		API.get_or_try_init(|| {
+			let (api, state_dir) = create_session()?;
+			restore_index(&api, &state_dir);
+			Ok(Mutex::new(api))
 		})
 	}
 
+	// Swap in a session built from the current settings. The API lock is held throughout, so
+	// other torrent calls wait and then see the new session; open torrent streams are dropped.
+	// When the new session can't be built, `rollback` restores the previous settings and a
+	// session is built from those instead; the original error is still returned.
+	pub fn rebuild_session(rollback: impl FnOnce() -> Result<(), String>) -> Result<(), String> {
+		// Not created yet (or the last attempt failed): creating it now checks the new settings
+		if API.get().is_none() {
+			return get_api().map(|_| ()).or_else(|e| { rollback()?; Err(e) });
+		}
+		let mut api = get_api()?.lock().map_err(|_| "api poisoned")?;
+		replace_session(&mut api, &create_session, rollback)?;
+		log_info!("[torrent] Session rebuilt with new network settings");
+		Ok(())
+	}
+
+	// The old session must be gone before the new one starts: it holds the listen port and the
+	// DHT's UDP address, which the new session binds again.
+	fn replace_session(api: &mut Api, create: &dyn Fn() -> Result<(Api, PathBuf), String>, rollback: impl FnOnce() -> Result<(), String>) -> Result<(), String> {
+		let old = api.session().clone();
+		// Pause running torrents first; the pause is persisted, so the next session restores
+		// them paused and they are resumed there.
+		let running: Vec<std::sync::Arc<librqbit::ManagedTorrent>> = old.with_torrents(|torrents| {
+			torrents.filter(|(_, t)| !t.is_paused()).map(|(_, t)| t.clone()).collect()
+		});
+		let held: Vec<_> = running.into_iter().filter(|t| rt_block_on(old.pause(t)).is_ok()).collect();
+		for (_, task) in get_stream_focus().lock().unwrap().drain() { task.abort(); }
+		rt_block_on(old.stop());
+		drop(old);
+		let (new_api, state_dir, failure) = match create() {
+			Ok((new_api, state_dir)) => (new_api, state_dir, None),
+			Err(e) => {
+				log_warn!("[torrent] New session failed, restoring the previous settings: {}", e);
+				rollback()?;
+				let (new_api, state_dir) = create().map_err(|restore| {
+					log_warn!("[torrent] Torrents are unavailable until restart: {}", restore);
+					format!("{e}; the previous settings failed too: {restore}")
+				})?;
+				(new_api, state_dir, Some(e))
+			}
+		};
+		let session = new_api.session().clone();
+		for t in &held {
+			let Some(handle) = session.get(librqbit::api::TorrentIdOrHash::Hash(t.info_hash())) else { continue };
+			if let Err(e) = rt_block_on(session.unpause(&handle)) {
+				log_warn!("[torrent] Could not resume {} after the rebuild: {}", t.info_hash().as_string(), e);
+			}
+		}
+		*api = new_api;
+		get_torrent_ids().lock().unwrap().clear();
+		get_save_dir_overrides().lock().unwrap().clear();
+		get_details_cache().lock().unwrap().clear();
+		get_speed_cache().lock().unwrap().clear();
+		restore_index(api, &state_dir);
+		failure.map_or(Ok(()), Err)
+	}
+
+	fn get_session_index() -> &'static Mutex<SessionIndex> {
+		SESSION_INDEX.get_or_init(|| Mutex::new(SessionIndex::default()))
+	}
+
+	// Load our index and rebuild the id/folder maps for every torrent rqbit restored
+	fn restore_index(api: &Api, state_dir: &std::path::Path) {
+		let path = state_dir.join(SESSION_INDEX_FILE);
+		let index: SessionIndex = std::fs::read_to_string(&path)
+			.ok()
+			.and_then(|c| serde_json::from_str(&c).ok())
+			.unwrap_or_default();
+		let restored = api.api_torrent_list().torrents;
+		let mut ids = get_torrent_ids().lock().unwrap();
+		let mut overrides = get_save_dir_overrides().lock().unwrap();
+		for t in &restored {
+			let Some(id) = t.id else { continue };
+			let ih = t.info_hash.to_lowercase();
+			ids.insert(ih.clone(), id);
+			if let Some(dir) = index.save_dirs.get(&ih) { overrides.insert(ih.clone(), dir.clone()); }
+			for (alias, target) in &index.aliases {
+				if *target != ih { continue; }
+				ids.insert(alias.clone(), id);
+				if let Some(dir) = index.save_dirs.get(&ih) { overrides.insert(alias.clone(), dir.clone()); }
+			}
+		}
+		log_info!("[torrent] Restored {} torrent(s) from {}", restored.len(), state_dir.display());
+		// Drop entries for torrents rqbit no longer knows about
+		let live: HashSet<String> = restored.iter().map(|t| t.info_hash.to_lowercase()).collect();
+		let mut guard = get_session_index().lock().unwrap();
+		*guard = index;
+		guard.aliases.retain(|_, ih| live.contains(ih));
+		guard.save_dirs.retain(|ih, _| live.contains(ih));
+	}
+
+	fn save_index(index: &SessionIndex) {
+		let path = super::torrents_dir().join(SESSION_STATE_DIR).join(SESSION_INDEX_FILE);
+		let result = serde_json::to_string_pretty(index)
+			.map_err(|e| e.to_string())
+			.and_then(|content| {
+				if let Some(parent) = path.parent() { std::fs::create_dir_all(parent).map_err(|e| e.to_string())?; }
+				std::fs::write(&path, content).map_err(|e| e.to_string())
+			});
+		if let Err(e) = result { log_warn!("[torrent] Failed to save session index {}: {}", path.display(), e); }
+	}
+
+	fn remember_torrent(key: &str, info_hash: &str, save_dir: &PathBuf) {
+		let mut index = get_session_index().lock().unwrap();
+		index.aliases.insert(key.to_string(), info_hash.to_string());
+		index.save_dirs.insert(info_hash.to_string(), save_dir.clone());
+		save_index(&index);
+	}
+
+	// Forget every alias of the torrent behind `key` (after remove)
+	fn forget_torrent(key: &str, info_hash: Option<String>) {
+		let mut index = get_session_index().lock().unwrap();
+		let Some(ih) = index.aliases.get(key).cloned().or(info_hash) else { return };
+		let mut keys: Vec<String> = index.aliases.iter().filter(|(_, t)| **t == ih).map(|(k, _)| k.clone()).collect();
+		keys.push(ih.clone());
+		keys.push(key.to_string());
+		index.aliases.retain(|_, t| *t != ih);
+		index.save_dirs.remove(&ih);
+		save_index(&index);
+		drop(index);
+		if let Ok(mut ids) = get_torrent_ids().lock() { for k in &keys { ids.remove(k); } }
+		if let Ok(mut ov) = get_save_dir_overrides().lock() { for k in &keys { ov.remove(k); } }
+	}
+
+	fn torrent_file_path(info_hash: &str) -> PathBuf {
+		super::torrents_dir().join(SESSION_STATE_DIR).join(TORRENT_FILES_DIR).join(format!("{}.torrent", info_hash))
+	}
+
+	fn store_torrent_file(info_hash: &str, bytes: &[u8]) {
+		let path = torrent_file_path(info_hash);
+		let result = path.parent().map_or(Ok(()), std::fs::create_dir_all).and_then(|_| std::fs::write(&path, bytes));
+		if let Err(e) = result { log_warn!("[torrent] Failed to save {}: {}", path.display(), e); }
+		TORRENT_FILES.get_or_init(|| Mutex::new(HashMap::new())).lock().unwrap().insert(info_hash.to_string(), bytes.to_vec());
+	}
+
+	fn torrent_file(info_hash: &str) -> Option<Vec<u8>> {
+		let mut files = TORRENT_FILES.get_or_init(|| Mutex::new(HashMap::new())).lock().unwrap();
+		if let Some(bytes) = files.get(info_hash) { return Some(bytes.clone()); }
+		let bytes = std::fs::read(torrent_file_path(info_hash)).ok()?;
+		files.insert(info_hash.to_string(), bytes.clone());
+		Some(bytes)
+	}
+
+	#[derive(serde::Serialize, serde::Deserialize)]
+	struct StoredMetadata {
+		info_hash: String,
+		files: Vec<CachedFile>,
+	}
+
+	fn metadata_path(info_hash: &str) -> Option<PathBuf> {
+		crate::utils::get_path_config_clone().map(|c| c.app_data_dir.join(METADATA_DIR).join(format!("{}.json", info_hash)))
+	}
+
+	// A torrent's file list never changes, so it is written once and read back on any later lookup
+	fn store_metadata(cached: &CachedTorrent) {
+		let info_hash = cached.info_hash.to_lowercase();
+		let Some(path) = metadata_path(&info_hash) else { return };
+		if path.exists() || cached.files.is_empty() { return; }
+		let stored = StoredMetadata { info_hash, files: cached.files.clone() };
+		let result = serde_json::to_vec(&stored).map_err(std::io::Error::other)
+			.and_then(|bytes| path.parent().map_or(Ok(()), std::fs::create_dir_all).and_then(|_| std::fs::write(&path, bytes)));
+		if let Err(e) = result { log_warn!("[torrent] Failed to save metadata {}: {}", path.display(), e); }
+	}
+
+	// Search prefetches store a file list per result, so drop the least recently written past the cap
+	fn prune_metadata() {
+		let Some(dir) = crate::utils::get_path_config_clone().map(|c| c.app_data_dir.join(METADATA_DIR)) else { return };
+		let Ok(entries) = std::fs::read_dir(&dir) else { return };
+		let mut files: Vec<(std::time::SystemTime, PathBuf)> = entries
+			.filter_map(|e| e.ok())
+			.filter(|e| e.path().extension().is_some_and(|x| x == "json"))
+			.filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+			.collect();
+		if files.len() <= MAX_STORED_METADATA { return; }
+		files.sort_by(|a, b| b.0.cmp(&a.0));
+		let stale = files.split_off(MAX_STORED_METADATA);
+		let removed = stale.iter().filter(|(_, path)| std::fs::remove_file(path).is_ok()).count();
+		log_info!("[torrent] Pruned {} stored metadata files from {}", removed, dir.display());
+	}
+
+	fn load_metadata(info_hash: &str) -> Option<Vec<CachedFile>> {
+		let bytes = std::fs::read(metadata_path(info_hash)?).ok()?;
+		match serde_json::from_slice::<StoredMetadata>(&bytes) {
+			Ok(stored) => Some(stored.files),
+			Err(e) => { log_warn!("[torrent] Ignoring unreadable metadata for {}: {}", info_hash, e); None }
+		}
+	}
+
+	// What to hand rqbit for `key`: the stored .torrent when we have one, else the magnet/hash
+	fn torrent_source(key: &str) -> AddTorrent<'_> {
+		match super::info_hash_from_key(key).and_then(|ih| torrent_file(&ih)) {
+			Some(bytes) => AddTorrent::from_bytes(bytes),
+			None => AddTorrent::from_url(key),
+		}
+	}
+
+	// Create the session now so restored downloads resume without waiting for a torrent command
+	pub fn restore_session() {
+		prune_metadata();
+		if let Err(e) = get_api() {
+			log_warn!("[torrent] Torrent session unavailable: {}", e);
+		}
+	}
+
 	fn get_torrent_ids() -> &'static Mutex<HashMap<String, usize>> { TORRENT_IDS.get_or_init(|| Mutex::new(HashMap::new())) }
 
 	fn get_speed_cache() -> &'static Mutex<HashMap<String, HashMap<u32, (u64, Instant)>>> {
@@ -240,6 +620,192 @@ mod rqbit_impl {
 		SAVE_DIR_OVERRIDES.get_or_init(|| Mutex::new(HashMap::new()))
 	}
 
+	fn get_stream_focus() -> &'static Mutex<HashMap<(String, u32), tokio::task::JoinHandle<()>>> {
+		STREAM_FOCUS.get_or_init(|| Mutex::new(HashMap::new()))
+	}
+
+	// Replace the file's reader with one starting at `offset`; the old reader's window is released
+	fn focus_stream(handle: std::sync::Arc<librqbit::ManagedTorrent>, file_index: u32, offset: u64, prefetch_tail: bool) {
+		let key = (handle.info_hash().as_string(), file_index);
+		let task = rt().spawn(read_in_order(handle, file_index, offset, prefetch_tail));
+		if let Some(previous) = get_stream_focus().lock().unwrap().insert(key, task) {
+			previous.abort();
+		}
+	}
+
+	// Note the file playback now streams and stop pulling pieces for the one it left
+	pub fn focus_playback(info_hash: &str, file_index: u32) {
+		let current = (info_hash.to_lowercase(), file_index);
+		let previous = PLAYBACK_FOCUS.lock().unwrap().replace(current.clone());
+		if let Some(previous) = previous.filter(|p| *p != current) {
+			if let Some(task) = get_stream_focus().lock().unwrap().remove(&previous) {
+				task.abort();
+			}
+		}
+	}
+
+	// Aborts the task when dropped, so an aborted head reader takes its tail reader with it
+	struct AbortOnDrop(tokio::task::JoinHandle<()>);
+
+	impl Drop for AbortOnDrop {
+		fn drop(&mut self) { self.0.abort(); }
+	}
+
+	fn get_streamed_runs() -> &'static Mutex<HashMap<(String, u32), Vec<(u64, u64)>>> {
+		STREAMED_RUNS.get_or_init(|| Mutex::new(HashMap::new()))
+	}
+
+	// Record that [start, end) of the file was streamed, merging with the runs already known
+	fn note_streamed(info_hash: &str, file_index: u32, start: u64, end: u64) {
+		let mut all = get_streamed_runs().lock().unwrap();
+		let runs = all.entry((info_hash.to_string(), file_index)).or_default();
+		let (mut start, mut end) = (start, end);
+		runs.retain(|&(s, e)| {
+			if s > end || e < start { return true; }
+			start = start.min(s);
+			end = end.max(e);
+			false
+		});
+		let at = runs.partition_point(|&(s, _)| s < start);
+		runs.insert(at, (start, end));
+	}
+
+	fn drop_stream_focus(info_hash: &str) {
+		get_stream_focus().lock().unwrap().retain(|(ih, _), task| {
+			if ih == info_hash { task.abort(); false } else { true }
+		});
+	}
+
+	async fn read_in_order(handle: std::sync::Arc<librqbit::ManagedTorrent>, file_index: u32, offset: u64, prefetch_tail: bool) {
+		use tokio::io::{AsyncReadExt, AsyncSeekExt};
+		let mut stream = match handle.clone().stream(file_index as usize) {
+			Ok(s) => s,
+			Err(e) => { log_warn!("[torrent] Cannot open stream for file {}: {}", file_index, e); return; }
+		};
+		if offset > 0 && stream.seek(std::io::SeekFrom::Start(offset)).await.is_err() { return; }
+		let info_hash = handle.info_hash().as_string();
+		let mut buf = vec![0u8; FOCUS_READ_CHUNK];
+		let mut read = 0u64;
+		let mut tail_started = !prefetch_tail;
+		let mut tail: Option<AbortOnDrop> = None;
+		loop {
+			if !tail_started && read >= HEAD_BEFORE_TAIL {
+				tail_started = true;
+				tail = Some(AbortOnDrop(rt().spawn(read_tail(handle.clone(), file_index))));
+			}
+			match stream.read(&mut buf).await {
+				Ok(0) => break,
+				Ok(n) => {
+					note_streamed(&info_hash, file_index, offset + read, offset + read + n as u64);
+					read += n as u64;
+				}
+				Err(e) => { log_debug!("[torrent] Stream reader for file {} stopped: {}", file_index, e); break; }
+			}
+		}
+		// Let the tail finish unless this reader is aborted first
+		if let Some(mut tail) = tail { let _ = (&mut tail.0).await; }
+	}
+
+	async fn read_tail(handle: std::sync::Arc<librqbit::ManagedTorrent>, file_index: u32) {
+		use tokio::io::{AsyncReadExt, AsyncSeekExt};
+		let mut stream = match handle.stream(file_index as usize) { Ok(s) => s, Err(_) => return };
+		// Files shorter than the tail window are covered by the head reader
+		if stream.seek(std::io::SeekFrom::End(-(TAIL_PREFETCH as i64))).await.is_err() { return; }
+		let mut buf = vec![0u8; FOCUS_READ_CHUNK];
+		while let Ok(n) = stream.read(&mut buf).await {
+			if n == 0 { break; }
+		}
+	}
+
+	// Run `fut` on the torrent runtime and wait for it from any thread. Unlike rt_block_on this
+	// is safe on tokio worker threads, where BASS may call the reader during stream creation.
+	fn run_on_rt<T: Send + 'static>(fut: impl Future<Output = T> + Send + 'static) -> Option<T> {
+		let (tx, rx) = std::sync::mpsc::channel();
+		rt().spawn(async move { let _ = tx.send(fut.await); });
+		rx.recv().ok()
+	}
+
+	trait AsyncFile: tokio::io::AsyncRead + tokio::io::AsyncSeek + Send {}
+	impl<T: tokio::io::AsyncRead + tokio::io::AsyncSeek + Send> AsyncFile for T {}
+
+	// BASS file reader over a librqbit file stream. The open stream keeps the pieces just ahead
+	// of its position at the front of the queue, so decoding pulls the download along with it.
+	struct RqbitReader {
+		// Moved into each runtime task and handed back with the result
+		stream: Option<std::pin::Pin<Box<dyn AsyncFile>>>,
+		length: u64,
+		scratch: Vec<u8>,
+	}
+
+	impl RqbitReader {
+		fn closed() -> std::io::Error {
+			std::io::Error::other("torrent stream closed")
+		}
+	}
+
+	impl BlockingReader for RqbitReader {
+		fn length(&self) -> u64 { self.length }
+
+		fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+			use tokio::io::AsyncReadExt;
+			let mut stream = self.stream.take().ok_or_else(Self::closed)?;
+			let mut scratch = std::mem::take(&mut self.scratch);
+			scratch.resize(buf.len(), 0);
+			let (stream, scratch, res) = run_on_rt(async move {
+				let res = match tokio::time::timeout(READER_TIMEOUT, stream.read(&mut scratch)).await {
+					Ok(r) => r,
+					Err(_) => Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "timed out waiting for torrent pieces")),
+				};
+				(stream, scratch, res)
+			}).ok_or_else(Self::closed)?;
+			self.stream = Some(stream);
+			let n = res?;
+			buf[..n].copy_from_slice(&scratch[..n]);
+			self.scratch = scratch;
+			Ok(n)
+		}
+
+		fn seek(&mut self, offset: u64) -> bool {
+			use tokio::io::AsyncSeekExt;
+			if offset > self.length { return false; }
+			let Some(mut stream) = self.stream.take() else { return false };
+			match run_on_rt(async move {
+				let ok = stream.seek(std::io::SeekFrom::Start(offset)).await.is_ok();
+				(stream, ok)
+			}) {
+				Some((stream, ok)) => { self.stream = Some(stream); ok }
+				None => false,
+			}
+		}
+	}
+
+	// rqbit reports speeds in MiB/s
+	fn mib_to_bytes(mbps: f64) -> u64 { (mbps * 1024.0 * 1024.0) as u64 }
+
+	fn summarize(info_hash: &str, name: Option<String>, stats: &librqbit::TorrentStats) -> TorrentSummary {
+		let live = stats.live.as_ref();
+		TorrentSummary {
+			info_hash: info_hash.to_lowercase(),
+			name,
+			state: stats.state.to_string(),
+			downloaded: stats.progress_bytes,
+			total: stats.total_bytes,
+			uploaded: stats.uploaded_bytes,
+			down_speed: live.map(|l| mib_to_bytes(l.download_speed.mbps)).unwrap_or(0),
+			up_speed: live.map(|l| mib_to_bytes(l.upload_speed.mbps)).unwrap_or(0),
+			finished: stats.finished,
+		}
+	}
+
+	// Announce URLs carried in a magnet link (`tr=` parameters)
+	fn magnet_trackers(key: &str) -> Vec<String> {
+		let Some(query) = key.strip_prefix("magnet:?") else { return Vec::new() };
+		query.split('&')
+			.filter_map(|kv| kv.strip_prefix("tr="))
+			.filter_map(|v| urlencoding::decode(v).ok().map(|d| d.into_owned()))
+			.collect()
+	}
+
 	fn join_components(components: &[String]) -> PathBuf {
 		let mut pb = PathBuf::new();
 		for c in components {
@@ -257,8 +823,7 @@ mod rqbit_impl {
 
 	impl TorrentEngine for RqbitEngine {
 		fn list_files(&self, magnet_or_infohash: &str) -> Result<Vec<TorrentFileInfo>, String> {
-			let cached = self.get_or_fetch_details(magnet_or_infohash)?;
-			Ok(cached.files
+			Ok(self.file_list(magnet_or_infohash)?
 				.iter()
 				.enumerate()
 				.map(|(i, f)| TorrentFileInfo {
@@ -282,7 +847,7 @@ mod rqbit_impl {
 			save_dir: &PathBuf,
 		) -> Result<(), String> {
 			// Path B: bypass high-level api_add_torrent to capture native handle immediately via Session
-			let api_locked = get_api().lock().map_err(|_| "api poisoned")?;
+			let api_locked = get_api()?.lock().map_err(|_| "api poisoned")?;
 			let session = api_locked.session().clone();
 			drop(api_locked); // release lock early
 			let opts = AddTorrentOptions {
@@ -292,19 +857,22 @@ mod rqbit_impl {
 				overwrite: true,
 				..Default::default()
 			};
-			let add_result = rt_block_on(session.add_torrent(AddTorrent::from_url(magnet_or_infohash), Some(opts)) )
+			let add_result = rt_block_on(session.add_torrent(torrent_source(magnet_or_infohash), Some(opts)))
 				.map_err(|e| format!("session.add_torrent: {e}"))?;
-			let (info_hash_str, id_opt) = match add_result {
-				librqbit::AddTorrentResponse::AlreadyManaged(id, handle) => (handle.info_hash().as_string(), Some(id)),
-				librqbit::AddTorrentResponse::Added(id, handle) => (handle.info_hash().as_string(), Some(id)),
+			let (info_hash_str, id_opt, handle) = match add_result {
+				librqbit::AddTorrentResponse::AlreadyManaged(id, handle) => (handle.info_hash().as_string(), Some(id), handle),
+				librqbit::AddTorrentResponse::Added(id, handle) => (handle.info_hash().as_string(), Some(id), handle),
 				librqbit::AddTorrentResponse::ListOnly(_) => { return Err("unexpected ListOnly response while list_only=false".into()); }
 			};
+			// Head first (then the tail) so playback can start before the rest arrives
+			focus_stream(handle, file_index, 0, true);
 			if let Some(id) = id_opt { if let Ok(mut ids) = get_torrent_ids().lock() { ids.insert(info_hash_str.clone(), id); ids.insert(magnet_or_infohash.to_string(), id); } }
 			// Record override path under both info_hash and original key
 			if let Ok(mut ov) = get_save_dir_overrides().lock() {
 				ov.insert(info_hash_str.clone(), save_dir.clone());
 				ov.insert(magnet_or_infohash.to_string(), save_dir.clone());
 			}
+			remember_torrent(magnet_or_infohash, &info_hash_str, save_dir);
 			if std::env::var("FREELY_TORRENT_DEBUG").is_ok() {
 														log_debug!("[torrent-debug] Added (Path B) info_hash={} override_dir={:?} file_index={}", info_hash_str, save_dir, file_index);
 			}
@@ -312,8 +880,54 @@ mod rqbit_impl {
 			Ok(())
 		}
 
+		fn start_download_files(
+			&self,
+			magnet_or_infohash: &str,
+			file_indices: &[u32],
+			save_dir: &PathBuf,
+		) -> Result<(), String> {
+			let first = *file_indices.first().ok_or("no files selected")?;
+			let api = get_api()?.lock().map_err(|_| "api poisoned")?.clone();
+			let session = api.session().clone();
+			let wanted: Vec<usize> = file_indices.iter().map(|&i| i as usize).collect();
+			let opts = AddTorrentOptions {
+				list_only: false,
+				only_files: Some(wanted.clone()),
+				output_folder: Some(save_dir.to_string_lossy().to_string()),
+				overwrite: true,
+				..Default::default()
+			};
+			let add_result = rt_block_on(session.add_torrent(torrent_source(magnet_or_infohash), Some(opts)))
+				.map_err(|e| format!("session.add_torrent: {e}"))?;
+			let (info_hash_str, id, handle) = match add_result {
+				librqbit::AddTorrentResponse::AlreadyManaged(id, handle) => {
+					// Keep whatever other downloads already selected and add ours
+					let mut selected: HashSet<usize> = self.torrent_details(magnet_or_infohash)
+						.map(|d| d.files.iter().filter(|f| f.included).map(|f| f.index as usize).collect())
+						.unwrap_or_default();
+					selected.extend(wanted.iter().copied());
+					rt_block_on(api.api_torrent_action_update_only_files(librqbit::api::TorrentIdOrHash::Id(id), &selected))
+						.map_err(|e| format!("update only_files: {e}"))?;
+					(handle.info_hash().as_string(), id, handle)
+				}
+				librqbit::AddTorrentResponse::Added(id, handle) => (handle.info_hash().as_string(), id, handle),
+				librqbit::AddTorrentResponse::ListOnly(_) => { return Err("unexpected ListOnly response while list_only=false".into()); }
+			};
+			// Files are played in order, so the first one gets the head start
+			focus_stream(handle, first, 0, false);
+			if let Ok(mut ids) = get_torrent_ids().lock() { ids.insert(info_hash_str.clone(), id); ids.insert(magnet_or_infohash.to_string(), id); }
+			if let Ok(mut ov) = get_save_dir_overrides().lock() {
+				ov.insert(info_hash_str.clone(), save_dir.clone());
+				ov.insert(magnet_or_infohash.to_string(), save_dir.clone());
+			}
+			remember_torrent(magnet_or_infohash, &info_hash_str, save_dir);
+			log_info!("[torrent] Started {} files of {}", file_indices.len(), info_hash_str);
+			self.invalidate_cache_keys(magnet_or_infohash);
+			Ok(())
+		}
+
 		fn pause(&self, magnet_or_infohash: &str) -> Result<(), String> {
-			let api = get_api().lock().map_err(|_| "api poisoned")?.clone();
+			let api = get_api()?.lock().map_err(|_| "api poisoned")?.clone();
 			let idx = self.resolve_torrent_id(&api, magnet_or_infohash)?;
 			rt_block_on(api.api_torrent_action_pause(idx))
 				.map_err(|e| format!("pause failed: {e}"))?;
@@ -321,7 +935,7 @@ mod rqbit_impl {
 		}
 
 		fn resume(&self, magnet_or_infohash: &str) -> Result<(), String> {
-			let api = get_api().lock().map_err(|_| "api poisoned")?.clone();
+			let api = get_api()?.lock().map_err(|_| "api poisoned")?.clone();
 			let idx = self.resolve_torrent_id(&api, magnet_or_infohash)?;
 			rt_block_on(api.api_torrent_action_start(idx))
 				.map_err(|e| format!("resume failed: {e}"))?;
@@ -329,7 +943,7 @@ mod rqbit_impl {
 		}
 
 		fn remove(&self, magnet_or_infohash: &str, remove_data: bool) -> Result<(), String> {
-			let api = get_api().lock().map_err(|_| "api poisoned")?.clone();
+			let api = get_api()?.lock().map_err(|_| "api poisoned")?.clone();
 			let idx = self.resolve_torrent_id(&api, magnet_or_infohash)?;
 			if remove_data {
 				rt_block_on(api.api_torrent_action_delete(idx))
@@ -339,6 +953,12 @@ mod rqbit_impl {
 					.map_err(|e| format!("forget failed: {e}"))?;
 			}
 			// Clean caches (both magnet + infohash aliases)
+			let info_hash = self.extract_infohash(magnet_or_infohash).map(|ih| ih.to_lowercase());
+			if let Some(ih) = info_hash.as_ref() {
+				drop_stream_focus(ih);
+				get_streamed_runs().lock().unwrap().retain(|(h, _), _| h != ih);
+			}
+			forget_torrent(magnet_or_infohash, info_hash);
 			self.invalidate_cache_keys(magnet_or_infohash);
 			Ok(())
 		}
@@ -376,14 +996,20 @@ mod rqbit_impl {
 			// Attempt native stats via Api -> handle lookup using stored torrent id
 			let mut verified_bytes = None;
 			let mut live_speed_bytes_per_s = None;
+			let mut uploaded = 0u64;
+			let mut up_speed = 0u64;
 			if let Ok(ids) = get_torrent_ids().lock() {
 				let key_id = ids.get(magnet_or_infohash).or_else(|| ids.get(&details.info_hash)).copied();
 				if let Some(tid) = key_id {
-					if let Ok(api) = get_api().lock() {
+					if let Some(api) = get_api().ok().and_then(|a| a.lock().ok()) {
 						// Use api_stats_v1 to obtain TorrentStats (public API)
 						if let Ok(stats) = api.api_stats_v1(tid.into()) {
 							if let Some(b) = stats.file_progress.get(file_index as usize) { verified_bytes = Some((*b).min(total)); }
-							if let Some(live) = &stats.live { live_speed_bytes_per_s = Some((live.download_speed.mbps * 1024.0 * 1024.0) as u64); }
+							uploaded = stats.uploaded_bytes;
+							if let Some(live) = &stats.live {
+								live_speed_bytes_per_s = Some(mib_to_bytes(live.download_speed.mbps));
+								up_speed = mib_to_bytes(live.upload_speed.mbps);
+							}
 						}
 					}
 				}
@@ -409,7 +1035,7 @@ mod rqbit_impl {
 				}
 			};
 			if let Some(ls) = live_speed_bytes_per_s { down_speed = ls; }
-			Ok(TorrentProgress { bytes, total, peers, down_speed, verified_bytes: bytes, on_disk_bytes: disk_bytes })
+			Ok(TorrentProgress { bytes, total, peers, down_speed, verified_bytes: bytes, on_disk_bytes: disk_bytes, uploaded, up_speed })
 		}
 
 		fn file_path(
@@ -433,6 +1059,133 @@ mod rqbit_impl {
 			}
 			Ok(p)
 		}
+
+		fn set_download_limit(&self, bytes_per_sec: Option<u64>) -> Result<(), String> {
+			let api_locked = get_api()?.lock().map_err(|_| "api poisoned")?;
+			let session = api_locked.session().clone();
+			drop(api_locked);
+			let bps = bytes_per_sec.and_then(|b| std::num::NonZeroU32::new(b.min(u32::MAX as u64) as u32));
+			session.ratelimits.set_download_bps(bps);
+			*DOWNLOAD_LIMIT.lock().unwrap() = bps;
+			Ok(())
+		}
+
+		fn prioritize(&self, magnet_or_infohash: &str, file_index: u32, offset: u64) -> Result<(), String> {
+			let api = get_api()?.lock().map_err(|_| "api poisoned")?.clone();
+			let idx = self.resolve_torrent_id(&api, magnet_or_infohash)?;
+			let handle = api.session().get(idx).ok_or_else(|| "torrent not active".to_string())?;
+			focus_playback(&handle.info_hash().as_string(), file_index);
+			// The tail was requested when the download started
+			focus_stream(handle, file_index, offset, false);
+			Ok(())
+		}
+
+		fn open_reader(&self, magnet_or_infohash: &str, file_index: u32) -> Result<Box<dyn BlockingReader>, String> {
+			let length = self.get_or_fetch_details(magnet_or_infohash)?
+				.files.get(file_index as usize).map(|f| f.length)
+				.ok_or_else(|| "file index out of range".to_string())?;
+			let api = get_api()?.lock().map_err(|_| "api poisoned")?.clone();
+			let idx = self.resolve_torrent_id(&api, magnet_or_infohash)?;
+			let handle = api.session().get(idx).ok_or_else(|| "torrent not active".to_string())?;
+			let stream = handle.stream(file_index as usize).map_err(|e| format!("open stream: {e}"))?;
+			Ok(Box::new(RqbitReader { stream: Some(Box::pin(stream)), length, scratch: Vec::new() }))
+		}
+
+		fn verified_ranges(&self, magnet_or_infohash: &str, file_index: u32) -> Result<Vec<(u64, u64)>, String> {
+			let api = get_api()?.lock().map_err(|_| "api poisoned")?.clone();
+			let idx = self.resolve_torrent_id(&api, magnet_or_infohash)?;
+			let handle = api.session().get(idx).ok_or_else(|| "torrent not active".to_string())?;
+			let length = handle
+				.with_metadata(|m| m.file_infos.get(file_index as usize).map(|f| f.len))
+				.map_err(|e| format!("torrent metadata: {e}"))?
+				.ok_or_else(|| "file index out of range".to_string())?;
+			// Per-file progress counts verified bytes without saying where they are; only a
+			// complete file tells us every byte
+			if handle.stats().file_progress.get(file_index as usize).is_some_and(|&have| have >= length) {
+				return Ok(vec![(0, length)]);
+			}
+			let key = (handle.info_hash().as_string(), file_index);
+			Ok(get_streamed_runs().lock().unwrap().get(&key).cloned().unwrap_or_default())
+		}
+
+		fn list_torrents(&self) -> Result<Vec<TorrentSummary>, String> {
+			let api = get_api()?.lock().map_err(|_| "api poisoned")?.clone();
+			let mut out = Vec::new();
+			for t in api.api_torrent_list().torrents {
+				let Some(id) = t.id else { continue };
+				let Ok(stats) = api.api_stats_v1(id.into()) else { continue };
+				out.push(summarize(&t.info_hash, t.name, &stats));
+			}
+			Ok(out)
+		}
+
+		fn add_torrent_bytes(&self, bytes: Vec<u8>) -> Result<String, String> {
+			let api = get_api()?.lock().map_err(|_| "api poisoned")?.clone();
+			let opts = AddTorrentOptions { list_only: true, ..Default::default() };
+			let resp = rt_block_on(api.api_add_torrent(AddTorrent::from_bytes(bytes.clone()), Some(opts)))
+				.map_err(|e| format!("invalid torrent file: {e}"))?;
+			let info_hash = resp.details.info_hash.to_lowercase();
+			store_torrent_file(&info_hash, &bytes);
+			// Seed the details cache so list_files answers right away
+			let files = resp.details.files.unwrap_or_default().into_iter()
+				.map(|f| CachedFile { components: f.components, name: f.name, length: f.length as u64 }).collect();
+			let cached = CachedTorrent { info_hash: info_hash.clone(), output_folder: resp.details.output_folder, files, last_update: Instant::now() };
+			store_metadata(&cached);
+			if let Ok(mut cache) = get_details_cache().lock() { cache.insert(info_hash.clone(), cached); }
+			log_info!("[torrent] Registered .torrent file {} ({} bytes)", info_hash, bytes.len());
+			Ok(info_hash)
+		}
+
+		fn torrent_details(&self, magnet_or_infohash: &str) -> Result<TorrentDetails, String> {
+			let api = get_api()?.lock().map_err(|_| "api poisoned")?.clone();
+			let idx = self.resolve_torrent_id(&api, magnet_or_infohash)?;
+			let details = api.api_torrent_details(idx).map_err(|e| format!("torrent details: {e}"))?;
+			let stats = api.api_stats_v1(idx).map_err(|e| format!("torrent stats: {e}"))?;
+			let files = details.files.unwrap_or_default().into_iter().enumerate().map(|(i, f)| TorrentFileDetail {
+				index: i as u32,
+				name: if f.components.is_empty() { f.name } else { join_components(&f.components).to_string_lossy().to_string() },
+				length: f.length as u64,
+				downloaded: stats.file_progress.get(i).copied().unwrap_or(0),
+				included: f.included,
+			}).collect();
+			let live = stats.live.as_ref();
+			let peers = live.map(|l| {
+				let p = &l.snapshot.peer_stats;
+				PeerCounts { live: p.live as u64, connecting: p.connecting as u64, queued: p.queued as u64, seen: p.seen as u64, dead: p.dead as u64 }
+			}).unwrap_or_default();
+			let pieces_have = live.map(|l| l.snapshot.downloaded_and_checked_pieces);
+			// Same estimate rqbit's time_remaining is based on: what's left at the current rate
+			let eta_secs = live.map(|l| mib_to_bytes(l.download_speed.mbps))
+				.filter(|&bps| bps > 0 && !stats.finished)
+				.map(|bps| stats.total_bytes.saturating_sub(stats.progress_bytes) / bps);
+			let error = stats.error.clone();
+			Ok(TorrentDetails {
+				summary: summarize(&details.info_hash, details.name, &stats),
+				output_folder: details.output_folder,
+				files,
+				peers,
+				pieces_have,
+				trackers: magnet_trackers(magnet_or_infohash),
+				eta_secs,
+				error,
+			})
+		}
+
+		fn peer_list(&self, magnet_or_infohash: &str) -> Result<Vec<PeerInfo>, String> {
+			let api = get_api()?.lock().map_err(|_| "api poisoned")?.clone();
+			let idx = self.resolve_torrent_id(&api, magnet_or_infohash)?;
+			let snapshot = api.api_peer_stats(idx, Default::default()).map_err(|e| format!("peer stats: {e}"))?;
+			let mut out: Vec<PeerInfo> = snapshot.peers.into_iter().map(|(addr, p)| PeerInfo {
+				addr,
+				state: p.state.to_string(),
+				fetched_bytes: p.counters.fetched_bytes,
+				pieces: p.counters.downloaded_and_checked_pieces as u64,
+				connections: p.counters.connections as u64,
+				errors: p.counters.errors as u64,
+			}).collect();
+			out.sort_by(|a, b| b.fetched_bytes.cmp(&a.fetched_bytes));
+			Ok(out)
+		}
 	}
 
 	impl RqbitEngine {
@@ -467,11 +1220,24 @@ mod rqbit_impl {
 			}
 			// Fallback: list_only add to discover infohash (network call)
 			let opts = AddTorrentOptions { list_only: true, ..Default::default() };
-			let resp = rt_block_on(api.api_add_torrent(AddTorrent::from_url(magnet_or_infohash), Some(opts)))
+			let resp = rt_block_on(api.api_add_torrent(torrent_source(magnet_or_infohash), Some(opts)))
 				.map_err(|e| format!("rqbit api_add_torrent(list_only): {e}"))?;
 			TorrentIdOrHash::try_from(resp.details.info_hash.as_str()).map_err(|e| format!("bad info hash: {e}"))
 		}
 
+		// File list for `key` without waiting on the DHT when it was resolved before: any in-memory
+		// entry regardless of age, then the copy persisted by info hash, then a list_only fetch
+		fn file_list(&self, key: &str) -> Result<Vec<CachedFile>, String> {
+			let info_hash = super::info_hash_from_key(key);
+			if let Ok(cache) = get_details_cache().lock() {
+				if let Some(cached) = cache.get(key).or_else(|| info_hash.as_ref().and_then(|ih| cache.get(ih))) {
+					return Ok(cached.files.clone());
+				}
+			}
+			if let Some(files) = info_hash.as_deref().and_then(load_metadata) { return Ok(files); }
+			Ok(self.get_or_fetch_details(key)?.files)
+		}
+
 		// Get cached details or fetch via list_only (with minimal locking / await).
 		fn get_or_fetch_details(&self, key: &str) -> Result<CachedTorrent, String> {
 			// 1. Fast path: fresh cache
@@ -489,14 +1255,15 @@ mod rqbit_impl {
 				}
 			}
 			// 2. Need fresh details – fetch outside lock
-			let api = get_api().lock().map_err(|_| "api poisoned")?.clone();
+			let api = get_api()?.lock().map_err(|_| "api poisoned")?.clone();
 			let opts = AddTorrentOptions { list_only: true, ..Default::default() };
-			let resp = rt_block_on(api.api_add_torrent(AddTorrent::from_url(key), Some(opts)))
+			let resp = rt_block_on(api.api_add_torrent(torrent_source(key), Some(opts)))
 				.map_err(|e| format!("rqbit api_add_torrent(list_only): {e}"))?;
 			let details = resp.details;
 			let files_src = details.files.unwrap_or_default();
 			let files: Vec<CachedFile> = files_src.into_iter().map(|f| CachedFile { components: f.components, name: f.name, length: f.length as u64 }).collect();
 			let cached = CachedTorrent { info_hash: details.info_hash.clone(), output_folder: details.output_folder.clone(), files, last_update: Instant::now() };
+			store_metadata(&cached);
 			// 3. Store back into cache
 			if let Ok(mut cache) = get_details_cache().lock() {
 				cache.insert(key.to_string(), cached.clone());
@@ -521,22 +1288,205 @@ mod rqbit_impl {
 	pub fn make_engine() -> Box<dyn TorrentEngine> {
 		Box::new(RqbitEngine::new())
 	}
+
+	#[cfg(test)]
+	mod tests {
+		use super::*;
+		use crate::torrent_settings::TorrentSettings;
+
+		// Empty session root under the temp dir
+		fn session_dir(name: &str) -> PathBuf {
+			let dir = std::env::temp_dir().join(format!("freely-session-{}-{}", name, std::process::id()));
+			let _ = std::fs::remove_dir_all(&dir);
+			std::fs::create_dir_all(&dir).unwrap();
+			dir
+		}
+
+		fn free_port() -> u16 {
+			std::net::TcpListener::bind(("0.0.0.0", 0)).unwrap().local_addr().unwrap().port()
+		}
+
+		// Only the one listen port, so a second session can't fall back to another
+		fn one_port(port: u16) -> TorrentSettings {
+			TorrentSettings { listen_port_min: port, listen_port_max: port, upnp_enabled: false, dht_enabled: false, ..Default::default() }
+		}
+
+		fn start(root: &std::path::Path, settings: &TorrentSettings, proxy: Option<String>) -> Result<(Api, PathBuf), String> {
+			let state_dir = root.join(SESSION_STATE_DIR);
+			Ok((build_session(root.to_path_buf(), session_options(settings, proxy, state_dir.clone()))?, state_dir))
+		}
+
+		#[test]
+		fn rebuild_binds_the_port_the_old_session_held() {
+			let root = session_dir("rebind");
+			let settings = one_port(free_port());
+			let (mut api, _) = start(&root, &settings, None).unwrap();
+			assert!(start(&root, &settings, None).is_err(), "the port is taken while the old session runs");
+
+			replace_session(&mut api, &|| start(&root, &settings, None), || panic!("rollback on a good rebuild")).unwrap();
+			assert_eq!(api.session().tcp_listen_port(), Some(settings.listen_port_min));
+		}
+
+		#[test]
+		fn failed_rebuild_restores_the_previous_settings() {
+			let root = session_dir("rollback");
+			let settings = one_port(free_port());
+			let (mut api, _) = start(&root, &settings, None).unwrap();
+			// librqbit rejects the proxy URL, so only the rolled back settings build
+			let proxy = Mutex::new(Some("not a proxy".to_string()));
+			let create = || start(&root, &settings, proxy.lock().unwrap().clone());
+
+			let result = replace_session(&mut api, &create, || { *proxy.lock().unwrap() = None; Ok(()) });
+			assert!(result.is_err());
+			assert!(proxy.lock().unwrap().is_none());
+			assert_eq!(api.session().tcp_listen_port(), Some(settings.listen_port_min));
+		}
+
+		#[test]
+		fn proxy_change_reaches_the_new_session() {
+			let root = session_dir("proxy");
+			let settings = one_port(free_port());
+			let (mut api, _) = start(&root, &settings, None).unwrap();
+			// Stands in for the SOCKS5 proxy; it only needs to see the connection arrive
+			let proxy = rt_block_on(tokio::net::TcpListener::bind("127.0.0.1:0")).unwrap();
+			let proxy_url = format!("socks5://{}", proxy.local_addr().unwrap());
+
+			replace_session(&mut api, &|| start(&root, &settings, Some(proxy_url.clone())), || Ok(())).unwrap();
+			// The session fetches .torrent URLs with its own HTTP client, which goes through the proxy
+			let add = api.clone();
+			rt().spawn(async move {
+				let _ = add.api_add_torrent(AddTorrent::from_url("http://127.0.0.1:9/album.torrent"), None).await;
+			});
+			let accepted = rt_block_on(async { tokio::time::timeout(std::time::Duration::from_secs(10), proxy.accept()).await });
+			assert!(matches!(accepted, Ok(Ok(_))), "the rebuilt session did not connect through the proxy");
+		}
+	}
 }
 
 #[cfg(not(feature = "torrent-rqbit"))]
 mod rqbit_impl {
 	use super::*;
 	pub fn make_engine() -> Box<dyn TorrentEngine> { Box::new(super::NoopEngine) }
+	pub fn restore_session() {}
+	pub fn rebuild_session(_rollback: impl FnOnce() -> Result<(), String>) -> Result<(), String> { Ok(()) }
+	pub fn focus_playback(_info_hash: &str, _file_index: u32) {}
 }
 
 static ENGINE: OnceCell<Box<dyn TorrentEngine>> = OnceCell::new();
 
+// Offline fixtures instead of the swarm (see torrent_fake.rs)
+#[cfg(any(test, feature = "fake-torrents"))]
+fn fake_fixtures() -> Option<PathBuf> { crate::torrent_fake::fixture_dir() }
+#[cfg(not(any(test, feature = "fake-torrents")))]
+fn fake_fixtures() -> Option<PathBuf> { None }
+
 pub fn get_engine() -> &'static dyn TorrentEngine {
-	ENGINE.get_or_init(|| rqbit_impl::make_engine());
+	ENGINE.get_or_init(|| {
+		#[cfg(any(test, feature = "fake-torrents"))]
+		if let Some(dir) = fake_fixtures() { return Box::new(crate::torrent_fake::FakeEngine::new(dir)); }
+		rqbit_impl::make_engine()
+	});
 	// SAFETY: OnceCell initialized above; unwrap ok
 	ENGINE.get().map(|b| &**b).unwrap()
 }
 
+// Where the engine keeps torrent data; a temporary folder until the app's paths are set up
+pub fn torrents_dir() -> PathBuf {
+	match crate::utils::get_path_config_clone() {
+		Some(config) => config.torrents_dir,
+		None => {
+			log_warn!("[torrent] Path config not ready; using a temporary torrents folder");
+			std::env::temp_dir().join("freely-torrents")
+		}
+	}
+}
+
+// Bring up the torrent session in the background at startup: torrents from the previous run
+// are restored from the torrents dir and unfinished ones continue downloading.
+pub fn restore_session() {
+	if fake_fixtures().is_some() { return; }
+	std::thread::spawn(rqbit_impl::restore_session);
+}
+
+// Lowercase hex info hash of a magnet link or bare hash, without asking the engine
+pub fn info_hash_from_key(key: &str) -> Option<String> {
+	let raw = match key.find("xt=urn:btih:") {
+		Some(pos) => { let after = &key[pos + 12..]; &after[..after.find('&').unwrap_or(after.len())] }
+		None => key,
+	};
+	let raw = raw.trim();
+	if raw.len() == 40 && raw.chars().all(|c| c.is_ascii_hexdigit()) { Some(raw.to_lowercase()) } else { None }
+}
+
+// Recreate the engine session after network settings change (blocking; call off the async runtime).
+// `rollback` restores the previous settings when the session can't be built from the new ones.
+pub fn rebuild_session(rollback: impl FnOnce() -> Result<(), String>) -> Result<(), String> {
+	if fake_fixtures().is_some() { return Ok(()); }
+	rqbit_impl::rebuild_session(rollback)
+}
+
+// Playback moved to this file: the reader pulling pieces for the previous track is dropped
+pub fn focus_playback(key: &str, file_index: u32) {
+	if let Some(info_hash) = info_hash_from_key(key) { rqbit_impl::focus_playback(&info_hash, file_index); }
+}
+
+// Re-point streaming priority at a playback position given as a fraction of the file.
+// Blocks on the engine; call from a blocking context. Returns the byte offset used.
+pub fn prioritize_position(key: &str, file_index: u32, fraction: f64) -> Result<u64, String> {
+	let engine = get_engine();
+	let total = engine.progress(key, file_index)?.total;
+	let offset = (total as f64 * fraction.clamp(0.0, 1.0)) as u64;
+	engine.prioritize(key, file_index, offset)?;
+	Ok(offset)
+}
+
+// Keys with a metadata prefetch in flight, so repeated searches don't stack lookups
+static PREFETCHING: OnceCell<Mutex<HashSet<String>>> = OnceCell::new();
+// Prefetches allowed in flight at once; keys beyond this are skipped, not queued. A slot is held
+// until its lookup returns, so lookups stuck on unreachable swarms can never pile up past this.
+const MAX_PREFETCHES: usize = 4;
+
+// Resolve file lists in the background so opening a result later lists files right away; the
+// engine persists what it resolves and answers known torrents without a lookup. Returns how many
+// prefetches were started.
+pub fn prefetch_metadata(keys: Vec<String>) -> usize {
+	let pending = PREFETCHING.get_or_init(|| Mutex::new(HashSet::new()));
+	let mut started = 0;
+	for key in keys {
+		{
+			let mut pending = pending.lock().unwrap();
+			if pending.len() >= MAX_PREFETCHES { break; }
+			if !pending.insert(key.clone()) { continue; }
+		}
+		started += 1;
+		std::thread::spawn(move || {
+			let label = info_hash_from_key(&key).unwrap_or_else(|| key.clone());
+			let started_at = std::time::Instant::now();
+			match get_engine().list_files(&key) {
+				Ok(files) => log_debug!("[torrent] Prefetched metadata for {} ({} files)", label, files.len()),
+				Err(e) => log_debug!("[torrent] Metadata prefetch failed for {} after {}s: {}", label, started_at.elapsed().as_secs(), e),
+			}
+			if let Some(p) = PREFETCHING.get() { p.lock().unwrap().remove(&key); }
+		});
+	}
+	started
+}
+
+// Start (or join) the download of one file and open a blocking reader over it for BASS.
+// The engine blocks on its own runtime, so the work runs on the blocking pool.
+pub async fn open_stream_reader(key: String, file_index: u32) -> Result<Box<dyn BlockingReader>, String> {
+	let save_dir = crate::utils::get_path_config_clone()
+		.map(|c| c.torrents_dir)
+		.ok_or("PathConfig not initialized")?;
+	tokio::task::spawn_blocking(move || {
+		let engine = get_engine();
+		engine.start_download(&key, file_index, &save_dir)?;
+		engine.open_reader(&key, file_index)
+	})
+	.await
+	.map_err(|e| format!("join error: {e}"))?
+}
+
 // Thin wrappers used by command layer to avoid re-exporting internal tracking details.
 #[derive(Serialize)]
 pub struct ProgressData {
@@ -546,6 +1496,8 @@ pub struct ProgressData {
 	pub total: u64,
 	pub peers: u32,
 	pub down_speed: u64,
+	pub uploaded: u64,
+	pub up_speed: u64,
 }
 
 pub fn engine_progress(key: &str, index: u32) -> Result<ProgressData, String> {
@@ -557,6 +1509,8 @@ pub fn engine_progress(key: &str, index: u32) -> Result<ProgressData, String> {
 			total: p.total,
 			peers: p.peers,
 			down_speed: p.down_speed,
+			uploaded: p.uploaded,
+			up_speed: p.up_speed,
 		}),
 		Err(e) => Err(e),
 	}
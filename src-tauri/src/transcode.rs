@@ -0,0 +1,223 @@
+use crate::cache_policy::{get_cache_policy, TranscodeFormat};
+use once_cell::sync::{Lazy, OnceCell};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tokio::process::Command;
+use tokio::sync::Semaphore;
+
+// Only one encoder runs at a time so background transcodes don't compete with playback
+static TRANSCODE_SLOTS: Lazy<Semaphore> = Lazy::new(|| Semaphore::new(1));
+// Cache keys currently queued or running, to avoid duplicate jobs
+static PENDING: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+// Codecs worth transcoding; lossy sources are left untouched
+const LOSSLESS_CODECS: &[&str] = &["flac", "wav", "aiff", "alac", "ape", "wavpack", "dsd"];
+
+/// Return ffmpeg executable from resources/bin, falling back to the one on PATH
+pub(crate) fn resolve_ffmpeg_exe() -> PathBuf {
+    let name = if cfg!(target_os = "windows") { "ffmpeg.exe" } else { "ffmpeg" };
+    if let Some(config) = crate::utils::get_path_config_clone() {
+        let p = config.resource_dir.join("bin").join(name);
+        if p.exists() {
+            return p;
+        }
+    }
+    PathBuf::from(name)
+}
+
+// Probed once per run; bundled builds ship ffmpeg, dev setups may not have it on PATH
+static FFMPEG_AVAILABLE: OnceCell<bool> = OnceCell::new();
+
+/// Whether an ffmpeg executable could be found and started
+pub fn ffmpeg_available() -> bool {
+    *FFMPEG_AVAILABLE.get_or_init(|| {
+        let mut cmd = std::process::Command::new(resolve_ffmpeg_exe());
+        cmd.arg("-version")
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null());
+        #[cfg(target_os = "windows")]
+        {
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x08000000;
+            cmd.creation_flags(CREATE_NO_WINDOW);
+        }
+        let found = cmd.status().map(|s| s.success()).unwrap_or(false);
+        if !found {
+            println!("[transcode] ffmpeg not found; transcoding, cover thumbnails and export tagging are disabled");
+        }
+        found
+    })
+}
+
+/// Identify lossless containers from their magic bytes
+fn sniff_lossless_codec(path: &Path) -> Option<&'static str> {
+    use std::io::Read;
+    let mut head = [0u8; 64];
+    let n = std::fs::File::open(path).and_then(|mut f| f.read(&mut head)).ok()?;
+    let head = &head[..n];
+    if head.len() < 12 {
+        return None;
+    }
+    match &head[..4] {
+        b"fLaC" => Some("flac"),
+        b"RIFF" | b"RF64" if &head[8..12] == b"WAVE" => Some("wav"),
+        b"FORM" if &head[8..12] == b"AIFF" || &head[8..12] == b"AIFC" => Some("aiff"),
+        b"MAC " => Some("ape"),
+        b"wvpk" => Some("wavpack"),
+        b"DSD " | b"FRM8" => Some("dsd"),
+        b"OggS" if head.len() >= 33 && &head[28..33] == b"\x7fFLAC" => Some("flac"),
+        _ => None,
+    }
+}
+
+/// Queue a background transcode for a freshly cached entry if the cache policy asks for it
+pub fn schedule(cache_key: String) {
+    if !get_cache_policy().transcode_enabled || !ffmpeg_available() {
+        return;
+    }
+    {
+        let mut pending = PENDING.lock().unwrap();
+        if !pending.insert(cache_key.clone()) {
+            return;
+        }
+    }
+    tauri::async_runtime::spawn(async move {
+        let _permit = TRANSCODE_SLOTS.acquire().await;
+        if let Err(e) = transcode_entry(&cache_key).await {
+            println!("[transcode] Skipped {}: {}", cache_key, e);
+        }
+        PENDING.lock().unwrap().remove(&cache_key);
+    });
+}
+
+/// Queue every cached entry that is still in its original lossless form
+pub fn sweep() {
+    for key in crate::cache::list_cache_keys() {
+        schedule(key);
+    }
+}
+
+async fn transcode_entry(cache_key: &str) -> Result<(), String> {
+    let policy = get_cache_policy();
+    if !policy.transcode_enabled {
+        return Err("transcoding disabled".into());
+    }
+    let (entry, src) = crate::cache::get_entry_snapshot(cache_key).ok_or("entry no longer cached")?;
+    if entry.transcoded_from.is_some() {
+        return Err("already transcoded".into());
+    }
+    let source_codec = match entry.codec.as_deref().map(|c| c.to_lowercase()) {
+        Some(c) if LOSSLESS_CODECS.contains(&c.as_str()) => c,
+        _ => sniff_lossless_codec(&src).ok_or("not a lossless source")?.to_string(),
+    };
+
+    let format = policy.transcode_format;
+    let file_name = format!(
+        "{}.{}",
+        src.file_name().and_then(|n| n.to_str()).ok_or("bad cache file name")?,
+        format.extension()
+    );
+    let dest = src.with_file_name(&file_name);
+    let tmp = src.with_file_name(format!("{}.part", file_name));
+    let bitrate = format!("{}k", policy.transcode_bitrate_kbps);
+
+    let mut cmd = Command::new(resolve_ffmpeg_exe());
+    cmd.args(["-hide_banner", "-nostdin", "-v", "error", "-y", "-i"])
+        .arg(&src)
+        .args(["-map", "0:a:0", "-map_metadata", "0", "-vn"]);
+    // Opus always runs at 48 kHz; AAC keeps the source rate unless it exceeds 48 kHz
+    let sample_rate = match format {
+        TranscodeFormat::Opus => {
+            cmd.args(["-c:a", "libopus", "-b:a", &bitrate, "-f", "ogg"]);
+            Some(48000)
+        }
+        TranscodeFormat::Aac => {
+            cmd.args(["-c:a", "aac", "-b:a", &bitrate]);
+            let rate = match entry.sample_rate {
+                Some(r) if r <= 48000 => Some(r),
+                _ => {
+                    cmd.args(["-ar", "48000"]);
+                    Some(48000)
+                }
+            };
+            cmd.args(["-movflags", "+faststart", "-f", "ipod"]);
+            rate
+        }
+    };
+    cmd.arg(&tmp);
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    println!(
+        "[transcode] {} ({}) -> {} @ {}",
+        cache_key,
+        source_codec,
+        format.codec_name(),
+        bitrate
+    );
+    let output = cmd
+        .output()
+        .await
+        .map_err(|e| format!("failed to run ffmpeg: {}", e))?;
+    if !output.status.success() {
+        let _ = tokio::fs::remove_file(&tmp).await;
+        return Err(format!(
+            "ffmpeg exit {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    tokio::fs::rename(&tmp, &dest)
+        .await
+        .map_err(|e| format!("failed to finalize transcode: {}", e))?;
+    let new_size = tokio::fs::metadata(&dest)
+        .await
+        .map_err(|e| format!("failed to stat transcode: {}", e))?
+        .len();
+
+    // Swap the index over to the new file; playback keeps using the original until this succeeds
+    let obsolete = match crate::cache::apply_transcoded_file(
+        cache_key,
+        file_name,
+        new_size,
+        format.codec_name().to_string(),
+        sample_rate,
+        source_codec,
+    ) {
+        Ok(obsolete) => obsolete,
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&dest).await;
+            return Err(e);
+        }
+    };
+    println!(
+        "[transcode] Finished {}: {} -> {} bytes",
+        cache_key, entry.file_size, new_size
+    );
+    if let Some(original) = obsolete {
+        remove_when_released(original);
+    }
+    Ok(())
+}
+
+/// Delete a replaced original, retrying while a player may still hold it open
+pub fn remove_when_released(path: PathBuf) {
+    tauri::async_runtime::spawn(async move {
+        let mut delay_ms = 1000u64;
+        for _ in 0..8 {
+            match tokio::fs::remove_file(&path).await {
+                Ok(_) => return,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+                Err(_) => {}
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
+            delay_ms = (delay_ms * 2).min(60_000);
+        }
+        println!("[transcode] Could not remove replaced original: {:?}", path);
+    });
+}
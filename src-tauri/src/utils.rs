@@ -206,7 +206,7 @@ pub async fn resolve_youtube_source_with_format(
     Ok(ResolvedAudioSource { url, format })
 }
 
-fn get_path_config_clone() -> Option<crate::paths::PathConfig> {
+pub(crate) fn get_path_config_clone() -> Option<crate::paths::PathConfig> {
     if let Ok(lock) = crate::APP_HANDLE.lock() {
         if let Some(app) = lock.as_ref() {
             let state = app.state::<crate::paths::PathConfig>();
@@ -21,6 +21,7 @@ fn resolve_ytdlp_exe(resource_dir: &PathBuf) -> Option<PathBuf> {
 pub fn execute_ytdlp(args: &[&str], config: &PathConfig) -> Result<String, String> {
     let exe = resolve_ytdlp_exe(&config.resource_dir)
         .ok_or("yt-dlp/youtube-dl not found in resources/bin")?;
+    let proxy_args = crate::proxy::ytdlp_args();
 
     #[cfg(target_os = "windows")]
     {
@@ -28,6 +29,7 @@ pub fn execute_ytdlp(args: &[&str], config: &PathConfig) -> Result<String, Strin
         const CREATE_NO_WINDOW: u32 = 0x08000000;
         
         let output = std::process::Command::new(&exe)
+            .args(&proxy_args)
             .args(args)
             .creation_flags(CREATE_NO_WINDOW)
             .output()
@@ -48,6 +50,7 @@ pub fn execute_ytdlp(args: &[&str], config: &PathConfig) -> Result<String, Strin
     #[cfg(not(target_os = "windows"))]
     {
         let output = std::process::Command::new(&exe)
+            .args(&proxy_args)
             .args(args)
             .output()
             .map_err(|e| format!("Failed to run yt-dlp: {}", e))?;
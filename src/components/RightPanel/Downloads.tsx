@@ -331,47 +331,45 @@ export const DownloadsTab = React.memo<{ collapsed?: boolean }>(({ collapsed })
 
   // Listen to default CustomEvents for downloads actions emitted by ContextMenu
   useEffect(() => {
-    const onPause = (e: Event) => {
+    // Backend jobs are keyed with the torrent file index, so prefer the known item over parsing the id
+    const resolve = (e: Event) => {
       const id = (e as CustomEvent).detail?.id as string | undefined;
-      if (!id) return;
+      if (!id) return null;
+      const item = list.find(d => d.id === id);
+      if (item && item.trackId && item.sourceType && item.sourceHash) {
+        return { trackId: item.trackId, sourceType: item.sourceType, sourceHash: item.sourceHash, fileIndex: item.fileIndex };
+      }
       // Parse id => trackId, sourceType, sourceHash
       const [trackId, sourceType, ...rest] = id.split('_');
       const sourceHash = rest.join('_');
-      if (!trackId || !sourceType || !sourceHash) return;
+      if (!trackId || !sourceType || !sourceHash) return null;
+      return { trackId, sourceType, sourceHash, fileIndex: undefined };
+    };
+    const onPause = (e: Event) => {
+      const args = resolve(e);
+      if (!args) return;
       // Call backend command
       (async () => {
         try {
-          const res: any = await runTauriCommand('downloads_pause', {
-            trackId, sourceType, sourceHash
-          });
+          const res: any = await runTauriCommand('downloads_pause', args);
         } catch {}
       })();
     };
     const onResume = (e: Event) => {
-      const id = (e as CustomEvent).detail?.id as string | undefined;
-      if (!id) return;
-      const [trackId, sourceType, ...rest] = id.split('_');
-      const sourceHash = rest.join('_');
-      if (!trackId || !sourceType || !sourceHash) return;
+      const args = resolve(e);
+      if (!args) return;
       (async () => {
         try {
-          const res: any = await runTauriCommand('downloads_resume', {
-            trackId, sourceType, sourceHash
-          });
+          const res: any = await runTauriCommand('downloads_resume', args);
         } catch {}
       })();
     };
     const onRemove = (e: Event) => {
-      const id = (e as CustomEvent).detail?.id as string | undefined;
-      if (!id) return;
-      const [trackId, sourceType, ...rest] = id.split('_');
-      const sourceHash = rest.join('_');
-      if (!trackId || !sourceType || !sourceHash) return;
+      const args = resolve(e);
+      if (!args) return;
       (async () => {
         try {
-          const res: any = await runTauriCommand('downloads_remove', {
-            trackId, sourceType, sourceHash
-          });
+          const res: any = await runTauriCommand('downloads_remove', args);
         } catch {}
         // Prune immediately for UX
         try { prune({ olderThanMs: 0 }); } catch {}
@@ -385,7 +383,7 @@ export const DownloadsTab = React.memo<{ collapsed?: boolean }>(({ collapsed })
       window.removeEventListener('freely:downloads:resume', onResume as EventListener);
       window.removeEventListener('freely:downloads:remove', onRemove as EventListener);
     };
-  }, [prune]);
+  }, [list, prune]);
 
   const panelClass = useMemo(() => {
     return `rt-panel ${collapsed ? 'collapsed' : ''}`;